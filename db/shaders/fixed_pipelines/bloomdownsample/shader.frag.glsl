@@ -0,0 +1,16 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform texture2D t_Source;
+layout(set = 0, binding = 1) uniform sampler s_Source;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(t_Source, s_Source), 0));
+    vec3 color = texture(sampler2D(t_Source, s_Source), v_TexCoord + texel * vec2(-1.0, -1.0)).rgb;
+    color += texture(sampler2D(t_Source, s_Source), v_TexCoord + texel * vec2(1.0, -1.0)).rgb;
+    color += texture(sampler2D(t_Source, s_Source), v_TexCoord + texel * vec2(-1.0, 1.0)).rgb;
+    color += texture(sampler2D(t_Source, s_Source), v_TexCoord + texel * vec2(1.0, 1.0)).rgb;
+    o_Color = vec4(color * 0.25, 1.0);
+}
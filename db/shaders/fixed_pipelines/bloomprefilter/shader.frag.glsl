@@ -0,0 +1,22 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform texture2D t_Source;
+layout(set = 0, binding = 1) uniform sampler s_Source;
+
+// x: threshold, y: soft knee
+layout(PUSH_CONSTANTS) uniform pushConstants {
+    vec4 u_Params;
+};
+
+void main() {
+    vec3 color = texture(sampler2D(t_Source, s_Source), v_TexCoord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float knee = u_Params.x * u_Params.y;
+    float soft = clamp(brightness - u_Params.x + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 0.00001);
+    float contribution = max(soft, brightness - u_Params.x) / max(brightness, 0.00001);
+    o_Color = vec4(color * contribution, 1.0);
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform texture2D t_Source;
+layout(set = 0, binding = 1) uniform sampler s_Source;
+
+vec3 tap(vec2 offset) {
+    return texture(sampler2D(t_Source, s_Source), v_TexCoord + offset).rgb;
+}
+
+void main() {
+    // 3x3 tent filter:
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(t_Source, s_Source), 0));
+    vec3 color = tap(vec2(0.0)) * 4.0;
+    color += (tap(vec2(-texel.x, 0.0)) + tap(vec2(texel.x, 0.0))) * 2.0;
+    color += (tap(vec2(0.0, -texel.y)) + tap(vec2(0.0, texel.y))) * 2.0;
+    color += tap(-texel) + tap(texel) + tap(vec2(-texel.x, texel.y)) + tap(vec2(texel.x, -texel.y));
+    o_Color = vec4(color / 16.0, 1.0);
+}
@@ -0,0 +1,83 @@
+// Split sum BRDF integration lookup table, x: n dot v, y: roughness.
+
+const PI: f32 = 3.14159265359;
+const SAMPLE_COUNT: u32 = 512u;
+
+[[builtin(vertex_index)]]
+var<in> in_vertex_index: u32;
+[[builtin(position)]]
+var<out> out_position: vec4<f32>;
+[[location(0)]]
+var<out> out_tex_coord: vec2<f32>;
+
+[[stage(vertex)]]
+fn vs_main() {
+    // fullscreen triangle:
+    var index: i32 = i32(in_vertex_index);
+    out_tex_coord = vec2<f32>(f32((index << 1) & 2), f32(index & 2));
+    out_position = vec4<f32>(out_tex_coord.x * 2.0 - 1.0, 1.0 - out_tex_coord.y * 2.0, 0.0, 1.0);
+}
+
+[[location(0)]]
+var<in> in_tex_coord: vec2<f32>;
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+fn radical_inverse(index: u32) -> f32 {
+    // reverses the bits, masks 0x55555555, 0x33333333, 0x0f0f0f0f and 0x00ff00ff
+    // in decimal as there are no hex literals:
+    var bits: u32 = (index << 16u) | (index >> 16u);
+    bits = ((bits & 1431655765u) << 1u) | ((bits & 2863311530u) >> 1u);
+    bits = ((bits & 858993459u) << 2u) | ((bits & 3435973836u) >> 2u);
+    bits = ((bits & 252645135u) << 4u) | ((bits & 4042322160u) >> 4u);
+    bits = ((bits & 16711935u) << 8u) | ((bits & 4278255360u) >> 8u);
+    return f32(bits) * 2.3283064365386963e-10;
+}
+
+fn importance_sample_ggx(xi: vec2<f32>, roughness: f32) -> vec3<f32> {
+    // around the normal (0, 0, 1) of the lookup:
+    var a: f32 = roughness * roughness;
+    var phi: f32 = 2.0 * PI * xi.x;
+    var cos_theta: f32 = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    var sin_theta: f32 = sqrt(1.0 - cos_theta * cos_theta);
+    return vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+}
+
+fn geometry_schlick_ggx(n_dot_v: f32, roughness: f32) -> f32 {
+    // image based lighting uses k = a / 2:
+    var k: f32 = roughness * roughness * 0.5;
+    return n_dot_v / (n_dot_v * (1.0 - k) + k);
+}
+
+[[stage(fragment)]]
+fn fs_main() {
+    var n_dot_v: f32 = max(in_tex_coord.x, 0.0001);
+    var roughness: f32 = in_tex_coord.y;
+    var v: vec3<f32> = vec3<f32>(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+
+    var scale: f32 = 0.0;
+    var bias: f32 = 0.0;
+    var i: u32 = 0u;
+    loop {
+        if (i >= SAMPLE_COUNT) {
+            break;
+        }
+        var xi: vec2<f32> = vec2<f32>(f32(i) / f32(SAMPLE_COUNT), radical_inverse(i));
+        var h: vec3<f32> = importance_sample_ggx(xi, roughness);
+        var l: vec3<f32> = normalize(2.0 * dot(v, h) * h - v);
+        var n_dot_l: f32 = max(l.z, 0.0);
+        var n_dot_h: f32 = max(h.z, 0.0);
+        var v_dot_h: f32 = max(dot(v, h), 0.0);
+        if (n_dot_l > 0.0) {
+            var g: f32 = geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+            var g_vis: f32 = g * v_dot_h / (n_dot_h * n_dot_v);
+            var fc: f32 = pow(1.0 - v_dot_h, 5.0);
+            scale = scale + (1.0 - fc) * g_vis;
+            bias = bias + fc * g_vis;
+        }
+        continuing {
+            i = i + 1u;
+        }
+    }
+    out_color = vec4<f32>(scale / f32(SAMPLE_COUNT), bias / f32(SAMPLE_COUNT), 0.0, 1.0);
+}
@@ -0,0 +1,36 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform texture2D t_Source;
+layout(set = 0, binding = 1) uniform sampler s_Source;
+
+// x: cube face
+layout(PUSH_CONSTANTS) uniform pushConstants {
+    vec4 u_Params;
+};
+
+const float PI = 3.14159265359;
+
+vec3 cube_direction(int face, vec2 uv) {
+    vec2 p = uv * 2.0 - 1.0;
+    if (face == 0) {
+        return vec3(1.0, -p.y, -p.x);
+    } else if (face == 1) {
+        return vec3(-1.0, -p.y, p.x);
+    } else if (face == 2) {
+        return vec3(p.x, 1.0, p.y);
+    } else if (face == 3) {
+        return vec3(p.x, -1.0, -p.y);
+    } else if (face == 4) {
+        return vec3(p.x, -p.y, 1.0);
+    }
+    return vec3(-p.x, -p.y, -1.0);
+}
+
+void main() {
+    vec3 dir = normalize(cube_direction(int(u_Params.x), v_TexCoord));
+    vec2 uv = vec2(atan(dir.z, dir.x) / (2.0 * PI) + 0.5, acos(clamp(dir.y, -1.0, 1.0)) / PI);
+    o_Color = vec4(textureLod(sampler2D(t_Source, s_Source), uv, 0.0).rgb, 1.0);
+}
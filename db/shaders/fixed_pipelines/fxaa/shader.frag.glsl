@@ -0,0 +1,56 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform texture2D t_Source;
+layout(set = 0, binding = 1) uniform sampler s_Source;
+
+// x: subpixel blend, y: edge threshold, z: minimum edge threshold
+layout(PUSH_CONSTANTS) uniform pushConstants {
+    vec4 u_Params;
+};
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+
+float luma(vec3 color) {
+    // the source is linear, approximate perceptual luma:
+    return sqrt(dot(color, vec3(0.299, 0.587, 0.114)));
+}
+
+vec3 fetch(vec2 uv) {
+    return texture(sampler2D(t_Source, s_Source), uv).rgb;
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(t_Source, s_Source), 0));
+    vec3 center = fetch(v_TexCoord);
+    float lumaM = luma(center);
+    float lumaNW = luma(fetch(v_TexCoord + vec2(-1.0, -1.0) * texel));
+    float lumaNE = luma(fetch(v_TexCoord + vec2(1.0, -1.0) * texel));
+    float lumaSW = luma(fetch(v_TexCoord + vec2(-1.0, 1.0) * texel));
+    float lumaSE = luma(fetch(v_TexCoord + vec2(1.0, 1.0) * texel));
+
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+    if (lumaMax - lumaMin < max(u_Params.z, lumaMax * u_Params.y)) {
+        o_Color = vec4(center, 1.0);
+        return;
+    }
+
+    vec2 dir = vec2(
+        -((lumaNW + lumaNE) - (lumaSW + lumaSE)),
+        (lumaNW + lumaSW) - (lumaNE + lumaSE)
+    );
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 rgbA = 0.5 * (fetch(v_TexCoord + dir * (1.0 / 3.0 - 0.5)) + fetch(v_TexCoord + dir * (2.0 / 3.0 - 0.5)));
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (fetch(v_TexCoord - dir * 0.5) + fetch(v_TexCoord + dir * 0.5));
+    float lumaB = luma(rgbB);
+    vec3 result = (lumaB < lumaMin || lumaB > lumaMax) ? rgbA : rgbB;
+    o_Color = vec4(mix(center, result, u_Params.x), 1.0);
+}
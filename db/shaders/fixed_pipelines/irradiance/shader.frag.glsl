@@ -0,0 +1,51 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform textureCube t_Environment;
+layout(set = 0, binding = 1) uniform sampler s_Environment;
+
+// x: cube face
+layout(PUSH_CONSTANTS) uniform pushConstants {
+    vec4 u_Params;
+};
+
+const float PI = 3.14159265359;
+const float SAMPLE_DELTA = 0.05;
+
+vec3 cube_direction(int face, vec2 uv) {
+    vec2 p = uv * 2.0 - 1.0;
+    if (face == 0) {
+        return vec3(1.0, -p.y, -p.x);
+    } else if (face == 1) {
+        return vec3(-1.0, -p.y, p.x);
+    } else if (face == 2) {
+        return vec3(p.x, 1.0, p.y);
+    } else if (face == 3) {
+        return vec3(p.x, -1.0, -p.y);
+    } else if (face == 4) {
+        return vec3(p.x, -p.y, 1.0);
+    }
+    return vec3(-p.x, -p.y, -1.0);
+}
+
+void main() {
+    vec3 normal = normalize(cube_direction(int(u_Params.x), v_TexCoord));
+    vec3 up = abs(normal.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 right = normalize(cross(up, normal));
+    up = cross(normal, right);
+
+    // integrate the cosine weighted hemisphere:
+    vec3 irradiance = vec3(0.0);
+    float samples = 0.0;
+    for (float phi = 0.0; phi < 2.0 * PI; phi += SAMPLE_DELTA) {
+        for (float theta = 0.0; theta < 0.5 * PI; theta += SAMPLE_DELTA) {
+            vec3 tangent = vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            vec3 dir = tangent.x * right + tangent.y * up + tangent.z * normal;
+            irradiance += textureLod(samplerCube(t_Environment, s_Environment), dir, 2.0).rgb * cos(theta) * sin(theta);
+            samples += 1.0;
+        }
+    }
+    o_Color = vec4(PI * irradiance / samples, 1.0);
+}
@@ -1,10 +1,52 @@
 #version 450
+#extension GL_GOOGLE_include_directive : require
 
 layout(location = 0) in vec2 v_TexCoord;
+layout(location = 1) in vec3 v_Normal;
+layout(location = 2) in vec3 v_WorldPos;
 layout(location = 0) out vec4 o_Target;
+
 layout(set = 0, binding = 0) uniform texture2D t_Color;
 layout(set = 0, binding = 1) uniform sampler s_Color;
 
+#include "include/lighting.glsl"
+
+layout(PUSH_CONSTANTS) uniform pushContants {
+    layout(offset = 128) vec4 u_MaterialParams; // x: receive shadows, y: alpha cutoff, z: alpha to coverage, w: alpha blended
+};
+
+#include "include/alpha.glsl"
+
 void main() {
-    o_Target = texture(sampler2D(t_Color, s_Color), v_TexCoord);
-}
\ No newline at end of file
+    vec4 albedo = texture(sampler2D(t_Color, s_Color), v_TexCoord);
+    vec3 normal = normalize(v_Normal);
+
+    vec3 light = u_AmbientColor.rgb;
+
+    vec3 to_sun = -u_DirectionalDirection.xyz;
+    float n_dot_l = max(dot(normal, to_sun), 0.0);
+    light += u_DirectionalColor.rgb * n_dot_l * directional_shadow(v_WorldPos, n_dot_l, u_MaterialParams.x >= 0.5);
+
+    int spot_count = int(u_AmbientColor.w);
+    for (int i = 0; i < spot_count; ++i) {
+        SpotLight spot = u_SpotLights[i];
+        vec3 to_light = spot.position_range.xyz - v_WorldPos;
+        float distance = length(to_light);
+        to_light /= distance;
+        float cos_angle = dot(-to_light, spot.direction_cutoff.xyz);
+        if (cos_angle < spot.direction_cutoff.w || distance > spot.position_range.w) {
+            continue;
+        }
+        float n_dot_s = max(dot(normal, to_light), 0.0);
+        float attenuation = 1.0 - distance / spot.position_range.w;
+        float cone = smoothstep(spot.direction_cutoff.w, mix(spot.direction_cutoff.w, 1.0, 0.2), cos_angle);
+        float shadow = 1.0;
+        if (spot.color_layer.w >= 0.0 && u_MaterialParams.x >= 0.5) {
+            shadow = sample_shadow(int(spot.color_layer.w), v_WorldPos, u_ShadowParams.z);
+        }
+        light += spot.color_layer.rgb * n_dot_s * attenuation * attenuation * cone * shadow;
+    }
+
+    float alpha = resolve_alpha(albedo.a, u_MaterialParams.y, u_MaterialParams.z > 0.5, u_MaterialParams.w > 0.5);
+    o_Target = vec4(albedo.rgb * light, alpha);
+}
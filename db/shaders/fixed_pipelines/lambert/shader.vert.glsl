@@ -2,14 +2,20 @@
 
 layout(location = 0) in vec4 a_Pos;
 layout(location = 1) in vec2 a_TexCoord;
+layout(location = 2) in vec3 a_Normal;
 layout(location = 0) out vec2 v_TexCoord;
+layout(location = 1) out vec3 v_Normal;
+layout(location = 2) out vec3 v_WorldPos;
 
-layout(push_constant) uniform pushContants {
+layout(PUSH_CONSTANTS) uniform pushContants {
     mat4 u_World;
     mat4 u_ViewProjection;
 };
 
 void main() {
+    vec4 world_pos = u_World * a_Pos;
     v_TexCoord = a_TexCoord;
-    gl_Position = (u_ViewProjection * u_World) * a_Pos;
-}
\ No newline at end of file
+    v_Normal = mat3(u_World) * a_Normal;
+    v_WorldPos = world_pos.xyz;
+    gl_Position = u_ViewProjection * world_pos;
+}
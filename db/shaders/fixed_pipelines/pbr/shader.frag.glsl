@@ -0,0 +1,133 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 1) in vec3 v_Normal;
+layout(location = 2) in vec3 v_WorldPos;
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D t_Albedo;
+layout(set = 0, binding = 1) uniform texture2D t_Normal;
+layout(set = 0, binding = 2) uniform texture2D t_MetallicRoughness;
+layout(set = 0, binding = 3) uniform texture2D t_Occlusion;
+layout(set = 0, binding = 4) uniform texture2D t_Emissive;
+layout(set = 0, binding = 5) uniform sampler s_Material;
+
+#include "include/lighting.glsl"
+
+layout(set = 2, binding = 0) uniform Environment {
+    vec4 u_EnvironmentParams; // x: intensity, y: max specular mip
+};
+layout(set = 2, binding = 1) uniform textureCube t_Irradiance;
+layout(set = 2, binding = 2) uniform textureCube t_Specular;
+layout(set = 2, binding = 3) uniform texture2D t_BrdfLut;
+layout(set = 2, binding = 4) uniform sampler s_Environment;
+
+layout(PUSH_CONSTANTS) uniform pushContants {
+    layout(offset = 128) vec4 u_BaseColor;
+    vec4 u_MaterialParams; // x: metallic, y: roughness, z: occlusion strength, w: normal scale
+    vec4 u_Emissive; // w: receive shadows
+    vec4 u_Alpha; // x: alpha cutoff, y: alpha to coverage, z: alpha blended
+};
+
+#include "include/alpha.glsl"
+
+const float PI = 3.14159265359;
+
+// normal mapping without vertex tangents, from screen space derivatives:
+mat3 cotangent_frame(vec3 normal, vec3 pos, vec2 uv) {
+    vec3 dp1 = dFdx(pos);
+    vec3 dp2 = dFdy(pos);
+    vec2 duv1 = dFdx(uv);
+    vec2 duv2 = dFdy(uv);
+    vec3 dp2perp = cross(dp2, normal);
+    vec3 dp1perp = cross(normal, dp1);
+    vec3 tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    float inv_max = inversesqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 0.0000001));
+    return mat3(tangent * inv_max, bitangent * inv_max, normal);
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+vec3 fresnel_schlick_roughness(float cos_theta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+// cook-torrance with ggx distribution and smith-schlick geometry:
+vec3 brdf(vec3 n, vec3 v, vec3 l, vec3 albedo, float metallic, float roughness, vec3 f0) {
+    vec3 h = normalize(v + l);
+    float n_dot_l = max(dot(n, l), 0.0);
+    float n_dot_v = max(dot(n, v), 0.0001);
+    float n_dot_h = max(dot(n, h), 0.0);
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denom = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    float d = a2 / (PI * denom * denom);
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float g = (n_dot_v / (n_dot_v * (1.0 - k) + k)) * (n_dot_l / (n_dot_l * (1.0 - k) + k));
+    vec3 f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+    vec3 specular = d * g * f / max(4.0 * n_dot_v * n_dot_l, 0.0001);
+    vec3 kd = (1.0 - f) * (1.0 - metallic);
+    return (kd * albedo / PI + specular) * n_dot_l;
+}
+
+
+void main() {
+    vec4 albedo = texture(sampler2D(t_Albedo, s_Material), v_TexCoord) * u_BaseColor;
+    float alpha = resolve_alpha(albedo.a, u_Alpha.x, u_Alpha.y > 0.5, u_Alpha.z > 0.5);
+    vec4 metallic_roughness = texture(sampler2D(t_MetallicRoughness, s_Material), v_TexCoord);
+    float metallic = clamp(metallic_roughness.b * u_MaterialParams.x, 0.0, 1.0);
+    float roughness = clamp(metallic_roughness.g * u_MaterialParams.y, 0.04, 1.0);
+    float occlusion = mix(1.0, texture(sampler2D(t_Occlusion, s_Material), v_TexCoord).r, u_MaterialParams.z);
+    vec3 emissive = texture(sampler2D(t_Emissive, s_Material), v_TexCoord).rgb * u_Emissive.rgb;
+
+    vec3 tangent_normal = texture(sampler2D(t_Normal, s_Material), v_TexCoord).xyz * 2.0 - 1.0;
+    tangent_normal.xy *= u_MaterialParams.w;
+    vec3 geometry_normal = normalize(v_Normal);
+    vec3 normal = normalize(cotangent_frame(geometry_normal, v_WorldPos, v_TexCoord) * tangent_normal);
+
+    vec3 view = normalize(u_CameraPosition.xyz - v_WorldPos);
+    vec3 f0 = mix(vec3(0.04), albedo.rgb, metallic);
+
+    // direct lighting:
+    vec3 to_sun = -u_DirectionalDirection.xyz;
+    float n_dot_l = max(dot(geometry_normal, to_sun), 0.0);
+    vec3 light = u_DirectionalColor.rgb * brdf(normal, view, to_sun, albedo.rgb, metallic, roughness, f0)
+        * directional_shadow(v_WorldPos, n_dot_l, u_Emissive.w >= 0.5);
+
+    int spot_count = int(u_AmbientColor.w);
+    for (int i = 0; i < spot_count; ++i) {
+        SpotLight spot = u_SpotLights[i];
+        vec3 to_light = spot.position_range.xyz - v_WorldPos;
+        float distance = length(to_light);
+        to_light /= distance;
+        float cos_angle = dot(-to_light, spot.direction_cutoff.xyz);
+        if (cos_angle < spot.direction_cutoff.w || distance > spot.position_range.w) {
+            continue;
+        }
+        float attenuation = 1.0 - distance / spot.position_range.w;
+        float cone = smoothstep(spot.direction_cutoff.w, mix(spot.direction_cutoff.w, 1.0, 0.2), cos_angle);
+        float shadow = 1.0;
+        if (spot.color_layer.w >= 0.0 && u_Emissive.w >= 0.5) {
+            shadow = sample_shadow(int(spot.color_layer.w), v_WorldPos, u_ShadowParams.z);
+        }
+        light += spot.color_layer.rgb * brdf(normal, view, to_light, albedo.rgb, metallic, roughness, f0)
+            * attenuation * attenuation * cone * shadow;
+    }
+
+    // image based lighting:
+    float n_dot_v = max(dot(normal, view), 0.0001);
+    vec3 f = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    vec3 kd = (1.0 - f) * (1.0 - metallic);
+    vec3 irradiance = texture(samplerCube(t_Irradiance, s_Environment), normal).rgb;
+    vec3 reflected = reflect(-view, normal);
+    vec3 prefiltered = textureLod(samplerCube(t_Specular, s_Environment), reflected, roughness * u_EnvironmentParams.y).rgb;
+    vec2 env_brdf = texture(sampler2D(t_BrdfLut, s_Environment), vec2(n_dot_v, roughness)).rg;
+    vec3 ambient = (kd * irradiance * albedo.rgb + prefiltered * (f * env_brdf.x + env_brdf.y))
+        * occlusion * u_EnvironmentParams.x;
+
+    o_Target = vec4(light + ambient + emissive, alpha);
+}
@@ -0,0 +1,92 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform textureCube t_Environment;
+layout(set = 0, binding = 1) uniform sampler s_Environment;
+
+// x: cube face, y: roughness, z: environment resolution
+layout(PUSH_CONSTANTS) uniform pushConstants {
+    vec4 u_Params;
+};
+
+const float PI = 3.14159265359;
+const uint SAMPLE_COUNT = 256u;
+
+vec3 cube_direction(int face, vec2 uv) {
+    vec2 p = uv * 2.0 - 1.0;
+    if (face == 0) {
+        return vec3(1.0, -p.y, -p.x);
+    } else if (face == 1) {
+        return vec3(-1.0, -p.y, p.x);
+    } else if (face == 2) {
+        return vec3(p.x, 1.0, p.y);
+    } else if (face == 3) {
+        return vec3(p.x, -1.0, -p.y);
+    } else if (face == 4) {
+        return vec3(p.x, -p.y, 1.0);
+    }
+    return vec3(-p.x, -p.y, -1.0);
+}
+
+float radical_inverse(uint bits) {
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return float(bits) * 2.3283064365386963e-10;
+}
+
+vec2 hammersley(uint i, uint count) {
+    return vec2(float(i) / float(count), radical_inverse(i));
+}
+
+vec3 importance_sample_ggx(vec2 xi, vec3 normal, float roughness) {
+    float a = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    vec3 h = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + normal * h.z);
+}
+
+float distribution_ggx(float n_dot_h, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+void main() {
+    vec3 normal = normalize(cube_direction(int(u_Params.x), v_TexCoord));
+    float roughness = u_Params.y;
+    if (roughness <= 0.0) {
+        o_Color = vec4(textureLod(samplerCube(t_Environment, s_Environment), normal, 0.0).rgb, 1.0);
+        return;
+    }
+
+    // assume view = reflection = normal:
+    float texel_solid_angle = 4.0 * PI / (6.0 * u_Params.z * u_Params.z);
+    vec3 color = vec3(0.0);
+    float weight = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; ++i) {
+        vec3 h = importance_sample_ggx(hammersley(i, SAMPLE_COUNT), normal, roughness);
+        vec3 l = normalize(2.0 * dot(normal, h) * h - normal);
+        float n_dot_l = dot(normal, l);
+        if (n_dot_l > 0.0) {
+            // pick the mip matching the solid angle of the sample to avoid fireflies:
+            float n_dot_h = max(dot(normal, h), 0.0);
+            float pdf = distribution_ggx(n_dot_h, roughness) * 0.25 + 0.0001;
+            float sample_solid_angle = 1.0 / (float(SAMPLE_COUNT) * pdf + 0.0001);
+            float lod = 0.5 * log2(sample_solid_angle / texel_solid_angle);
+            color += textureLod(samplerCube(t_Environment, s_Environment), l, max(lod, 0.0)).rgb * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+    o_Color = vec4(color / max(weight, 0.0001), 1.0);
+}
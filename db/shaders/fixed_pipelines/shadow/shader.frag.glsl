@@ -0,0 +1,22 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+#ifdef ALPHA_TEST
+layout(location = 0) in vec2 v_TexCoord;
+
+layout(set = 0, binding = 0) uniform texture2D t_Color;
+layout(set = 0, binding = 1) uniform sampler s_Color;
+
+layout(PUSH_CONSTANTS) uniform pushContants {
+    layout(offset = 128) vec4 u_MaterialParams; // x: alpha cutoff, y: alpha factor
+};
+
+#include "include/alpha.glsl"
+#endif
+
+void main() {
+#ifdef ALPHA_TEST
+    float alpha = texture(sampler2D(t_Color, s_Color), v_TexCoord).a * u_MaterialParams.y;
+    resolve_alpha(alpha, u_MaterialParams.x, false, false);
+#endif
+}
@@ -0,0 +1,61 @@
+#version 450
+
+layout(location = 0) in vec2 v_TexCoord;
+layout(location = 0) out vec4 o_Color;
+
+layout(set = 0, binding = 0) uniform texture2D t_Hdr;
+layout(set = 0, binding = 1) uniform texture2D t_Bloom;
+layout(set = 0, binding = 2) uniform sampler s_Linear;
+
+layout(set = 1, binding = 0) uniform texture2D t_Lut;
+layout(set = 1, binding = 1) uniform sampler s_Lut;
+
+layout(PUSH_CONSTANTS) uniform pushConstants {
+    // x: exposure, y: 1 / gamma, z: bloom intensity, w: tonemapper
+    vec4 u_Params;
+    // x: color grading intensity
+    vec4 u_Grading;
+};
+
+const float LUT_SIZE = 16.0;
+
+vec3 reinhard(vec3 color) {
+    return color / (color + vec3(1.0));
+}
+
+// Narkowicz ACES filmic fit:
+vec3 aces(vec3 color) {
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 grade(vec3 color) {
+    vec3 encoded = pow(clamp(color, 0.0, 1.0), vec3(1.0 / 2.2));
+    float slice = encoded.b * (LUT_SIZE - 1.0);
+    float slice0 = floor(slice);
+    float slice1 = min(slice0 + 1.0, LUT_SIZE - 1.0);
+    vec2 uv = vec2(
+        (encoded.r * (LUT_SIZE - 1.0) + 0.5) / (LUT_SIZE * LUT_SIZE),
+        (encoded.g * (LUT_SIZE - 1.0) + 0.5) / LUT_SIZE
+    );
+    vec3 a = texture(sampler2D(t_Lut, s_Lut), uv + vec2(slice0 / LUT_SIZE, 0.0)).rgb;
+    vec3 b = texture(sampler2D(t_Lut, s_Lut), uv + vec2(slice1 / LUT_SIZE, 0.0)).rgb;
+    return pow(mix(a, b, slice - slice0), vec3(2.2));
+}
+
+void main() {
+    vec3 color = texture(sampler2D(t_Hdr, s_Linear), v_TexCoord).rgb;
+    color += texture(sampler2D(t_Bloom, s_Linear), v_TexCoord).rgb * u_Params.z;
+    color *= u_Params.x;
+
+    int tonemapper = int(u_Params.w);
+    if (tonemapper == 1) {
+        color = reinhard(color);
+    } else if (tonemapper == 2) {
+        color = aces(color);
+    } else {
+        color = clamp(color, 0.0, 1.0);
+    }
+
+    color = mix(color, grade(color), u_Grading.x);
+    o_Color = vec4(pow(color, vec3(u_Params.y)), 1.0);
+}
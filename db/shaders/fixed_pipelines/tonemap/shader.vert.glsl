@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) out vec2 v_TexCoord;
+
+void main() {
+    // fullscreen triangle:
+    v_TexCoord = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(v_TexCoord.x * 2.0 - 1.0, 1.0 - v_TexCoord.y * 2.0, 0.0, 1.0);
+}
@@ -0,0 +1,21 @@
+// Alpha of the surface pipelines. The cutoff is only tested in the ALPHA_TEST variants,
+// so opaque surfaces never discard and keep early depth testing.
+
+float resolve_alpha(float alpha, float cutoff, bool to_coverage, bool blended) {
+    if (blended) {
+        return alpha;
+    }
+#ifdef ALPHA_TEST
+    if (cutoff <= 0.0) {
+        return 1.0;
+    }
+    if (to_coverage) {
+        // sharpen the alpha around the cutoff so coverage only fades over about one pixel:
+        return clamp((alpha - cutoff) / max(fwidth(alpha), 0.0001) + 0.5, 0.0, 1.0);
+    }
+    if (alpha < cutoff) {
+        discard;
+    }
+#endif
+    return 1.0;
+}
@@ -0,0 +1,60 @@
+// Scene lighting bind group (set 1) and shadow sampling of the surface pipelines.
+
+#define MAX_CASCADES 4
+#define MAX_SPOT_LIGHTS 4
+#define MAX_SHADOW_LAYERS (MAX_CASCADES + MAX_SPOT_LIGHTS)
+
+struct SpotLight {
+    vec4 position_range;
+    vec4 direction_cutoff;
+    vec4 color_layer;
+};
+
+layout(set = 1, binding = 0) uniform Lighting {
+    mat4 u_ShadowMatrices[MAX_SHADOW_LAYERS];
+    vec4 u_CascadeSplits;
+    vec4 u_CameraPosition;
+    vec4 u_CameraForward;
+    vec4 u_DirectionalDirection; // w: casts shadows
+    vec4 u_DirectionalColor; // w: cascade count
+    vec4 u_AmbientColor; // w: spot light count
+    vec4 u_ShadowParams; // x: texel size, y: pcf radius, z: depth bias
+    SpotLight u_SpotLights[MAX_SPOT_LIGHTS];
+};
+layout(set = 1, binding = 1) uniform texture2DArray t_ShadowMap;
+layout(set = 1, binding = 2) uniform samplerShadow s_ShadowMap;
+
+float sample_shadow(int layer, vec3 world_pos, float bias) {
+    vec4 light_pos = u_ShadowMatrices[layer] * vec4(world_pos, 1.0);
+    vec3 proj = light_pos.xyz / light_pos.w;
+    vec2 uv = proj.xy * vec2(0.5, -0.5) + 0.5;
+    if (proj.z > 1.0 || any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        return 1.0;
+    }
+    int radius = int(u_ShadowParams.y);
+    float texel = u_ShadowParams.x;
+    float sum = 0.0;
+    for (int x = -radius; x <= radius; ++x) {
+        for (int y = -radius; y <= radius; ++y) {
+            vec4 coord = vec4(uv + vec2(x, y) * texel, float(layer), proj.z - bias);
+            sum += texture(sampler2DArrayShadow(t_ShadowMap, s_ShadowMap), coord);
+        }
+    }
+    float taps = float((2 * radius + 1) * (2 * radius + 1));
+    return sum / taps;
+}
+
+float directional_shadow(vec3 world_pos, float n_dot_l, bool receive_shadows) {
+    if (u_DirectionalDirection.w < 0.5 || !receive_shadows) {
+        return 1.0;
+    }
+    float depth = dot(world_pos - u_CameraPosition.xyz, u_CameraForward.xyz);
+    int cascade_count = int(u_DirectionalColor.w);
+    for (int i = 0; i < cascade_count; ++i) {
+        if (depth < u_CascadeSplits[i]) {
+            float bias = u_ShadowParams.z * (1.0 + float(i)) * (1.0 - n_dot_l + 0.1);
+            return sample_shadow(i, world_pos, bias);
+        }
+    }
+    return 1.0;
+}
@@ -3,6 +3,7 @@
 mod components;
 mod config;
 mod core;
+mod editor;
 mod engine;
 mod logger;
 mod panic_hook;
@@ -16,6 +17,16 @@ mod systems;
 use crate::engine::Engine;
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--compile-shaders") {
+        compile_shaders();
+        return;
+    }
+    if std::env::args().any(|arg| arg == "--list-adapters") {
+        let config = crate::config::CoreConfig::load();
+        crate::core::graphics::boot::print_adapter_report(&config.graphics_config);
+        return;
+    }
+
     {
         let mut engine = Engine::initialize();
         engine.run();
@@ -23,3 +34,23 @@ fn main() {
     }
     log::info!("System offline!");
 }
+
+/// Precompiles all shaders into the shader cache, for builds without the runtime shader compiler.
+fn compile_shaders() {
+    use crate::core::graphics::{pipelines, shader_compiler};
+
+    let _ = logger::create();
+    if !cfg!(feature = "runtime-shader-compiler") {
+        log::error!("Compiling shaders requires the \"runtime-shader-compiler\" feature!");
+        return;
+    }
+    if let Err(error) = shader_compiler::clear_cache() {
+        log::warn!("Failed to clear the shader cache: {}", error);
+    }
+    // the optimization level is part of the cache key:
+    let config = crate::config::CoreConfig::load();
+    pipelines::precompile_all(&mut shader_compiler::ShaderCompiler::new(
+        config.graphics_config.shader_optimization,
+    ));
+    log::info!("Shaders are compiled to: {:?}", shader_compiler::CACHE_DIR);
+}
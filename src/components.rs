@@ -1,5 +1,6 @@
 use super::resources::{material::Material, mesh::Mesh};
 use cgmath::*;
+use legion::Entity;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -22,35 +23,172 @@ impl Transform {
 pub struct MeshRenderer {
     pub mesh: Arc<Mesh>,
     pub material: Arc<Material>,
+    pub cast_shadows: bool,
+    pub receive_shadows: bool,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Spot { range: f32, angle: Deg<f32> },
+}
+
+/// Light source, emitting along the negative z axis of its transform.
+#[derive(Clone)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    pub cast_shadows: bool,
+}
+
+impl Light {
+    #[inline]
+    pub fn direction(&self, transform: &Transform) -> Vector3<f32> {
+        transform
+            .rotation
+            .rotate_vector(-Vector3::unit_z())
+            .normalize()
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            kind: LightKind::Directional,
+            color: Vector3::from_value(1.0),
+            intensity: 1.0,
+            cast_shadows: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    Perspective {
+        fov: Deg<f32>,
+    },
+    /// Orthographic projection showing `height` world units vertically.
+    Orthographic {
+        height: f32,
+    },
+}
+
+impl Projection {
+    /// OpenGL style projection matrix, without the depth correction.
+    pub fn matrix(&self, aspect_ratio: f32, near_clip: f32, far_clip: f32) -> Matrix4<f32> {
+        match *self {
+            Projection::Perspective { fov } => perspective(fov, aspect_ratio, near_clip, far_clip),
+            Projection::Orthographic { height } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * aspect_ratio;
+                ortho(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near_clip,
+                    far_clip,
+                )
+            }
+        }
+    }
+}
+
+/// Camera looking along `forward` from the position of its transform.
+/// The first camera in the world is rendered, controllers move it around.
 #[derive(Clone)]
 pub struct Camera {
-    pub fov: Deg<f32>,
+    pub projection: Projection,
     pub near_clip: f32,
     pub far_clip: f32,
-    pub clamp_y: Deg<f32>,
-    pub smoothness: f32,
-    pub speed: f32,
-    pub prev: Vector2<f32>,
-    pub angles: Vector2<f32>,
-    pub smooth_angles: Vector2<f32>,
     pub forward: Vector3<f32>,
 }
 
 impl Default for Camera {
     fn default() -> Self {
         Self {
-            fov: Deg(75.0),
+            projection: Projection::Perspective { fov: Deg(75.0) },
             near_clip: 0.1,
             far_clip: 100.0,
-            clamp_y: Deg(40.0),
-            smoothness: 1.5,
-            speed: 0.01,
-            prev: Vector2::zero(),
+            forward: Vector3::unit_z(),
+        }
+    }
+}
+
+/// Free flying camera, looks around with the look axes and moves with the move axes.
+#[derive(Clone)]
+pub struct FlyController {
+    /// Multiplier of the look axes.
+    pub look_sensitivity: f32,
+    /// Rate at which the view catches up with the input, per second. Zero disables smoothing.
+    pub smoothing: f32,
+    pub clamp_y: Deg<f32>,
+    /// Movement speed in units per second.
+    pub speed: f32,
+    /// Speed multiplier while the sprint action is held.
+    pub sprint_multiplier: f32,
+    /// Speed along the move_up axis in units per second.
+    pub vertical_speed: f32,
+    /// Yaw and pitch in radians the view is moving towards.
+    pub target_angles: Vector2<f32>,
+    pub angles: Vector2<f32>,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self {
+            look_sensitivity: 1.0,
+            smoothing: 25.0,
+            clamp_y: Deg(60.0),
+            speed: 3.0,
+            sprint_multiplier: 3.0,
+            vertical_speed: 2.0,
+            target_angles: Vector2::zero(),
             angles: Vector2::zero(),
-            smooth_angles: Vector2::zero(),
-            forward: Vector3::zero(),
         }
     }
 }
+
+/// Orbits around a target point while the look action is held, zooms with the zoom axis
+/// and pans the target with the move axes.
+#[derive(Clone)]
+pub struct OrbitController {
+    pub target: Vector3<f32>,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub look_sensitivity: f32,
+    /// Fraction of the distance zoomed per zoom step.
+    pub zoom_speed: f32,
+    /// Pan speed in units per second.
+    pub pan_speed: f32,
+    pub clamp_y: Deg<f32>,
+    /// Yaw and pitch in radians.
+    pub angles: Vector2<f32>,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            target: Vector3::zero(),
+            distance: 5.0,
+            min_distance: 0.5,
+            max_distance: 50.0,
+            look_sensitivity: 1.0,
+            zoom_speed: 0.1,
+            pan_speed: 3.0,
+            clamp_y: Deg(85.0),
+            angles: Vector2::new(0.0, -0.4),
+        }
+    }
+}
+
+/// Follows another entity, keeping an offset in its local space and looking at it.
+#[derive(Clone)]
+pub struct FollowController {
+    pub target: Entity,
+    pub offset: Vector3<f32>,
+    /// Rate at which the camera catches up with the target, per second. Zero snaps to it.
+    pub stiffness: f32,
+}
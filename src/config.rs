@@ -1,12 +1,69 @@
 use log::{info, warn};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, BTreeSet};
 use std::default::Default;
+use std::env;
+use std::fmt::Debug;
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 pub const CONFIG_DIR: &str = "config";
 
-#[derive(Serialize, Deserialize)]
+/// Environment variable with an additional config directory, applied on top of the others.
+pub const CONFIG_DIR_ENV_VAR: &str = "KESTD_CONFIG_DIR";
+
+/// Prefix of the environment variables overriding single values. It is followed by the
+/// config file name and the field path in upper case, like "KESTD_DISPLAY_VSYNC=true" or
+/// "KESTD_GRAPHICS_SHADOWS_RESOLUTION=1024". Values are parsed as YAML.
+pub const ENV_OVERRIDE_PREFIX: &str = "KESTD_";
+
+/// Name of the system and user config directories.
+const APP_DIR_NAME: &str = "kestd-ronin";
+
+/// Interval in which the config directories are checked for edited files.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Version of the config file layout, bumped whenever a migration is added to `migrate`.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Range checks of the config values.
+/// Out of range values are reset to their default and reported, so a typo never prevents booting.
+pub trait Validate {
+    fn validate(&mut self, file_name: &str);
+}
+
+fn check_range<T: PartialOrd + Debug + Copy>(
+    file_name: &str,
+    field: &str,
+    value: &mut T,
+    range: RangeInclusive<T>,
+    default: T,
+) {
+    if !range.contains(value) {
+        warn!(
+            "Invalid value in \"{}\": {} = {:?} is out of range {:?}! Using default: {:?}",
+            file_name, field, value, range, default
+        );
+        *value = default;
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigVersion {
+    pub version: u32,
+}
+
+impl ConfigVersion {
+    pub const FILE_NAME: &'static str = "version.ini";
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub product_name: String,
     pub product_company: String,
@@ -39,7 +96,28 @@ impl Default for AppConfig {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+impl Validate for AppConfig {
+    fn validate(&mut self, file_name: &str) {
+        let defaults = Self::default();
+        check_range(
+            file_name,
+            "default_resource_cache_capacity",
+            &mut self.default_resource_cache_capacity,
+            1..=65536,
+            defaults.default_resource_cache_capacity,
+        );
+        check_range(
+            file_name,
+            "service_routine_minute_interval",
+            &mut self.service_routine_minute_interval,
+            1..=u8::MAX,
+            defaults.service_routine_minute_interval,
+        );
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MemoryConfig {
     pub default_string_pool_size: usize,
     pub default_memory_pool_size: usize,
@@ -58,13 +136,34 @@ impl Default for MemoryConfig {
     }
 }
 
+impl Validate for MemoryConfig {
+    fn validate(&mut self, file_name: &str) {
+        let defaults = Self::default();
+        check_range(
+            file_name,
+            "default_string_pool_size",
+            &mut self.default_string_pool_size,
+            1..=1 << 30,
+            defaults.default_string_pool_size,
+        );
+        check_range(
+            file_name,
+            "default_memory_pool_size",
+            &mut self.default_memory_pool_size,
+            1..=1 << 30,
+            defaults.default_memory_pool_size,
+        );
+    }
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum WindowMode {
     FullScreen,
     Windowed,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DisplayConfig {
     pub window_mode: WindowMode,
     pub vsync: bool,
@@ -89,6 +188,40 @@ impl Default for DisplayConfig {
     }
 }
 
+impl Validate for DisplayConfig {
+    fn validate(&mut self, file_name: &str) {
+        let defaults = Self::default();
+        check_range(
+            file_name,
+            "resolution.0",
+            &mut self.resolution.0,
+            800..=16384,
+            defaults.resolution.0,
+        );
+        check_range(
+            file_name,
+            "resolution.1",
+            &mut self.resolution.1,
+            600..=16384,
+            defaults.resolution.1,
+        );
+        check_range(
+            file_name,
+            "gamma_offset",
+            &mut self.gamma_offset,
+            0.1..=10.0,
+            defaults.gamma_offset,
+        );
+        if self.fps_limit == Some(0) {
+            warn!(
+                "Invalid value in \"{}\": fps_limit = 0! Disabling the fps limit...",
+                file_name
+            );
+            self.fps_limit = None;
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum MsaaMode {
     Off = 1,
@@ -107,10 +240,140 @@ pub enum GraphicsApi {
     WebGpu,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Kind of GPU adapter, mirroring the wgpu device types.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum AdapterType {
+    Discrete,
+    Integrated,
+    Virtual,
+    Cpu,
+    Other,
+}
+
+/// Which of the adapters of a backend is used.
+/// Indices and names only match the adapters of the configured backend, indices count them
+/// as printed first by `--list-adapters`.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum AdapterSelection {
+    /// The adapter wgpu prefers for the power preference.
+    Auto,
+    Index(usize),
+    /// The first adapter with the case insensitive substring in its name.
+    Name(String),
+    DeviceType(AdapterType),
+}
+
+/// Depth buffer convention of the scene pass.
+/// Reverse-Z maps the near plane to 1 and puts the far plane of perspective cameras
+/// at infinity, which spreads the float precision evenly and avoids z-fighting at distance.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum DepthMode {
+    Standard,
+    ReverseZ,
+}
+
+/// Optimization level of the SPIR-V compiled from the shader sources.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum ShaderOptimization {
+    Zero,
+    Size,
+    Performance,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowConfig {
+    pub enabled: bool,
+    pub resolution: u32,
+    pub cascade_count: u8,
+    pub distance: f32,
+    pub split_lambda: f32,
+    pub pcf_radius: u8,
+    pub depth_bias: f32,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            resolution: 2048,
+            cascade_count: 4,
+            distance: 50.0,
+            split_lambda: 0.75,
+            pcf_radius: 1,
+            depth_bias: 0.0015,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Tonemapper {
+    Clamp = 0,
+    Reinhard = 1,
+    Aces = 2,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessingConfig {
+    pub enabled: bool,
+    pub exposure: f32,
+    pub tonemapper: Tonemapper,
+    pub bloom: bool,
+    pub bloom_threshold: f32,
+    pub bloom_knee: f32,
+    pub bloom_intensity: f32,
+    pub bloom_passes: u8,
+    pub fxaa: bool,
+    pub color_grading_lut: Option<String>,
+    pub color_grading_intensity: f32,
+}
+
+impl Default for PostProcessingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            exposure: 1.0,
+            tonemapper: Tonemapper::Aces,
+            bloom: true,
+            bloom_threshold: 1.0,
+            bloom_knee: 0.5,
+            bloom_intensity: 0.05,
+            bloom_passes: 5,
+            fxaa: false,
+            color_grading_lut: None,
+            color_grading_intensity: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvironmentConfig {
+    pub map: Option<String>,
+    pub intensity: f32,
+    pub resolution: u32,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            map: None,
+            intensity: 1.0,
+            resolution: 256,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GraphicsConfig {
     pub msaa_mode: MsaaMode,
+    pub depth_mode: DepthMode,
     pub backend_api: GraphicsApi,
+    pub adapter: AdapterSelection,
+    /// Only software adapters (e.g. llvmpipe or WARP) are used, for machines without a GPU.
+    pub force_fallback_adapter: bool,
     pub max_bind_groups: u32,
     pub max_dynamic_uniform_buffers_per_pipeline_layout: u32,
     pub max_dynamic_storage_buffers_per_pipeline_layout: u32,
@@ -121,6 +384,10 @@ pub struct GraphicsConfig {
     pub max_uniform_buffers_per_shader_stage: u32,
     pub max_uniform_buffer_binding_size: u32,
     pub max_push_constant_pool_byte_size: u32,
+    pub shader_optimization: ShaderOptimization,
+    pub shadows: ShadowConfig,
+    pub post_processing: PostProcessingConfig,
+    pub environment: EnvironmentConfig,
 }
 
 impl GraphicsConfig {
@@ -131,7 +398,10 @@ impl Default for GraphicsConfig {
     fn default() -> Self {
         Self {
             msaa_mode: MsaaMode::X8,
+            depth_mode: DepthMode::Standard,
             backend_api: GraphicsApi::Vulkan,
+            adapter: AdapterSelection::Auto,
+            force_fallback_adapter: false,
             max_bind_groups: 4,
             max_dynamic_uniform_buffers_per_pipeline_layout: 8,
             max_dynamic_storage_buffers_per_pipeline_layout: 4,
@@ -142,78 +412,887 @@ impl Default for GraphicsConfig {
             max_uniform_buffers_per_shader_stage: 12,
             max_uniform_buffer_binding_size: 16384,
             max_push_constant_pool_byte_size: 256,
+            shader_optimization: ShaderOptimization::Performance,
+            shadows: ShadowConfig::default(),
+            post_processing: PostProcessingConfig::default(),
+            environment: EnvironmentConfig::default(),
         }
     }
 }
 
-#[derive(Default)]
+impl Validate for GraphicsConfig {
+    fn validate(&mut self, file_name: &str) {
+        let defaults = Self::default();
+        // the pbr pipeline uses three bind groups and 192 bytes of push constants:
+        check_range(
+            file_name,
+            "max_bind_groups",
+            &mut self.max_bind_groups,
+            3..=8,
+            defaults.max_bind_groups,
+        );
+        check_range(
+            file_name,
+            "max_push_constant_pool_byte_size",
+            &mut self.max_push_constant_pool_byte_size,
+            192..=4096,
+            defaults.max_push_constant_pool_byte_size,
+        );
+
+        let shadows = &mut self.shadows;
+        check_range(
+            file_name,
+            "shadows.resolution",
+            &mut shadows.resolution,
+            256..=8192,
+            defaults.shadows.resolution,
+        );
+        check_range(
+            file_name,
+            "shadows.cascade_count",
+            &mut shadows.cascade_count,
+            1..=4,
+            defaults.shadows.cascade_count,
+        );
+        check_range(
+            file_name,
+            "shadows.distance",
+            &mut shadows.distance,
+            1.0..=10_000.0,
+            defaults.shadows.distance,
+        );
+        check_range(
+            file_name,
+            "shadows.split_lambda",
+            &mut shadows.split_lambda,
+            0.0..=1.0,
+            defaults.shadows.split_lambda,
+        );
+        check_range(
+            file_name,
+            "shadows.pcf_radius",
+            &mut shadows.pcf_radius,
+            0..=4,
+            defaults.shadows.pcf_radius,
+        );
+
+        let post_processing = &mut self.post_processing;
+        check_range(
+            file_name,
+            "post_processing.exposure",
+            &mut post_processing.exposure,
+            0.001..=100.0,
+            defaults.post_processing.exposure,
+        );
+        check_range(
+            file_name,
+            "post_processing.bloom_passes",
+            &mut post_processing.bloom_passes,
+            1..=8,
+            defaults.post_processing.bloom_passes,
+        );
+        check_range(
+            file_name,
+            "post_processing.bloom_knee",
+            &mut post_processing.bloom_knee,
+            0.0..=1.0,
+            defaults.post_processing.bloom_knee,
+        );
+        check_range(
+            file_name,
+            "post_processing.color_grading_intensity",
+            &mut post_processing.color_grading_intensity,
+            0.0..=1.0,
+            defaults.post_processing.color_grading_intensity,
+        );
+
+        check_range(
+            file_name,
+            "environment.resolution",
+            &mut self.environment.resolution,
+            16..=2048,
+            defaults.environment.resolution,
+        );
+    }
+}
+
+/// Named action, active while any of its inputs is held.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActionBinding {
+    pub name: String,
+    #[serde(default)]
+    pub inputs: Vec<String>,
+}
+
+/// Named axis, the sum of its positive minus its negative inputs, multiplied by the scale.
+/// Buttons contribute 1.0 while held, mouse axes their movement this frame.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub name: String,
+    #[serde(default)]
+    pub positive: Vec<String>,
+    #[serde(default)]
+    pub negative: Vec<String>,
+    #[serde(default = "default_axis_scale")]
+    pub scale: f32,
+}
+
+#[inline]
+fn default_axis_scale() -> f32 {
+    1.0
+}
+
+/// Input recording of a session, see `core::platform::replay`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum InputRecordingMode {
+    Off,
+    /// Records the input of every frame, written when the simulation shuts down.
+    Record {
+        path: PathBuf,
+    },
+    /// Replays a recording instead of polling glfw input and stops when it ends.
+    /// The window stays hidden and the resulting world is saved as a scene if a path is given,
+    /// so runs can be compared.
+    Replay {
+        path: PathBuf,
+        world_state_path: Option<PathBuf>,
+    },
+}
+
+/// Inputs are written as '+' separated chords, like "LeftControl+S", "Shift+Mouse1" or "MouseX".
+/// Keys use the glfw key names, mouse buttons are "Mouse1" to "Mouse8" and mouse axes are
+/// "MouseX", "MouseY", "ScrollX" and "ScrollY".
+/// "Shift", "Control", "Alt" and "Super" match the modifier key on either side.
+/// Gamepad buttons and axes use the glfw names with a "Pad" prefix instead of "Button"
+/// or "Axis", like "PadA", "PadDpadUp", "PadLeftX" or "PadRightTrigger".
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputConfig {
+    pub actions: Vec<ActionBinding>,
+    pub axes: Vec<AxisBinding>,
+    /// Radial deadzone of the gamepad sticks.
+    pub gamepad_deadzone: f32,
+    pub gamepad_trigger_deadzone: f32,
+    pub recording: InputRecordingMode,
+}
+
+impl InputConfig {
+    pub const FILE_NAME: &'static str = "input.ini";
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        fn action(name: &str, inputs: &[&str]) -> ActionBinding {
+            ActionBinding {
+                name: name.to_string(),
+                inputs: inputs.iter().map(|input| input.to_string()).collect(),
+            }
+        }
+
+        fn axis(name: &str, positive: &[&str], negative: &[&str], scale: f32) -> AxisBinding {
+            AxisBinding {
+                name: name.to_string(),
+                positive: positive.iter().map(|input| input.to_string()).collect(),
+                negative: negative.iter().map(|input| input.to_string()).collect(),
+                scale,
+            }
+        }
+
+        Self {
+            actions: vec![
+                action("look", &["Mouse2"]),
+                action("toggle_debug_ui", &["F1", "PadBack"]),
+                action("sprint", &["LeftShift", "PadLeftThumb"]),
+            ],
+            axes: vec![
+                axis(
+                    "move_forward",
+                    &["W", "Up"],
+                    &["S", "Down", "PadLeftY"],
+                    1.0,
+                ),
+                axis(
+                    "move_right",
+                    &["D", "Right", "PadLeftX"],
+                    &["A", "Left"],
+                    1.0,
+                ),
+                axis(
+                    "move_up",
+                    &["E", "Space", "PadRightTrigger"],
+                    &["Q", "LeftControl", "PadLeftTrigger"],
+                    1.0,
+                ),
+                // mouse look in radians per pixel, gamepad look in radians per second:
+                axis("look_x", &["MouseX"], &[], 1.0 / 300.0),
+                axis("look_y", &["MouseY"], &[], 1.0 / 300.0),
+                axis("pad_look_x", &["PadRightX"], &[], 2.5),
+                axis("pad_look_y", &["PadRightY"], &[], 2.5),
+                axis("zoom", &["ScrollY"], &[], 1.0),
+            ],
+            gamepad_deadzone: 0.15,
+            gamepad_trigger_deadzone: 0.1,
+            recording: InputRecordingMode::Off,
+        }
+    }
+}
+
+impl Validate for InputConfig {
+    fn validate(&mut self, file_name: &str) {
+        let defaults = Self::default();
+        check_range(
+            file_name,
+            "gamepad_deadzone",
+            &mut self.gamepad_deadzone,
+            0.0..=0.95,
+            defaults.gamepad_deadzone,
+        );
+        check_range(
+            file_name,
+            "gamepad_trigger_deadzone",
+            &mut self.gamepad_trigger_deadzone,
+            0.0..=0.95,
+            defaults.gamepad_trigger_deadzone,
+        );
+    }
+}
+
+#[derive(Default, Clone)]
 pub struct CoreConfig {
     pub application_config: AppConfig,
     pub memory_config: MemoryConfig,
     pub display_config: DisplayConfig,
     pub graphics_config: GraphicsConfig,
+    pub input_config: InputConfig,
+    /// Fields set by environment overrides as (file name, field path), these are never saved.
+    env_overrides: BTreeSet<(String, String)>,
 }
 
-macro_rules! deserialize_config {
-    ($dir:ident, $type:ty) => {
-    serde_yaml::from_str::<$type>(
-            &fs::read_to_string($dir.join(Path::new(<$type>::FILE_NAME)))
-                .unwrap_or_default(),
-        )
-        .unwrap_or_else(|_| {
+/// Directory of config files. Files of later layers override single values of earlier ones,
+/// the built-in defaults being the first layer.
+pub struct ConfigLayer {
+    pub name: &'static str,
+    pub dir: PathBuf,
+    pub version: u32,
+    /// Missing, migrated and broken files are only fixed in the project config directory.
+    pub writable: bool,
+}
+
+/// The system, user, project and environment variable config directories in override order.
+pub fn config_layers() -> Vec<ConfigLayer> {
+    let mut layers = Vec::new();
+    let mut push_existing = |name, dir: Option<PathBuf>| {
+        if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+            layers.push(ConfigLayer {
+                name,
+                version: read_config_version(&dir).unwrap_or(CONFIG_VERSION),
+                dir,
+                writable: false,
+            });
+        }
+    };
+    push_existing("system", system_config_dir());
+    push_existing("user", user_config_dir());
+
+    // versions before layering had no version file:
+    let project_dir = PathBuf::from(CONFIG_DIR);
+    let project_version = if project_dir.is_dir() {
+        read_config_version(&project_dir).unwrap_or(0)
+    } else {
+        CONFIG_VERSION
+    };
+    layers.push(ConfigLayer {
+        name: "project",
+        dir: project_dir,
+        version: project_version,
+        writable: true,
+    });
+
+    if let Some(dir) = env::var_os(CONFIG_DIR_ENV_VAR).map(PathBuf::from) {
+        if dir.is_dir() {
+            layers.push(ConfigLayer {
+                name: CONFIG_DIR_ENV_VAR,
+                version: read_config_version(&dir).unwrap_or(CONFIG_VERSION),
+                dir,
+                writable: false,
+            });
+        } else {
             warn!(
-                "Failed to load: \"{}\"! Setting default values...",
-                <$type>::FILE_NAME
+                "{} is set, but {:?} is not a directory!",
+                CONFIG_DIR_ENV_VAR, dir
             );
-            warn!("Trying autofix by removing config files! Clean config files will be recreated when rebooting the engine!");
-            let _ = fs::remove_dir_all(&$dir);
-            std::default::Default::default()
+        }
+    }
+    layers
+}
+
+fn system_config_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env::var_os("PROGRAMDATA").map(|dir| PathBuf::from(dir).join(APP_DIR_NAME))
+    } else if cfg!(target_os = "macos") {
+        Some(PathBuf::from("/Library/Application Support").join(APP_DIR_NAME))
+    } else {
+        Some(PathBuf::from("/etc").join(APP_DIR_NAME))
+    }
+}
+
+fn user_config_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join(APP_DIR_NAME))
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library/Application Support")
+                .join(APP_DIR_NAME)
         })
-    };
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join(APP_DIR_NAME))
+    }
+}
+
+/// Detects edited config files by the newest modification time in the config directories.
+pub struct ConfigWatcher {
+    last_check: Instant,
+    stamp: Option<SystemTime>,
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self {
+            last_check: Instant::now(),
+            stamp: newest_modification(),
+        }
+    }
+}
+
+impl ConfigWatcher {
+    /// Returns true once after any config file changed, checking at most once per interval.
+    pub fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let stamp = newest_modification();
+        if stamp == self.stamp {
+            return false;
+        }
+        self.stamp = stamp;
+        true
+    }
+
+    /// Accepts the files as they are now, so files written by the engine itself are not reloaded.
+    #[inline]
+    pub fn refresh(&mut self) {
+        self.stamp = newest_modification();
+    }
+}
+
+fn newest_modification() -> Option<SystemTime> {
+    config_layers()
+        .iter()
+        .filter_map(|layer| fs::read_dir(&layer.dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .max()
 }
 
-macro_rules! serialize_config {
-    ($dir:ident, $self:ident, $data:ident, $type:ty) => {
-        fs::write(
-            $dir.join(Path::new(<$type>::FILE_NAME)),
-            serde_yaml::to_string(&$self.$data).unwrap_or_default(),
+fn read_config_version(dir: &Path) -> Option<u32> {
+    fs::read_to_string(dir.join(ConfigVersion::FILE_NAME))
+        .ok()
+        .and_then(|text| serde_yaml::from_str::<ConfigVersion>(&text).ok())
+        .map(|config_version| config_version.version)
+}
+
+fn write_config_version(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(Path::new(ConfigVersion::FILE_NAME)),
+        serde_yaml::to_string(&ConfigVersion {
+            version: CONFIG_VERSION,
+        })
+        .unwrap_or_default(),
+    )
+}
+
+/// Environment variable overrides, as (name, value).
+fn env_overrides() -> Vec<(String, String)> {
+    env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_OVERRIDE_PREFIX) && name != CONFIG_DIR_ENV_VAR)
+        .collect()
+}
+
+/// Prefix of the environment overrides of a config file, like "KESTD_DISPLAY_".
+fn env_override_prefix(file_name: &str) -> String {
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    format!("{}{}_", ENV_OVERRIDE_PREFIX, stem.to_uppercase())
+}
+
+macro_rules! deserialize_config {
+    ($layers:ident, $type:ty, $env:ident, $dirty:ident, $overrides:ident) => {
+        load_config_file::<$type>(
+            &$layers,
+            <$type>::FILE_NAME,
+            &$env,
+            &mut $dirty,
+            &mut $overrides,
         )
     };
 }
 
+/// Loads a single config file by merging it from all layers and the environment overrides.
+/// Missing files and fields fall back to the layers below, files of older versions are migrated
+/// and broken files are skipped and backed up instead of deleted.
+/// Sets `dirty` if the project config directory was updated
+/// and adds the fields set by environment overrides to `env_overrides`.
+fn load_config_file<T: Serialize + DeserializeOwned + Default + Validate>(
+    layers: &[ConfigLayer],
+    file_name: &str,
+    env: &[(String, String)],
+    dirty: &mut bool,
+    env_overrides: &mut BTreeSet<(String, String)>,
+) -> T {
+    let mut merged = serde_yaml::to_value(T::default()).unwrap_or(Value::Null);
+    let mut sources = BTreeMap::new();
+    let mut supplied = false;
+
+    for layer in layers {
+        let path = layer.dir.join(Path::new(file_name));
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        let mut value = match serde_yaml::from_str::<Value>(&text) {
+            Ok(value) => value,
+            Err(error) => {
+                warn!("Failed to load: {:?}! {}", path, error);
+                if layer.writable {
+                    backup_broken_file(&path);
+                }
+                continue;
+            }
+        };
+
+        let migrated = layer.version < CONFIG_VERSION;
+        migrate(file_name, layer.version, &mut value);
+
+        // only accept the layer if the merged values still deserialize:
+        let mut candidate = merged.clone();
+        let mut candidate_sources = sources.clone();
+        merge_layer(
+            &mut candidate,
+            value.clone(),
+            layer.name,
+            "",
+            &mut candidate_sources,
+        );
+        if let Err(error) = serde_yaml::from_value::<T>(candidate.clone()) {
+            warn!("Failed to load: {:?}! {}", path, error);
+            if layer.writable {
+                backup_broken_file(&path);
+            }
+            continue;
+        }
+        merged = candidate;
+        sources = candidate_sources;
+        supplied = true;
+
+        // migrated files are written back as they are, to keep them partial:
+        if migrated && layer.writable {
+            match serde_yaml::to_string(&value)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+                .and_then(|yaml| fs::write(&path, yaml))
+            {
+                Ok(()) => *dirty = true,
+                Err(error) => warn!("Failed to update {:?}: {}", path, error),
+            }
+        }
+    }
+
+    // recreate the defaults if no directory provides the file:
+    if !supplied {
+        if let Some(layer) = layers.iter().find(|layer| layer.writable) {
+            let path = layer.dir.join(Path::new(file_name));
+            warn!("Missing config file: {:?}! Setting default values...", path);
+            let written = fs::create_dir_all(&layer.dir)
+                .and_then(|_| fs::write(&path, serde_yaml::to_string(&merged).unwrap_or_default()));
+            match written {
+                Ok(()) => *dirty = true,
+                Err(error) => warn!("Failed to create {:?}: {}", path, error),
+            }
+        }
+    }
+
+    let prefix = env_override_prefix(file_name);
+    for (name, text) in env.iter().filter(|(name, _)| name.starts_with(&prefix)) {
+        let value =
+            serde_yaml::from_str::<Value>(text).unwrap_or_else(|_| Value::from(text.as_str()));
+        let mut candidate = merged.clone();
+        match set_env_field(&mut candidate, &name[prefix.len()..], &value) {
+            Some(field) if serde_yaml::from_value::<T>(candidate.clone()).is_ok() => {
+                merged = candidate;
+                env_overrides.insert((file_name.to_string(), field.clone()));
+                sources.insert(field, name.clone());
+            }
+            Some(field) => warn!(
+                "Invalid value of {}: {:?} does not fit \"{}\" in \"{}\"!",
+                name, text, field, file_name
+            ),
+            None => warn!("{} does not match any value in \"{}\"!", name, file_name),
+        }
+    }
+
+    for (field, source) in &sources {
+        info!("Config \"{}\": {} <- {}", file_name, field, source);
+    }
+
+    let mut config = serde_yaml::from_value::<T>(merged).unwrap_or_default();
+    config.validate(file_name);
+    config
+}
+
+/// Merges the values of a layer into the merged values, recording the layer of every value.
+/// Mappings are merged key by key, everything else is replaced as a whole.
+fn merge_layer(
+    merged: &mut Value,
+    layer: Value,
+    layer_name: &str,
+    path: &str,
+    sources: &mut BTreeMap<String, String>,
+) {
+    match (merged, layer) {
+        (Value::Mapping(merged), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                let field = match key.as_str() {
+                    Some(name) if path.is_empty() => name.to_string(),
+                    Some(name) => format!("{}.{}", path, name),
+                    None => continue,
+                };
+                match merged.get_mut(&key) {
+                    Some(merged_value) => {
+                        merge_layer(merged_value, value, layer_name, &field, sources)
+                    }
+                    None => {
+                        merged.insert(key, value);
+                        sources.insert(field, layer_name.to_string());
+                    }
+                }
+            }
+        }
+        (merged, layer) => {
+            *merged = layer;
+            sources.insert(path.to_string(), layer_name.to_string());
+        }
+    }
+}
+
+/// Sets the value matching the upper case field path, like "SHADOWS_RESOLUTION".
+/// Returns the field path in config notation, like "shadows.resolution".
+fn set_env_field(merged: &mut Value, field: &str, value: &Value) -> Option<String> {
+    let mapping = merged.as_mapping_mut()?;
+    for (key, child) in mapping.iter_mut() {
+        let name = match key.as_str() {
+            Some(name) => name,
+            None => continue,
+        };
+        let upper = name.to_uppercase();
+        if field == upper {
+            *child = value.clone();
+            return Some(name.to_string());
+        }
+        let rest = field
+            .strip_prefix(upper.as_str())
+            .and_then(|rest| rest.strip_prefix('_'));
+        if let Some(path) = rest.and_then(|rest| set_env_field(child, rest, value)) {
+            return Some(format!("{}.{}", name, path));
+        }
+    }
+    None
+}
+
+/// Moves a config file which failed to load aside, so the user's edits are not lost.
+fn backup_broken_file(path: &Path) {
+    let backup = path.with_extension(format!(
+        "ini.{}.broken",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    match fs::rename(path, &backup) {
+        Ok(()) => warn!("Moved broken config file to: {:?}", backup),
+        Err(error) => warn!("Failed to back up broken config file {:?}: {}", path, error),
+    }
+}
+
+/// Upgrades a config file written by an older version, one version at a time.
+fn migrate(file_name: &str, version: u32, value: &mut Value) {
+    for from in version..CONFIG_VERSION {
+        info!(
+            "Migrating \"{}\" from config version {} to {}...",
+            file_name,
+            from,
+            from + 1
+        );
+        if from == 0 && file_name == InputConfig::FILE_NAME {
+            migrate_input_bindings_v0(value);
+        }
+    }
+}
+
+/// Version 1 made the gamepad look axes rates per second instead of per frame
+/// and added the sprint, move_up and zoom bindings.
+/// Only the missing default bindings are added, existing scales are kept as written.
+fn migrate_input_bindings_v0(value: &mut Value) {
+    // add the new default bindings to existing binding lists:
+    let defaults = serde_yaml::to_value(InputConfig::default()).unwrap_or(Value::Null);
+    for list in &["actions", "axes"] {
+        let (bindings, default_bindings) = match (
+            value.get_mut(*list).and_then(Value::as_sequence_mut),
+            defaults.get(*list).and_then(Value::as_sequence),
+        ) {
+            (Some(bindings), Some(default_bindings)) => (bindings, default_bindings),
+            _ => continue,
+        };
+        for default_binding in default_bindings {
+            let name = default_binding.get("name");
+            if !bindings.iter().any(|binding| binding.get("name") == name) {
+                bindings.push(default_binding.clone());
+            }
+        }
+    }
+}
+
+macro_rules! serialize_config_changes {
+    ($dir:ident, $self:ident, $previous:ident, $data:ident, $type:ty) => {
+        $self.save_file_changes($dir, <$type>::FILE_NAME, &$self.$data, &$previous.$data)
+    };
+}
+
+/// Copies the values of `new` which differ from `old` into the file value, descending into
+/// mappings so the unchanged values keep coming from the other layers.
+/// Adds the paths of the copied fields to `changed`, fields `skip` returns true for are left out.
+fn merge_changes(
+    file: &mut Value,
+    new: &Value,
+    old: &Value,
+    path: &str,
+    skip: &dyn Fn(&str) -> bool,
+    changed: &mut Vec<String>,
+) {
+    let (new, old) = match (new.as_mapping(), old.as_mapping()) {
+        (Some(new), Some(old)) => (new, old),
+        _ => return,
+    };
+    if !file.is_mapping() {
+        *file = Value::Mapping(Default::default());
+    }
+    for (key, new_value) in new {
+        let old_value = old.get(key);
+        if old_value == Some(new_value) {
+            continue;
+        }
+        let field = match key.as_str() {
+            Some(name) if path.is_empty() => name.to_string(),
+            Some(name) => format!("{}.{}", path, name),
+            None => continue,
+        };
+        let file = file.as_mapping_mut().expect("file value is a mapping");
+        // structs are merged field by field, other values like enum variants as a whole:
+        let same_fields = match (
+            new_value.as_mapping(),
+            old_value.and_then(Value::as_mapping),
+        ) {
+            (Some(new), Some(old)) => {
+                new.len() == old.len() && new.iter().all(|(key, _)| old.contains_key(key))
+            }
+            _ => false,
+        };
+        if same_fields {
+            let mut child = file.get(key).cloned().unwrap_or(Value::Null);
+            merge_changes(
+                &mut child,
+                new_value,
+                old_value.unwrap_or(&Value::Null),
+                &field,
+                skip,
+                changed,
+            );
+            if child
+                .as_mapping()
+                .filter(|child| !child.is_empty())
+                .is_some()
+            {
+                file.insert(key.clone(), child);
+            }
+        } else if skip(&field) {
+            warn!(
+                "\"{}\" is set by an environment override and not saved!",
+                field
+            );
+        } else {
+            file.insert(key.clone(), new_value.clone());
+            changed.push(field);
+        }
+    }
+}
+
 impl CoreConfig {
     pub fn load() -> Self {
-        let config_dir = PathBuf::from(CONFIG_DIR);
-        info!(
-            "Parsing config from dir: {:?}",
-            fs::canonicalize(&config_dir).unwrap_or_default()
-        );
-        if !config_dir.exists() {
-            warn!("Config directory does not exist! Creating config...");
-            let this = Self::default();
-            let _ = this.save();
-            return this;
-        }
-        let application_config = deserialize_config!(config_dir, AppConfig);
-        let memory_config = deserialize_config!(config_dir, MemoryConfig);
-        let display_config = deserialize_config!(config_dir, DisplayConfig);
-        let graphics_config = deserialize_config!(config_dir, GraphicsConfig);
+        let layers = config_layers();
+        for layer in &layers {
+            info!(
+                "Parsing {} config from dir: {:?}",
+                layer.name,
+                fs::canonicalize(&layer.dir).unwrap_or_else(|_| layer.dir.clone())
+            );
+        }
+        let project_version = layers
+            .iter()
+            .find(|layer| layer.writable)
+            .map_or(CONFIG_VERSION, |layer| layer.version);
+        if project_version > CONFIG_VERSION {
+            warn!(
+                "Config version {} is newer than the supported version {}! Unknown values are ignored...",
+                project_version, CONFIG_VERSION
+            );
+        }
+
+        let env = env_overrides();
+        let known_prefixes = [
+            AppConfig::FILE_NAME,
+            MemoryConfig::FILE_NAME,
+            DisplayConfig::FILE_NAME,
+            GraphicsConfig::FILE_NAME,
+            InputConfig::FILE_NAME,
+        ]
+        .iter()
+        .map(|file_name| env_override_prefix(file_name))
+        .collect::<Vec<_>>();
+        for (name, _) in &env {
+            if !known_prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+                warn!("{} does not match any config file!", name);
+            }
+        }
+
+        let mut dirty = false;
+        let mut env_overrides = BTreeSet::new();
+        let application_config = deserialize_config!(layers, AppConfig, env, dirty, env_overrides);
+        let memory_config = deserialize_config!(layers, MemoryConfig, env, dirty, env_overrides);
+        let display_config = deserialize_config!(layers, DisplayConfig, env, dirty, env_overrides);
+        let graphics_config =
+            deserialize_config!(layers, GraphicsConfig, env, dirty, env_overrides);
+        let input_config = deserialize_config!(layers, InputConfig, env, dirty, env_overrides);
+
+        // never downgrade the files of a newer version:
+        if dirty && project_version <= CONFIG_VERSION {
+            if let Err(error) = write_config_version(Path::new(CONFIG_DIR)) {
+                warn!("Failed to save config version: {}", error);
+            }
+        }
+
         Self {
             application_config,
             memory_config,
             display_config,
             graphics_config,
+            input_config,
+            env_overrides,
         }
     }
 
-    pub fn save(&self) -> std::io::Result<()> {
+    /// Conservative settings to boot with broken drivers or after a bad config edit:
+    /// windowed at the default resolution, no MSAA, the automatic backend and the smallest
+    /// device limits the pipelines work with.
+    pub fn apply_safe_mode(&mut self) {
+        warn!("Safe mode is enabled! Booting with conservative settings...");
+        self.display_config.window_mode = WindowMode::Windowed;
+        self.display_config.resolution = DisplayConfig::default().resolution;
+
+        let defaults = GraphicsConfig::default();
+        let graphics = &mut self.graphics_config;
+        graphics.msaa_mode = MsaaMode::Off;
+        graphics.depth_mode = DepthMode::Standard;
+        graphics.backend_api = GraphicsApi::Auto;
+        graphics.adapter = AdapterSelection::Auto;
+        graphics.max_bind_groups = 3;
+        graphics.max_dynamic_uniform_buffers_per_pipeline_layout =
+            defaults.max_dynamic_uniform_buffers_per_pipeline_layout;
+        graphics.max_dynamic_storage_buffers_per_pipeline_layout =
+            defaults.max_dynamic_storage_buffers_per_pipeline_layout;
+        graphics.max_sampled_textures_per_shader_stage =
+            defaults.max_sampled_textures_per_shader_stage;
+        graphics.max_samplers_per_shader_stage = defaults.max_samplers_per_shader_stage;
+        graphics.max_storage_buffers_per_shader_stage =
+            defaults.max_storage_buffers_per_shader_stage;
+        graphics.max_storage_textures_per_shader_stage =
+            defaults.max_storage_textures_per_shader_stage;
+        graphics.max_uniform_buffers_per_shader_stage =
+            defaults.max_uniform_buffers_per_shader_stage;
+        graphics.max_uniform_buffer_binding_size = defaults.max_uniform_buffer_binding_size;
+        graphics.max_push_constant_pool_byte_size = 192;
+
+        // a recording or replay should not be what breaks the boot:
+        self.input_config.recording = InputRecordingMode::Off;
+    }
+
+    /// Writes the values which differ from `previous` to the project config directory.
+    /// Only the changed fields are added to the files, so values of the other layers and
+    /// the environment overrides stay where they are.
+    pub fn save_changes(&self, previous: &CoreConfig) -> std::io::Result<()> {
         let config_dir = &PathBuf::from(CONFIG_DIR);
-        if !config_dir.exists() {
-            fs::create_dir(config_dir)?;
+        let mut saved = false;
+        saved |=
+            serialize_config_changes!(config_dir, self, previous, application_config, AppConfig)?;
+        saved |=
+            serialize_config_changes!(config_dir, self, previous, memory_config, MemoryConfig)?;
+        saved |=
+            serialize_config_changes!(config_dir, self, previous, display_config, DisplayConfig)?;
+        saved |=
+            serialize_config_changes!(config_dir, self, previous, graphics_config, GraphicsConfig)?;
+        saved |= serialize_config_changes!(config_dir, self, previous, input_config, InputConfig)?;
+        if saved {
+            write_config_version(config_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the file was written.
+    fn save_file_changes<T: Serialize>(
+        &self,
+        config_dir: &Path,
+        file_name: &str,
+        new: &T,
+        old: &T,
+    ) -> std::io::Result<bool> {
+        let to_value = |config: &T| {
+            serde_yaml::to_value(config)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+        };
+        let (new, old) = (to_value(new)?, to_value(old)?);
+        let is_env_override = |field: &str| {
+            self.env_overrides.iter().any(|(file, overridden)| {
+                file == file_name
+                    && (field == overridden || field.starts_with(&format!("{}.", overridden)))
+            })
+        };
+        let path = config_dir.join(Path::new(file_name));
+        let mut file = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_yaml::from_str::<Value>(&text).ok())
+            .unwrap_or(Value::Null);
+        let mut changed = Vec::new();
+        merge_changes(&mut file, &new, &old, "", &is_env_override, &mut changed);
+        if changed.is_empty() {
+            return Ok(false);
+        }
+        for field in &changed {
+            info!("Saving \"{}\": {}", file_name, field);
         }
-        serialize_config!(config_dir, self, application_config, AppConfig)?;
-        serialize_config!(config_dir, self, memory_config, MemoryConfig)?;
-        serialize_config!(config_dir, self, display_config, DisplayConfig)?;
-        serialize_config!(config_dir, self, graphics_config, GraphicsConfig)
+        fs::create_dir_all(config_dir)?;
+        let yaml = serde_yaml::to_string(&file)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        fs::write(path, yaml)?;
+        Ok(true)
     }
 }
@@ -1,79 +1,348 @@
-use crate::config::GraphicsConfig;
+use super::push_constants::MIN_PUSH_CONSTANT_SIZE;
+use crate::config::{AdapterSelection, AdapterType, DepthMode, GraphicsApi, GraphicsConfig};
+use log::{info, warn};
 use wgpu::*;
 
-pub const REQUIRED_DEVICE_FEATURES: Features = Features::PUSH_CONSTANTS;
+/// Features used when the adapter has them.
+pub const OPTIONAL_DEVICE_FEATURES: Features = Features::PUSH_CONSTANTS;
 pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Value the scene depth buffer is cleared to, the farthest depth.
+#[inline]
+pub fn depth_clear_value(mode: DepthMode) -> f32 {
+    match mode {
+        DepthMode::Standard => 1.0,
+        DepthMode::ReverseZ => 0.0,
+    }
+}
+
+/// Depth test of the scene pipelines, passing fragments closer than the stored depth.
+#[inline]
+pub fn depth_compare(mode: DepthMode) -> CompareFunction {
+    match mode {
+        DepthMode::Standard => CompareFunction::Less,
+        DepthMode::ReverseZ => CompareFunction::Greater,
+    }
+}
+
+/// Adapter and device negotiated with the backends.
+pub struct GpuContext {
+    pub instance: Instance,
+    pub surface: Surface,
+    pub adapter: Adapter,
+    pub device: Device,
+    pub queue: Queue,
+    /// Whether the device has push constants, otherwise they are emulated with uniform buffers.
+    pub push_constants: bool,
+}
+
+#[inline]
+pub fn backend_bit(api: GraphicsApi) -> BackendBit {
+    match api {
+        GraphicsApi::Auto => BackendBit::PRIMARY,
+        GraphicsApi::Direct3D11 => BackendBit::DX11,
+        GraphicsApi::Direct3D12 => BackendBit::DX12,
+        GraphicsApi::OpenGl => BackendBit::GL,
+        GraphicsApi::Vulkan => BackendBit::VULKAN,
+        GraphicsApi::WebGpu => BackendBit::BROWSER_WEBGPU,
+    }
+}
+
+/// The configured backend first, then the primary and secondary backends.
+fn backend_candidates(config: &GraphicsConfig) -> Vec<BackendBit> {
+    let mut candidates = vec![backend_bit(config.backend_api)];
+    for fallback in &[BackendBit::PRIMARY, BackendBit::SECONDARY] {
+        if !candidates.contains(fallback) {
+            candidates.push(*fallback);
+        }
+    }
+    candidates
+}
+
+#[inline]
+fn adapter_type(device_type: &DeviceType) -> AdapterType {
+    match device_type {
+        DeviceType::DiscreteGpu => AdapterType::Discrete,
+        DeviceType::IntegratedGpu => AdapterType::Integrated,
+        DeviceType::VirtualGpu => AdapterType::Virtual,
+        DeviceType::Cpu => AdapterType::Cpu,
+        DeviceType::Other => AdapterType::Other,
+    }
+}
+
+/// Prints every adapter of the backend candidates with its features and limits,
+/// numbered like the `adapter` index of the graphics config, which refers to the first backends.
+pub fn print_adapter_report(config: &GraphicsConfig) {
+    for backends in backend_candidates(config) {
+        println!("Backends: {:?}", backends);
+        let instance = Instance::new(backends);
+        for (i, adapter) in instance.enumerate_adapters(backends).enumerate() {
+            let info = adapter.get_info();
+            println!("  Adapter {}: {}", i, info.name);
+            println!("    Backend: {:?}", info.backend);
+            println!("    Type: {:?}", info.device_type);
+            println!(
+                "    Vendor: {:#06x}, device: {:#06x}",
+                info.vendor, info.device
+            );
+            println!("    Features: {:?}", adapter.features());
+            println!("    Limits: {:#?}", adapter.limits());
+        }
+    }
+}
+
+/// Tries the configured backend first, then the primary and secondary backends.
+/// Adapters selected by index or name are only looked up on the configured backend.
+pub fn create_gpu_context(
+    window: &glfw::Window,
+    low_power_mode: bool,
+    config: &GraphicsConfig,
+) -> GpuContext {
+    // indices and names refer to the adapters of the configured backend:
+    let pinned = matches!(
+        config.adapter,
+        AdapterSelection::Index(_) | AdapterSelection::Name(_)
+    );
+    for backends in backend_candidates(config) {
+        info!("Probing backends: {:?}", backends);
+        let instance = Instance::new(backends);
+        for (i, adapter) in instance.enumerate_adapters(backends).enumerate() {
+            let info = adapter.get_info();
+            info!(
+                "Adapter {}: {} ({:?}, {:?}), push constants: {}",
+                i,
+                info.name,
+                info.backend,
+                info.device_type,
+                adapter.features().contains(OPTIONAL_DEVICE_FEATURES)
+            );
+        }
+        let surface = unsafe { instance.create_surface(window) };
+        match futures::executor::block_on(create_async_resources(
+            &instance,
+            backends,
+            &surface,
+            low_power_mode,
+            config,
+        )) {
+            Ok((adapter, device, queue, push_constants)) => {
+                return GpuContext {
+                    instance,
+                    surface,
+                    adapter,
+                    device,
+                    queue,
+                    push_constants,
+                }
+            }
+            Err(error) if pinned => panic!(
+                "The configured adapter is unusable on backends {:?}: {}! See --list-adapters for the adapters.",
+                backends, error
+            ),
+            Err(error) => warn!("Backends {:?} are unusable: {}", backends, error),
+        }
+    }
+    panic!("Failed to find a usable GPU adapter on any backend!");
+}
+
+/// Picks the configured adapter among the ones of the backends.
+async fn select_adapter(
+    instance: &Instance,
+    backends: BackendBit,
+    surface: &Surface,
+    low_power_mode: bool,
+    config: &GraphicsConfig,
+) -> Result<Adapter, String> {
+    if config.adapter == AdapterSelection::Auto && !config.force_fallback_adapter {
+        return instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: if low_power_mode {
+                    PowerPreference::LowPower
+                } else {
+                    PowerPreference::HighPerformance
+                },
+                compatible_surface: Some(surface),
+            })
+            .await
+            .ok_or_else(|| String::from("no adapter is compatible with the window surface"));
+    }
+
+    let mut skipped = false;
+    for (i, adapter) in instance.enumerate_adapters(backends).enumerate() {
+        let info = adapter.get_info();
+        let device_type = adapter_type(&info.device_type);
+        let selected = match &config.adapter {
+            AdapterSelection::Auto => true,
+            AdapterSelection::Index(index) => i == *index,
+            AdapterSelection::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            AdapterSelection::DeviceType(selected_type) => device_type == *selected_type,
+        };
+        if !selected || (config.force_fallback_adapter && device_type != AdapterType::Cpu) {
+            continue;
+        }
+        match can_present(instance, &info, surface).await {
+            Some(true) => return Ok(adapter),
+            Some(false) => {
+                warn!("{} can't present to the window surface!", info.name);
+                skipped = true;
+            }
+            None => {
+                info!(
+                    "Can't tell whether {} presents to the window surface, trying it...",
+                    info.name
+                );
+                return Ok(adapter);
+            }
+        }
+    }
+    Err(format!(
+        "no adapter matches {:?}{}{}",
+        config.adapter,
+        if config.force_fallback_adapter {
+            " with a software rasterizer"
+        } else {
+            ""
+        },
+        if skipped {
+            " and can present to the window surface"
+        } else {
+            ""
+        }
+    ))
+}
+
+/// Whether the adapter can present to the surface, if wgpu tells.
+/// wgpu only checks the surface in `request_adapter`, which returns the first compatible adapter
+/// of the preferred device type if there is one: discrete for high performance,
+/// integrated for low power.
+async fn can_present(instance: &Instance, info: &AdapterInfo, surface: &Surface) -> Option<bool> {
+    for &(power_preference, preferred_type) in &[
+        (PowerPreference::HighPerformance, AdapterType::Discrete),
+        (PowerPreference::LowPower, AdapterType::Integrated),
+    ] {
+        let compatible = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference,
+                compatible_surface: Some(surface),
+            })
+            .await
+            .map(|adapter| adapter.get_info());
+        let compatible = match compatible {
+            Some(compatible) => compatible,
+            None => return Some(false),
+        };
+        if compatible.name == info.name
+            && compatible.vendor == info.vendor
+            && compatible.device == info.device
+            && compatible.backend == info.backend
+        {
+            return Some(true);
+        }
+        if adapter_type(&info.device_type) == preferred_type
+            && adapter_type(&compatible.device_type) != preferred_type
+        {
+            return Some(false);
+        }
+    }
+    None
+}
 
 pub async fn create_async_resources(
     instance: &Instance,
+    backends: BackendBit,
     surface: &Surface,
     low_power_mode: bool,
     config: &GraphicsConfig,
-) -> (Adapter, Device, Queue) {
-    let adapter = instance
-        .request_adapter(&RequestAdapterOptions {
-            power_preference: if low_power_mode {
-                PowerPreference::LowPower
-            } else {
-                PowerPreference::HighPerformance
-            },
-            compatible_surface: Some(&surface),
-        })
-        .await
-        .expect("Failed to find GPU adapter!");
+) -> Result<(Adapter, Device, Queue, bool), String> {
+    let adapter = select_adapter(instance, backends, surface, low_power_mode, config).await?;
+
+    let info = adapter.get_info();
+    info!("Selected adapter: {} ({:?})", info.name, info.backend);
+    let supported = adapter.limits();
+    let push_constants = adapter.features().contains(OPTIONAL_DEVICE_FEATURES)
+        && supported.max_push_constant_size >= MIN_PUSH_CONSTANT_SIZE;
+    if !push_constants {
+        warn!(
+            "{} has no push constants, emulating them with uniform buffers!",
+            info.name
+        );
+    }
+
+    let requested = Limits {
+        // the emulated push constants take one more bind group:
+        max_bind_groups: if push_constants {
+            config.max_bind_groups
+        } else {
+            config.max_bind_groups + 1
+        },
+        max_dynamic_uniform_buffers_per_pipeline_layout: config
+            .max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout: config
+            .max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage: config.max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage: config.max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage: config.max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage: config.max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage: config.max_uniform_buffers_per_shader_stage,
+        max_uniform_buffer_binding_size: config.max_uniform_buffer_binding_size,
+        max_push_constant_size: if push_constants {
+            config.max_push_constant_pool_byte_size
+        } else {
+            0
+        },
+    };
+    let limits = clamp_limits(&info.name, requested, &supported);
+
     let (device, queue) = adapter
         .request_device(
             &DeviceDescriptor {
                 label: None,
-                features: REQUIRED_DEVICE_FEATURES,
-                limits: Limits {
-                    max_bind_groups: config.max_bind_groups,
-                    max_dynamic_uniform_buffers_per_pipeline_layout: config
-                        .max_dynamic_uniform_buffers_per_pipeline_layout,
-                    max_dynamic_storage_buffers_per_pipeline_layout: config
-                        .max_dynamic_storage_buffers_per_pipeline_layout,
-                    max_sampled_textures_per_shader_stage: config
-                        .max_sampled_textures_per_shader_stage,
-                    max_samplers_per_shader_stage: config.max_samplers_per_shader_stage,
-                    max_storage_buffers_per_shader_stage: config
-                        .max_storage_buffers_per_shader_stage,
-                    max_storage_textures_per_shader_stage: config
-                        .max_storage_textures_per_shader_stage,
-                    max_uniform_buffers_per_shader_stage: config
-                        .max_uniform_buffers_per_shader_stage,
-                    max_uniform_buffer_binding_size: config.max_uniform_buffer_binding_size,
-                    max_push_constant_size: config.max_push_constant_pool_byte_size,
+                features: if push_constants {
+                    OPTIONAL_DEVICE_FEATURES
+                } else {
+                    Features::empty()
                 },
+                limits,
             },
             None,
         )
         .await
-        .expect("Failed to create device and queue!");
-    (adapter, device, queue)
+        .map_err(|error| format!("{}: {}", info.name, error))?;
+    Ok((adapter, device, queue, push_constants))
 }
 
-pub fn create_multi_sampled_framebuffer(
-    device: &Device,
-    sc_desc: &SwapChainDescriptor,
-    sample_count: u32,
-) -> TextureView {
-    let multi_sampled_texture_extent = Extent3d {
-        width: sc_desc.width,
-        height: sc_desc.height,
-        depth: 1,
-    };
-
-    let multi_sampled_frame_descriptor = &TextureDescriptor {
-        size: multi_sampled_texture_extent,
-        mip_level_count: 1,
-        sample_count,
-        dimension: TextureDimension::D2,
-        format: sc_desc.format,
-        usage: TextureUsage::RENDER_ATTACHMENT,
-        label: None,
-    };
+/// Lowers the requested limits to the ones of the adapter.
+fn clamp_limits(adapter_name: &str, requested: Limits, supported: &Limits) -> Limits {
+    macro_rules! clamp {
+        ($($field:ident),*) => {
+            Limits {
+                $($field: {
+                    if requested.$field > supported.$field {
+                        warn!(
+                            "{} supports {} = {} only, {} was requested!",
+                            adapter_name,
+                            stringify!($field),
+                            supported.$field,
+                            requested.$field
+                        );
+                    }
+                    requested.$field.min(supported.$field)
+                },)*
+            }
+        };
+    }
 
-    device
-        .create_texture(multi_sampled_frame_descriptor)
-        .create_view(&TextureViewDescriptor::default())
+    clamp!(
+        max_bind_groups,
+        max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage,
+        max_uniform_buffer_binding_size,
+        max_push_constant_size
+    )
 }
@@ -1,80 +1,236 @@
-use crate::components::{Camera, Transform};
-use crate::scenery_resources::{
-    CursorPos, Key, KeyInputStateCollection, MouseButton, MouseInputStateCollection,
+use crate::components::{
+    Camera, FlyController, FollowController, OrbitController, Projection, Transform,
 };
+use crate::config::DepthMode;
+use crate::scenery_resources::{CursorControl, CursorMode, InputMap};
 use cgmath::*;
+use legion::{Entity, EntityStore, IntoQuery, World};
+
+/// Moves all cameras which have a controller.
+/// Mouse axes are movements of this frame, all other axes rates scaled by the frame delta.
+pub fn update_controllers(world: &mut World, input: &InputMap, delta: f32) {
+    let mut fly_query = <(&mut Transform, &mut Camera, &mut FlyController)>::query();
+    for (transform, camera, controller) in fly_query.iter_mut(world) {
+        update_fly(transform, camera, controller, input, delta);
+    }
 
-pub fn compute_camera(
-    aspect_ratio: f32,
-    camera_entity: (&mut Transform, &mut Camera),
-    cursor_pos: CursorPos,
-    key_state: &KeyInputStateCollection,
-    mouse_state: &MouseInputStateCollection,
-) -> Matrix4<f32> {
-    let trans: &mut Transform = camera_entity.0;
-    let cam: &mut Camera = camera_entity.1;
-
-    if mouse_state.is_key_pressed(MouseButton::Button2) || cam.prev.is_zero() {
-        let dx = (cursor_pos.0 - cam.prev.x) / 300.0;
-        let dy = (cursor_pos.1 - cam.prev.y) / 300.0;
+    let mut orbit_query = <(&mut Transform, &mut Camera, &mut OrbitController)>::query();
+    for (transform, camera, controller) in orbit_query.iter_mut(world) {
+        update_orbit(transform, camera, controller, input, delta);
+    }
 
-        cam.smooth_angles = cam
-            .smooth_angles
-            .lerp(Vector2::new(dx, dy), 1.0 / cam.smoothness);
+    update_follow(world, delta);
+}
 
-        cam.angles.x -= dx + cam.smooth_angles.x;
-        cam.angles.y -= dy + cam.smooth_angles.y;
+/// Free fly camera driven by the "look" and "sprint" actions and the "look_x", "look_y",
+/// "pad_look_x", "pad_look_y", "move_forward", "move_right" and "move_up" axes.
+fn update_fly(
+    transform: &mut Transform,
+    camera: &mut Camera,
+    controller: &mut FlyController,
+    input: &InputMap,
+    delta: f32,
+) {
+    let look = look_input(input, delta) * controller.look_sensitivity;
+    let clamp_val = Rad::from(controller.clamp_y).0;
+    controller.target_angles -= look;
+    controller.target_angles.y = controller.target_angles.y.clamp(-clamp_val, clamp_val);
+    controller.angles = controller.angles.lerp(
+        controller.target_angles,
+        catch_up_factor(controller.smoothing, delta),
+    );
+    camera.forward = forward_from_angles(controller.angles);
 
-        let clamp_val = Rad::from(cam.clamp_y).0;
-        cam.angles.y = cam.angles.y.clamp(-clamp_val, clamp_val);
+    let mut speed = controller.speed;
+    if input.is_pressed("sprint") {
+        speed *= controller.sprint_multiplier;
+    }
+    let right = camera.forward.cross(Vector3::unit_y()).normalize();
+    let forward = input.axis("move_forward").clamp(-1.0, 1.0);
+    let sideways = input.axis("move_right").clamp(-1.0, 1.0);
+    let up = input.axis("move_up").clamp(-1.0, 1.0);
 
-        let x = Rad(cam.angles.y).0.cos() * Rad(cam.angles.x).0.sin(); // x = cos(rad(CY)) * sin(rad(CX))
-        let y = Rad(cam.angles.y).0.sin(); // y = sin(rad(CY))
-        let z = Rad(cam.angles.y).0.cos() * Rad(cam.angles.x).0.cos(); // z = cos(rad(CY)) * cos(rad(CX))
+    transform.position += (camera.forward * forward + right * sideways) * (speed * delta)
+        + Vector3::unit_y() * (up * controller.vertical_speed * delta);
+}
 
-        cam.forward = Vector3::new(x, y, z).normalize();
+/// Orbit camera driven by the "look" action and the look, "zoom", "move_forward"
+/// and "move_right" axes.
+fn update_orbit(
+    transform: &mut Transform,
+    camera: &mut Camera,
+    controller: &mut OrbitController,
+    input: &InputMap,
+    delta: f32,
+) {
+    let look = look_input(input, delta) * controller.look_sensitivity;
+    let clamp_val = Rad::from(controller.clamp_y).0;
+    controller.angles -= look;
+    controller.angles.y = controller.angles.y.clamp(-clamp_val, clamp_val);
+    let forward = forward_from_angles(controller.angles);
+
+    let zoom = input.axis("zoom");
+    controller.distance = (controller.distance * (1.0 - controller.zoom_speed).powf(zoom))
+        .clamp(controller.min_distance, controller.max_distance);
+
+    // pan the target along the ground plane:
+    let planar_forward = Vector3::new(forward.x, 0.0, forward.z);
+    if !planar_forward.is_zero() {
+        let planar_forward = planar_forward.normalize();
+        let right = planar_forward.cross(Vector3::unit_y()).normalize();
+        let pan = planar_forward * input.axis("move_forward").clamp(-1.0, 1.0)
+            + right * input.axis("move_right").clamp(-1.0, 1.0);
+        controller.target += pan * (controller.pan_speed * delta);
     }
-    cam.prev = Vector2::new(cursor_pos.0, cursor_pos.1);
 
-    let left = cam.forward.cross(Vector3::unit_y()).normalize();
-    let mut eye = trans.position;
+    camera.forward = forward;
+    transform.position = controller.target - forward * controller.distance;
+}
 
-    if key_state.is_key_pressed(Key::W) {
-        eye += Vector3::from_value(cam.speed).mul_element_wise(cam.forward);
+fn update_follow(world: &mut World, delta: f32) {
+    let followers = <(Entity, &FollowController)>::query()
+        .iter(world)
+        .map(|(entity, controller)| (*entity, controller.clone()))
+        .collect::<Vec<_>>();
+
+    for (entity, controller) in followers {
+        let (target_position, target_rotation) = match world
+            .entry_ref(controller.target)
+            .ok()
+            .and_then(|entry| entry.get_component::<Transform>().ok().cloned())
+        {
+            Some(target) => (target.position, target.rotation),
+            None => continue,
+        };
+        let mut entry = match world.entry(entity) {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        let desired = target_position + target_rotation.rotate_vector(controller.offset);
+        let position = match entry.get_component_mut::<Transform>() {
+            Ok(transform) => {
+                transform.position = transform
+                    .position
+                    .lerp(desired, catch_up_factor(controller.stiffness, delta));
+                transform.position
+            }
+            Err(_) => continue,
+        };
+        if let Ok(camera) = entry.get_component_mut::<Camera>() {
+            let to_target = target_position - position;
+            if !to_target.is_zero() {
+                camera.forward = to_target.normalize();
+            }
+        }
     }
+}
 
-    if key_state.is_key_pressed(Key::A) {
-        eye -= Vector3::from_value(cam.speed).mul_element_wise(left);
+/// The mouse only looks around while the look action is held, the gamepad always.
+fn look_input(input: &InputMap, delta: f32) -> Vector2<f32> {
+    let mut look = Vector2::new(input.axis("pad_look_x"), input.axis("pad_look_y")) * delta;
+    if input.is_pressed("look") {
+        look += Vector2::new(input.axis("look_x"), input.axis("look_y"));
     }
+    look
+}
 
-    if key_state.is_key_pressed(Key::S) {
-        eye -= Vector3::from_value(cam.speed).mul_element_wise(cam.forward);
+/// Frame rate independent interpolation factor for an exponential approach with the given rate.
+#[inline]
+fn catch_up_factor(rate: f32, delta: f32) -> f32 {
+    if rate > 0.0 {
+        1.0 - (-rate * delta).exp()
+    } else {
+        1.0
     }
+}
 
-    if key_state.is_key_pressed(Key::D) {
-        eye += Vector3::from_value(cam.speed).mul_element_wise(left);
-    }
+/// Direction from yaw and pitch in radians.
+#[inline]
+pub fn forward_from_angles(angles: Vector2<f32>) -> Vector3<f32> {
+    let x = angles.y.cos() * angles.x.sin(); // x = cos(rad(CY)) * sin(rad(CX))
+    let y = angles.y.sin(); // y = sin(rad(CY))
+    let z = angles.y.cos() * angles.x.cos(); // z = cos(rad(CY)) * cos(rad(CX))
+    Vector3::new(x, y, z).normalize()
+}
+
+#[inline]
+pub fn view_projection_matrix(
+    aspect_ratio: f32,
+    trans: &Transform,
+    cam: &Camera,
+    depth_mode: DepthMode,
+) -> Matrix4<f32> {
+    projection_matrix(aspect_ratio, cam, depth_mode) * view_matrix(trans, cam)
+}
 
-    let at = eye + cam.forward;
+/// Projection into the wgpu clip space with the depth convention of the scene pass.
+/// Reverse-Z perspective projections ignore the far clip and reach to infinity.
+pub fn projection_matrix(aspect_ratio: f32, cam: &Camera, depth_mode: DepthMode) -> Matrix4<f32> {
+    match (depth_mode, cam.projection) {
+        (DepthMode::ReverseZ, Projection::Perspective { fov }) => {
+            infinite_reverse_z_perspective(fov, aspect_ratio, cam.near_clip)
+        }
+        (DepthMode::ReverseZ, projection) => {
+            REVERSE_Z_MATRIX
+                * CORRECTION_MATRIX
+                * projection.matrix(aspect_ratio, cam.near_clip, cam.far_clip)
+        }
+        (DepthMode::Standard, projection) => {
+            CORRECTION_MATRIX * projection.matrix(aspect_ratio, cam.near_clip, cam.far_clip)
+        }
+    }
+}
 
-    trans.position = eye;
+/// Perspective projection mapping the near plane to depth 1 and infinity to depth 0.
+#[rustfmt::skip]
+pub fn infinite_reverse_z_perspective(
+    fov: Deg<f32>,
+    aspect_ratio: f32,
+    near_clip: f32,
+) -> Matrix4<f32> {
+    let f = 1.0 / (Rad::from(fov).0 * 0.5).tan();
+    Matrix4::new(
+        f / aspect_ratio, 0.0, 0.0, 0.0,
+        0.0, f, 0.0, 0.0,
+        0.0, 0.0, 0.0, -1.0,
+        0.0, 0.0, near_clip, 0.0,
+    )
+}
 
-    let projection_matrix = perspective(cam.fov, aspect_ratio, cam.near_clip, cam.far_clip);
+/// Captures the cursor while the "look" action is held,
+/// so mouse look is neither bounded by the window nor visible.
+pub fn capture_cursor(input: &InputMap, cursor: &mut CursorControl) {
+    if input.is_pressed("look") {
+        cursor.mode = CursorMode::Disabled;
+    } else if cursor.mode == CursorMode::Disabled {
+        cursor.mode = CursorMode::Normal;
+    }
+}
 
-    let view_matrix = Matrix4::look_at_rh(
-        Point3::from_vec(eye),
-        Point3::from_vec(at),
+#[inline]
+pub fn view_matrix(trans: &Transform, cam: &Camera) -> Matrix4<f32> {
+    Matrix4::look_to_rh(
+        Point3::from_vec(trans.position),
+        cam.forward,
         Vector3::unit_y(),
-    );
-
-    CORRECTION_MATRIX * projection_matrix * view_matrix
+    )
 }
 
+/// Maps the OpenGL clip space depth range [-1, 1] to [0, 1].
 #[rustfmt::skip]
-#[allow(unused)]
-const CORRECTION_MATRIX: Matrix4<f32> = Matrix4::new(
+pub const CORRECTION_MATRIX: Matrix4<f32> = Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
     0.0, 0.0, 0.5, 0.0,
     0.0, 0.0, 0.5, 1.0,
 );
+
+/// Maps the depth range [0, 1] to [1, 0].
+#[rustfmt::skip]
+pub const REVERSE_Z_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, -1.0, 0.0,
+    0.0, 0.0, 1.0, 1.0,
+);
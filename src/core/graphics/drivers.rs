@@ -1,12 +1,17 @@
 use super::boot;
 use super::frame::Frame;
 use super::mipgen;
-use super::pipeline::{Pipeline, ShaderPipeline, ShaderPipelineDescriptor};
-use super::shader_compiler;
-use crate::config::{CoreConfig, GraphicsApi, MsaaMode};
+use super::pipeline::{CompiledShader, Pipeline, ShaderPipeline, ShaderPipelineDescriptor};
+use super::pipeline_cache::{CachedPipelineLayout, CachedShaderModule, PipelineCache};
+use super::pipeline_definition::PipelineDefinition;
+use super::push_constants::PushConstantEmulation;
+use super::shader_compiler::{ShaderCompiler, ShaderDefines, ShaderKind};
+use crate::config::{CoreConfig, DepthMode, MsaaMode};
 use log::info;
-use shaderc::{CompilationArtifact, Compiler as ShaderCompiler, ShaderKind};
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 use wgpu::*;
 
 pub struct Drivers {
@@ -18,11 +23,18 @@ pub struct Drivers {
     pub swap_chain: SwapChain,
     pub swap_chain_desc: SwapChainDescriptor,
     pub swap_chain_format: TextureFormat,
-    pub frame_buffer: TextureView,
-    pub depth_texture: TextureView,
     pub msaa_samples: MsaaMode,
+    pub depth_mode: DepthMode,
+    /// Uniform buffer replacement of push constants, if the adapter has none.
+    pub push_constant_emulation: Option<PushConstantEmulation>,
     pub shader_compiler: ShaderCompiler,
-    pub blit_shader: (ShaderModule, ShaderModule),
+    /// Shared render pipelines, layouts and samplers.
+    pub pipeline_cache: PipelineCache,
+    pub blit_shader: (CachedShaderModule, CachedShaderModule),
+    /// Held here so purging the pipeline cache keeps them, the pipelines are created per format.
+    pub blit_layout: CachedPipelineLayout,
+    pub blit_pipelines: RefCell<HashMap<TextureFormat, Arc<RenderPipeline>>>,
+    pub blit_sampler: Arc<Sampler>,
 }
 
 impl Drivers {
@@ -40,6 +52,12 @@ impl Drivers {
         ShaderPipeline::create_shader_bundle::<T>(self, desc)
     }
 
+    /// Creates the pipeline of `db/shaders/fixed_pipelines/<name>/pipeline.yaml`.
+    pub fn load_shader_pipeline(&mut self, name: &str) -> ShaderPipeline {
+        let definition = PipelineDefinition::load(name).unwrap_or_else(|error| panic!("{}", error));
+        definition.create(self, &[])
+    }
+
     pub fn begin_frame(&self) -> Frame {
         let view = self
             .swap_chain
@@ -53,49 +71,63 @@ impl Drivers {
             view,
             encoder,
             queue: &self.queue,
-            frame_buf: &self.frame_buffer,
-            depth_stencil: &self.depth_texture,
-            samples: self.msaa_samples,
+            width: self.swap_chain_desc.width,
+            height: self.swap_chain_desc.height,
+            push_constants: self.push_constant_emulation.as_ref(),
         }
     }
 
-    pub fn compile_shader_raw(&mut self, path: PathBuf, kind: ShaderKind) -> CompilationArtifact {
-        shader_compiler::compile_to_bytecode(self, path, kind)
+    pub fn compile_shader_raw(
+        &mut self,
+        path: &Path,
+        kind: ShaderKind,
+        defines: &ShaderDefines,
+    ) -> Vec<u8> {
+        self.shader_compiler.compile(path, kind, defines)
     }
 
-    pub fn compile_and_create_shader(&mut self, path: PathBuf, kind: ShaderKind) -> ShaderModule {
-        let code = self.compile_shader_raw(path, kind);
-        // bug in wgpu - shader validation fails on vertex shaders with push constants
-        let flags = if kind == ShaderKind::Vertex {
+    pub fn create_shader_module(
+        &self,
+        shader: &CompiledShader,
+        kind: ShaderKind,
+    ) -> CachedShaderModule {
+        // bug in wgpu - shader validation fails on vertex shaders with push constants,
+        // the reflection already validated them against the pipeline layout
+        let flags = if kind == ShaderKind::Vertex && shader.reflection.push_constants.is_some() {
             ShaderFlags::default()
         } else {
             ShaderFlags::VALIDATION
         };
         let desc = ShaderModuleDescriptor {
             label: None,
-            source: util::make_spirv(code.as_binary_u8()),
+            source: util::make_spirv(&shader.bytecode[..]),
             flags,
         };
-        self.device.create_shader_module(&desc)
+        self.pipeline_cache.shader_module(&self.device, &desc)
     }
 
-    pub fn initialize(window: &glfw::Window, config: &CoreConfig) -> Self {
-        let backend_bit = match config.graphics_config.backend_api {
-            GraphicsApi::Auto => BackendBit::PRIMARY,
-            GraphicsApi::Direct3D11 => BackendBit::DX11,
-            GraphicsApi::Direct3D12 => BackendBit::DX12,
-            GraphicsApi::OpenGl => BackendBit::GL,
-            GraphicsApi::Vulkan => BackendBit::VULKAN,
-            GraphicsApi::WebGpu => BackendBit::BROWSER_WEBGPU,
+    pub fn create_wgsl_module(&self, source: String) -> CachedShaderModule {
+        let desc = ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Wgsl(source.into()),
+            flags: ShaderFlags::VALIDATION,
         };
-        let instance = Instance::new(backend_bit);
-        let surface = unsafe { instance.create_surface(window) };
-        let (adapter, device, queue) = futures::executor::block_on(boot::create_async_resources(
-            &instance,
-            &surface,
+        self.pipeline_cache.shader_module(&self.device, &desc)
+    }
+
+    pub fn initialize(window: &glfw::Window, config: &CoreConfig) -> Self {
+        let boot::GpuContext {
+            instance,
+            surface,
+            adapter,
+            device,
+            queue,
+            push_constants,
+        } = boot::create_gpu_context(
+            window,
             config.application_config.power_safe_mode,
             &config.graphics_config,
-        ));
+        );
 
         let info = adapter.get_info();
 
@@ -113,11 +145,7 @@ impl Drivers {
             format: swap_chain_format,
             width: window.get_framebuffer_size().0 as _,
             height: window.get_framebuffer_size().1 as _,
-            present_mode: if config.display_config.vsync {
-                PresentMode::Fifo
-            } else {
-                PresentMode::Mailbox
-            },
+            present_mode: present_mode(config.display_config.vsync),
         };
 
         info!("Swap chain usage: {:#?}", swap_chain_desc.usage);
@@ -132,35 +160,18 @@ impl Drivers {
 
         let swap_chain = device.create_swap_chain(&surface, &swap_chain_desc);
 
-        let frame_buffer = boot::create_multi_sampled_framebuffer(
-            &device,
-            &swap_chain_desc,
-            config.graphics_config.msaa_mode as u32,
+        let mut shader_compiler = ShaderCompiler::new(config.graphics_config.shader_optimization);
+        let defines = ShaderDefines::default();
+        let vs_bytecode = shader_compiler.compile(
+            Path::new(mipgen::BLIT_VS_PATH),
+            ShaderKind::Vertex,
+            &defines,
+        );
+        let fs_bytecode = shader_compiler.compile(
+            Path::new(mipgen::BLIT_FS_PATH),
+            ShaderKind::Fragment,
+            &defines,
         );
-
-        let depth_texture = device
-            .create_texture(&TextureDescriptor {
-                size: Extent3d {
-                    width: swap_chain_desc.width,
-                    height: swap_chain_desc.height,
-                    depth: 1,
-                },
-                mip_level_count: 1,
-                sample_count: config.graphics_config.msaa_mode as u32,
-                dimension: TextureDimension::D2,
-                format: boot::DEPTH_FORMAT,
-                usage: TextureUsage::RENDER_ATTACHMENT,
-                label: None,
-            })
-            .create_view(&TextureViewDescriptor::default());
-
-        let vs_bytecode_path = "db/shaders/mipgen/final/blit.vert.spv";
-        let fs_bytecode_path = "db/shaders/mipgen/final/blit.frag.spv";
-
-        let vs_bytecode = std::fs::read(&vs_bytecode_path)
-            .unwrap_or_else(|_| panic!("Failed to load vertex shader: {:?}", vs_bytecode_path));
-        let fs_bytecode = std::fs::read(&fs_bytecode_path)
-            .unwrap_or_else(|_| panic!("Failed to fragment shader: {:?}", fs_bytecode_path));
 
         let vs_module_desc = ShaderModuleDescriptor {
             label: None,
@@ -173,11 +184,18 @@ impl Drivers {
             flags: ShaderFlags::VALIDATION,
         };
 
-        let vs_module = device.create_shader_module(&vs_module_desc);
-        let fs_module = device.create_shader_module(&fs_module_desc);
+        let pipeline_cache = PipelineCache::default();
+        let vs_module = pipeline_cache.shader_module(&device, &vs_module_desc);
+        let fs_module = pipeline_cache.shader_module(&device, &fs_module_desc);
         let blit_shader = (vs_module, fs_module);
+        let blit_layout = mipgen::blit_layout(&device, &pipeline_cache);
+        let blit_sampler = mipgen::blit_sampler(&device, &pipeline_cache);
 
-        let shader_compiler = shaderc::Compiler::new().expect("Failed to create shader compiler!");
+        let push_constant_emulation = if push_constants {
+            None
+        } else {
+            Some(PushConstantEmulation::new(&device))
+        };
 
         Self {
             instance,
@@ -188,14 +206,43 @@ impl Drivers {
             swap_chain,
             swap_chain_desc,
             swap_chain_format,
-            frame_buffer,
-            depth_texture,
             msaa_samples: config.graphics_config.msaa_mode,
+            depth_mode: config.graphics_config.depth_mode,
+            push_constant_emulation,
             shader_compiler,
+            pipeline_cache,
             blit_shader,
+            blit_layout,
+            blit_pipelines: RefCell::new(HashMap::new()),
+            blit_sampler,
         }
     }
 
+    /// Recreates the swap chain if the size or the present mode changed.
+    pub fn reconfigure_swap_chain(&mut self, width: u32, height: u32, vsync: bool) {
+        let present_mode = present_mode(vsync);
+        // an iconified window has no framebuffer:
+        if width == 0 || height == 0 {
+            return;
+        }
+        if self.swap_chain_desc.width == width
+            && self.swap_chain_desc.height == height
+            && self.swap_chain_desc.present_mode == present_mode
+        {
+            return;
+        }
+        self.swap_chain_desc.width = width;
+        self.swap_chain_desc.height = height;
+        self.swap_chain_desc.present_mode = present_mode;
+        info!(
+            "Recreating swap chain: {}x{}, present mode: {:?}",
+            width, height, present_mode
+        );
+        self.swap_chain = self
+            .device
+            .create_swap_chain(&self.surface, &self.swap_chain_desc);
+    }
+
     #[inline]
     pub fn generate_mipmaps(
         &self,
@@ -207,3 +254,11 @@ impl Drivers {
         mipgen::generate_mipmaps(self, encoder, texture, format, mip_count);
     }
 }
+
+fn present_mode(vsync: bool) -> PresentMode {
+    if vsync {
+        PresentMode::Fifo
+    } else {
+        PresentMode::Mailbox
+    }
+}
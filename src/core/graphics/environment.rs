@@ -0,0 +1,564 @@
+use super::boot::HDR_FORMAT;
+use super::drivers::Drivers;
+use super::pass::Pass;
+use super::pipeline::Pipeline;
+use super::pipelines::ibl::{self, BRDF_LUT_FORMAT};
+use super::push_constants::PushConstantEmulation;
+use crate::config::{CoreConfig, EnvironmentConfig};
+use bytemuck::{Pod, Zeroable};
+use cgmath::Vector4;
+use log::{info, warn};
+use std::io::BufReader;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::Arc;
+use wgpu::*;
+
+pub const IRRADIANCE_RESOLUTION: u32 = 32;
+pub const SPECULAR_RESOLUTION: u32 = 128;
+pub const SPECULAR_MIP_LEVELS: u32 = 5;
+pub const BRDF_LUT_RESOLUTION: u32 = 256;
+
+/// Size of the procedural sky used when no environment map is configured.
+pub const FALLBACK_SKY_RESOLUTION: (u32, u32) = (128, 64);
+
+pub const ENVIRONMENT_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[
+    BindGroupLayoutEntry {
+        binding: 0,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 1,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::Cube,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 2,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::Cube,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 3,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 4,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Sampler {
+            comparison: false,
+            filtering: true,
+        },
+        count: None,
+    },
+];
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct EnvironmentUniforms {
+    /// x: intensity, y: max specular mip level
+    pub params: Vector4<f32>,
+}
+
+unsafe impl Pod for EnvironmentUniforms {}
+unsafe impl Zeroable for EnvironmentUniforms {}
+
+/// Image based lighting: the environment cubemap with its diffuse irradiance,
+/// prefiltered specular mip chain and the split sum brdf lookup table.
+pub struct Environment {
+    pub config: EnvironmentConfig,
+    pub environment_texture: Texture,
+    pub irradiance_texture: Texture,
+    pub specular_texture: Texture,
+    pub brdf_lut: Texture,
+    pub sampler: Arc<Sampler>,
+    pub uniform_buffer: Buffer,
+    pub bind_group_layout: Arc<BindGroupLayout>,
+    pub bind_group: BindGroup,
+}
+
+impl Environment {
+    pub fn new(drivers: &mut Drivers, config: &CoreConfig) -> Self {
+        let mut env_config = config.graphics_config.environment.clone();
+        env_config.resolution = env_config.resolution.clamp(16, 2048).next_power_of_two();
+        let mip_levels = (env_config.resolution as f32).log2() as u32 + 1;
+        info!(
+            "Creating environment lighting: {}x{} with {} mips",
+            env_config.resolution, env_config.resolution, mip_levels
+        );
+
+        let (width, height, texels) = env_config
+            .map
+            .as_ref()
+            .and_then(|path| load_equirect(Path::new(path)))
+            .unwrap_or_else(procedural_sky);
+
+        let source_extent = Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let source_texture = drivers.device.create_texture(&TextureDescriptor {
+            label: Some("environment source"),
+            size: source_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+        });
+        drivers.queue.write_texture(
+            TextureCopyView {
+                texture: &source_texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+            },
+            bytemuck::cast_slice(&texels),
+            TextureDataLayout {
+                offset: 0,
+                bytes_per_row: 8 * width,
+                rows_per_image: 0,
+            },
+            source_extent,
+        );
+        let source_view = source_texture.create_view(&TextureViewDescriptor::default());
+
+        let equirect_pipeline = ibl::EquirectToCubePipeline::create(drivers, config);
+        let irradiance_pipeline = ibl::IrradiancePipeline::create(drivers, config);
+        let prefilter_pipeline = ibl::PrefilterPipeline::create(drivers, config);
+        let brdf_lut_pipeline = drivers.load_shader_pipeline("brdflut");
+
+        let equirect_sampler = drivers.pipeline_cache.sampler(
+            &drivers.device,
+            &SamplerDescriptor {
+                label: Some("equirect"),
+                address_mode_u: AddressMode::Repeat,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Nearest,
+                ..Default::default()
+            },
+        );
+
+        let sampler = drivers.pipeline_cache.sampler(
+            &drivers.device,
+            &SamplerDescriptor {
+                label: Some("environment"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Linear,
+                ..Default::default()
+            },
+        );
+
+        let environment_texture = create_cube_texture(
+            &drivers.device,
+            "environment",
+            env_config.resolution,
+            mip_levels,
+        );
+        let irradiance_texture =
+            create_cube_texture(&drivers.device, "irradiance", IRRADIANCE_RESOLUTION, 1);
+        let specular_texture = create_cube_texture(
+            &drivers.device,
+            "specular",
+            SPECULAR_RESOLUTION,
+            SPECULAR_MIP_LEVELS,
+        );
+        let brdf_lut = drivers.device.create_texture(&TextureDescriptor {
+            label: Some("brdf lut"),
+            size: Extent3d {
+                width: BRDF_LUT_RESOLUTION,
+                height: BRDF_LUT_RESOLUTION,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: BRDF_LUT_FORMAT,
+            usage: TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT,
+        });
+
+        let environment_view = create_cube_view(&environment_texture);
+        let irradiance_view = create_cube_view(&irradiance_texture);
+        let specular_view = create_cube_view(&specular_texture);
+        let brdf_lut_view = brdf_lut.create_view(&TextureViewDescriptor::default());
+
+        let source_bind_group = create_input_bind_group(
+            &drivers.device,
+            &equirect_pipeline,
+            &source_view,
+            &equirect_sampler,
+        );
+        let environment_bind_group = create_input_bind_group(
+            &drivers.device,
+            &irradiance_pipeline,
+            &environment_view,
+            &sampler,
+        );
+        let prefilter_bind_group = create_input_bind_group(
+            &drivers.device,
+            &prefilter_pipeline,
+            &environment_view,
+            &sampler,
+        );
+
+        let mut encoder = drivers
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("environment"),
+            });
+
+        // every mip is projected from the source directly:
+        render_cube_faces(
+            &mut encoder,
+            &environment_texture,
+            mip_levels,
+            &equirect_pipeline,
+            Some(&source_bind_group),
+            drivers.push_constant_emulation.as_ref(),
+            |face, _| Vector4::new(face as f32, 0.0, 0.0, 0.0),
+        );
+        render_cube_faces(
+            &mut encoder,
+            &irradiance_texture,
+            1,
+            &irradiance_pipeline,
+            Some(&environment_bind_group),
+            drivers.push_constant_emulation.as_ref(),
+            |face, _| Vector4::new(face as f32, 0.0, 0.0, 0.0),
+        );
+        let resolution = env_config.resolution as f32;
+        render_cube_faces(
+            &mut encoder,
+            &specular_texture,
+            SPECULAR_MIP_LEVELS,
+            &prefilter_pipeline,
+            Some(&prefilter_bind_group),
+            drivers.push_constant_emulation.as_ref(),
+            |face, mip| {
+                let roughness = mip as f32 / (SPECULAR_MIP_LEVELS - 1) as f32;
+                Vector4::new(face as f32, roughness, resolution, 0.0)
+            },
+        );
+        {
+            let render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("brdf lut"),
+                color_attachments: &[RenderPassColorAttachmentDescriptor {
+                    attachment: &brdf_lut_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            let mut pass = Pass::new(render_pass, drivers.push_constant_emulation.as_ref());
+            pass.set_shader_pipeline(&brdf_lut_pipeline);
+            pass.draw_fullscreen_triangle();
+        }
+        if let Some(push_constants) = &drivers.push_constant_emulation {
+            push_constants.upload(&drivers.queue);
+        }
+        drivers.queue.submit(Some(encoder.finish()));
+
+        let uniform_buffer = drivers.device.create_buffer(&BufferDescriptor {
+            label: Some("environment"),
+            size: std::mem::size_of::<EnvironmentUniforms>() as BufferAddress,
+            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniforms = EnvironmentUniforms {
+            params: Vector4::new(
+                env_config.intensity,
+                (SPECULAR_MIP_LEVELS - 1) as f32,
+                0.0,
+                0.0,
+            ),
+        };
+        drivers
+            .queue
+            .write_buffer(&uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let bind_group_layout = drivers
+            .pipeline_cache
+            .bind_group_layout(&drivers.device, ENVIRONMENT_BIND_GROUP_LAYOUT_ENTRIES);
+
+        let bind_group = drivers.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("environment"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&irradiance_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&specular_view),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&brdf_lut_view),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            config: env_config,
+            environment_texture,
+            irradiance_texture,
+            specular_texture,
+            brdf_lut,
+            sampler,
+            uniform_buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+}
+
+fn create_cube_texture(device: &Device, label: &str, resolution: u32, mip_levels: u32) -> Texture {
+    device.create_texture(&TextureDescriptor {
+        label: Some(label),
+        size: Extent3d {
+            width: resolution,
+            height: resolution,
+            depth: 6,
+        },
+        mip_level_count: mip_levels,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: HDR_FORMAT,
+        usage: TextureUsage::SAMPLED | TextureUsage::RENDER_ATTACHMENT,
+    })
+}
+
+fn create_cube_view(texture: &Texture) -> TextureView {
+    texture.create_view(&TextureViewDescriptor {
+        label: None,
+        dimension: Some(TextureViewDimension::Cube),
+        ..Default::default()
+    })
+}
+
+fn create_input_bind_group<T: Pipeline>(
+    device: &Device,
+    pipeline: &T,
+    view: &TextureView,
+    sampler: &Sampler,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        label: Some(T::NAME),
+        layout: &pipeline.shader_pipeline().per_material_bind_group_layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+/// Draws a fullscreen pass into every face and mip of the cubemap.
+/// The push constant parameters are computed per face and mip.
+fn render_cube_faces<T: Pipeline, F: Fn(u32, u32) -> Vector4<f32>>(
+    encoder: &mut CommandEncoder,
+    texture: &Texture,
+    mip_levels: u32,
+    pipeline: &T,
+    bind_group: Option<&BindGroup>,
+    push_constants: Option<&PushConstantEmulation>,
+    params: F,
+) {
+    for mip in 0..mip_levels {
+        for face in 0..6 {
+            let view = texture.create_view(&TextureViewDescriptor {
+                label: Some("cube face"),
+                format: None,
+                dimension: Some(TextureViewDimension::D2),
+                aspect: TextureAspect::All,
+                base_mip_level: mip,
+                level_count: NonZeroU32::new(1),
+                base_array_layer: face,
+                array_layer_count: NonZeroU32::new(1),
+            });
+            let render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("cube face"),
+                color_attachments: &[RenderPassColorAttachmentDescriptor {
+                    attachment: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            let mut pass = Pass::new(render_pass, push_constants);
+            pass.set_pipeline(pipeline);
+            if let Some(bind_group) = bind_group {
+                pass.set_bind_group(0, bind_group);
+            }
+            let push_constant_data = ibl::PushConstantData {
+                params: params(face, mip),
+            };
+            pass.set_push_constans(
+                ShaderStage::FRAGMENT,
+                0,
+                bytemuck::bytes_of(&push_constant_data),
+            );
+            pass.draw_fullscreen_triangle();
+        }
+    }
+}
+
+/// Loads an equirectangular environment map as half float rgba texels.
+/// Radiance (.hdr) files keep their range, other images are converted from srgb.
+fn load_equirect(path: &Path) -> Option<(u32, u32, Vec<u16>)> {
+    info!("Loading environment map: {:?}", path);
+    let is_radiance = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some(ext) if ext.eq_ignore_ascii_case("hdr")
+    );
+    let result = if is_radiance {
+        std::fs::File::open(path)
+            .map_err(image::ImageError::IoError)
+            .and_then(|file| image::codecs::hdr::HdrDecoder::new(BufReader::new(file)))
+            .and_then(|decoder| {
+                let meta = decoder.metadata();
+                let pixels = decoder.read_image_hdr()?;
+                let mut texels = Vec::with_capacity(pixels.len() * 4);
+                for p in pixels.iter() {
+                    texels.extend_from_slice(&[
+                        f16_bits(p[0]),
+                        f16_bits(p[1]),
+                        f16_bits(p[2]),
+                        f16_bits(1.0),
+                    ]);
+                }
+                Ok((meta.width, meta.height, texels))
+            })
+    } else {
+        image::open(path).map(|image| {
+            let image = image.into_rgba8();
+            let mut texels = Vec::with_capacity((image.width() * image.height() * 4) as usize);
+            for p in image.pixels() {
+                texels.extend_from_slice(&[
+                    f16_bits(srgb_to_linear(p[0])),
+                    f16_bits(srgb_to_linear(p[1])),
+                    f16_bits(srgb_to_linear(p[2])),
+                    f16_bits(1.0),
+                ]);
+            }
+            (image.width(), image.height(), texels)
+        })
+    };
+    match result {
+        Ok(source) => Some(source),
+        Err(error) => {
+            warn!(
+                "Failed to load environment map {:?}: {}, using procedural sky",
+                path, error
+            );
+            None
+        }
+    }
+}
+
+/// Simple gradient sky with a darker ground, used without an environment map.
+fn procedural_sky() -> (u32, u32, Vec<u16>) {
+    let (width, height) = FALLBACK_SKY_RESOLUTION;
+    let zenith = [0.25, 0.45, 0.85];
+    let horizon = [0.85, 0.9, 1.0];
+    let ground = [0.25, 0.22, 0.2];
+    let mut texels = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        // 1 at the zenith, -1 at the nadir:
+        let elevation = 1.0 - 2.0 * (y as f32 + 0.5) / height as f32;
+        let color = if elevation >= 0.0 {
+            let t = elevation.powf(0.5);
+            [
+                horizon[0] + (zenith[0] - horizon[0]) * t,
+                horizon[1] + (zenith[1] - horizon[1]) * t,
+                horizon[2] + (zenith[2] - horizon[2]) * t,
+            ]
+        } else {
+            ground
+        };
+        for _ in 0..width {
+            texels.extend_from_slice(&[
+                f16_bits(color[0]),
+                f16_bits(color[1]),
+                f16_bits(color[2]),
+                f16_bits(1.0),
+            ]);
+        }
+    }
+    (width, height, texels)
+}
+
+#[inline]
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts to half float bits, flushing denormals to zero and clamping to infinity.
+fn f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    if value.is_nan() {
+        0x7e00
+    } else if exponent <= 0 {
+        sign
+    } else if exponent >= 31 {
+        sign | 0x7c00
+    } else {
+        sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16
+    }
+}
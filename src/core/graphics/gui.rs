@@ -1,3 +1,4 @@
+use super::pipeline_cache::{CachedRenderPipelineDescriptor, PipelineCache};
 use imgui::{
     Context, DrawCmd::Elements, DrawData, DrawIdx, DrawList, DrawVert, TextureId, Textures,
 };
@@ -5,6 +6,7 @@ use smallvec::SmallVec;
 use std::error::Error;
 use std::fmt;
 use std::mem::size_of;
+use std::sync::Arc;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::*;
 
@@ -122,22 +124,6 @@ impl Texture {
         // Extract the texture view.
         let view = texture.create_view(&TextureViewDescriptor::default());
 
-        // Create the texture sampler.
-        let sampler = device.create_sampler(&SamplerDescriptor {
-            label: Some("imgui-wgpu sampler"),
-            address_mode_u: AddressMode::ClampToEdge,
-            address_mode_v: AddressMode::ClampToEdge,
-            address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: FilterMode::Linear,
-            min_filter: FilterMode::Linear,
-            mipmap_filter: FilterMode::Linear,
-            lod_min_clamp: -100.0,
-            lod_max_clamp: 100.0,
-            compare: None,
-            anisotropy_clamp: None,
-            border_color: None,
-        });
-
         // Create the texture bind group from the layout.
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: config.label,
@@ -149,7 +135,7 @@ impl Texture {
                 },
                 BindGroupEntry {
                     binding: 1,
-                    resource: BindingResource::Sampler(&sampler),
+                    resource: BindingResource::Sampler(&renderer.sampler),
                 },
             ],
         });
@@ -279,24 +265,61 @@ impl RendererConfig<'_, '_> {
     }
 }
 
+/// Matrix uniform of the vertex shader.
+const UNIFORM_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[BindGroupLayoutEntry {
+    binding: 0,
+    visibility: wgpu::ShaderStage::VERTEX,
+    ty: BindingType::Buffer {
+        ty: BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+    },
+    count: None,
+}];
+
+/// Texture and sampler of the draw commands.
+const TEXTURE_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[
+    BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStage::FRAGMENT,
+        ty: BindingType::Sampler {
+            comparison: false,
+            filtering: true,
+        },
+        count: None,
+    },
+];
+
 pub struct Renderer {
-    pipeline: RenderPipeline,
+    pipeline: Arc<RenderPipeline>,
     uniform_buffer: Buffer,
     uniform_bind_group: BindGroup,
     /// Textures of the font atlas and all images.
     pub textures: Textures<Texture>,
-    texture_layout: BindGroupLayout,
+    texture_layout: Arc<BindGroupLayout>,
+    sampler: Arc<Sampler>,
     index_buffers: SmallVec<[Buffer; 4]>,
     vertex_buffers: SmallVec<[Buffer; 4]>,
     config: RendererConfig<'static, 'static>,
 }
 
 impl Renderer {
-    /// Create an entirely new imgui wgpu renderer.
+    /// Create an entirely new imgui wgpu renderer, sharing GPU objects through the `cache`.
     pub fn new(
         imgui: &mut Context,
         device: &Device,
         queue: &Queue,
+        cache: &PipelineCache,
         config: RendererConfig,
     ) -> Self {
         let RendererConfig {
@@ -308,8 +331,8 @@ impl Renderer {
         } = config;
 
         // Load shaders.
-        let vs_module = device.create_shader_module(&vertex_shader.unwrap());
-        let fs_module = device.create_shader_module(&fragment_shader.unwrap());
+        let vs_module = cache.shader_module(device, &vertex_shader.unwrap());
+        let fs_module = cache.shader_module(device, &fragment_shader.unwrap());
 
         // Create the uniform matrix buffer.
         let size = 64;
@@ -320,97 +343,78 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
-        // Create the uniform matrix buffer bind group layout.
-        let uniform_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStage::VERTEX,
-                ty: BindingType::Buffer {
-                    ty: BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
+        // Create the render pipeline layout.
+        let pipeline_layout = cache.pipeline_layout(
+            device,
+            &[
+                UNIFORM_BIND_GROUP_LAYOUT_ENTRIES,
+                TEXTURE_BIND_GROUP_LAYOUT_ENTRIES,
+            ],
+            &[],
+        );
+        let uniform_layout = &pipeline_layout.bind_group_layouts[0];
+        let texture_layout = pipeline_layout.bind_group_layouts[1].clone();
+
+        // Create the texture sampler shared by all textures.
+        let sampler = cache.sampler(
+            device,
+            &SamplerDescriptor {
+                label: Some("imgui-wgpu sampler"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Linear,
+                lod_min_clamp: -100.0,
+                lod_max_clamp: 100.0,
+                compare: None,
+                anisotropy_clamp: None,
+                border_color: None,
+            },
+        );
 
         // Create the uniform matrix buffer bind group.
         let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some("imgui-wgpu bind group"),
-            layout: &uniform_layout,
+            layout: uniform_layout,
             entries: &[BindGroupEntry {
                 binding: 0,
                 resource: uniform_buffer.as_entire_binding(),
             }],
         });
 
-        // Create the texture layout for further usage.
-        let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("imgui-wgpu bind group layout"),
-            entries: &[
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        sample_type: TextureSampleType::Float { filterable: true },
-                        view_dimension: TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStage::FRAGMENT,
-                    ty: BindingType::Sampler {
-                        comparison: false,
-                        filtering: true,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        // Create the render pipeline layout.
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("imgui-wgpu pipeline layout"),
-            bind_group_layouts: &[&uniform_layout, &texture_layout],
-            push_constant_ranges: &[],
-        });
-
-        // Create the render pipeline.
         // Create the render pipeline.
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("imgui-wgpu pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: VertexState {
-                module: &vs_module,
-                entry_point: "main",
-                buffers: &[VertexBufferLayout {
+        let pipeline = cache.render_pipeline(
+            device,
+            &CachedRenderPipelineDescriptor {
+                label: Some("imgui-wgpu pipeline"),
+                layout: &pipeline_layout,
+                vertex_module: &vs_module,
+                vertex_entry: "main",
+                fragment_module: &fs_module,
+                fragment_entry: "main",
+                vertex_buffers: &[VertexBufferLayout {
                     array_stride: size_of::<DrawVert>() as BufferAddress,
                     step_mode: InputStepMode::Vertex,
                     attributes: &vertex_attr_array![0 => Float2, 1 => Float2, 2 => Uchar4Norm],
                 }],
-            },
-            primitive: PrimitiveState {
-                topology: PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: FrontFace::Cw,
-                cull_mode: CullMode::None,
-                polygon_mode: PolygonMode::Fill,
-            },
-            depth_stencil: depth_format.map(|format| DepthStencilState {
-                format,
-                depth_write_enabled: false,
-                depth_compare: wgpu::CompareFunction::Always,
-                stencil: wgpu::StencilState::default(),
-                bias: DepthBiasState::default(),
-                clamp_depth: false,
-            }),
-            multisample: MultisampleState::default(),
-            fragment: Some(FragmentState {
-                module: &fs_module,
-                entry_point: "main",
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: CullMode::None,
+                    polygon_mode: PolygonMode::Fill,
+                },
+                depth_stencil: depth_format.map(|format| DepthStencilState {
+                    format,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: DepthBiasState::default(),
+                    clamp_depth: false,
+                }),
+                multisample: MultisampleState::default(),
                 targets: &[ColorTargetState {
                     format: texture_format,
                     alpha_blend: BlendState {
@@ -425,8 +429,8 @@ impl Renderer {
                     },
                     write_mask: ColorWrite::ALL,
                 }],
-            }),
-        });
+            },
+        );
 
         let mut renderer = Self {
             pipeline,
@@ -434,6 +438,7 @@ impl Renderer {
             uniform_bind_group,
             textures: Textures::new(),
             texture_layout,
+            sampler,
             vertex_buffers: SmallVec::new(),
             index_buffers: SmallVec::new(),
             config: RendererConfig {
@@ -0,0 +1,407 @@
+use super::boot::DEPTH_FORMAT;
+use super::camera::{self, CORRECTION_MATRIX};
+use super::drivers::Drivers;
+use crate::components::{Camera, Light, LightKind, Transform};
+use crate::config::ShadowConfig;
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+use legion::{IntoQuery, World};
+use log::{info, warn};
+use smallvec::SmallVec;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use wgpu::*;
+
+pub const MAX_CASCADES: usize = 4;
+pub const MAX_SPOT_LIGHTS: usize = 4;
+pub const MAX_SHADOW_LAYERS: usize = MAX_CASCADES + MAX_SPOT_LIGHTS;
+
+/// Extra depth range in front of each cascade, so casters outside the view still cast shadows.
+pub const CASCADE_CASTER_MARGIN: f32 = 20.0;
+pub const SPOT_SHADOW_NEAR_CLIP: f32 = 0.05;
+
+pub const LIGHTING_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[
+    BindGroupLayoutEntry {
+        binding: 0,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 1,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Depth,
+            view_dimension: TextureViewDimension::D2Array,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 2,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Sampler {
+            comparison: true,
+            filtering: true,
+        },
+        count: None,
+    },
+];
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SpotLightData {
+    pub position_range: Vector4<f32>,
+    pub direction_cutoff: Vector4<f32>,
+    /// w: shadow map layer or -1 if the light casts no shadows
+    pub color_layer: Vector4<f32>,
+}
+
+unsafe impl Pod for SpotLightData {}
+unsafe impl Zeroable for SpotLightData {}
+
+/// Must match the `Lighting` uniform block in the surface shaders (std140).
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct LightingUniforms {
+    pub shadow_matrices: [Matrix4<f32>; MAX_SHADOW_LAYERS],
+    pub cascade_splits: Vector4<f32>,
+    pub camera_position: Vector4<f32>,
+    pub camera_forward: Vector4<f32>,
+    /// w: 1 if the directional light casts shadows
+    pub directional_direction: Vector4<f32>,
+    /// w: cascade count
+    pub directional_color: Vector4<f32>,
+    /// w: spot light count
+    pub ambient_color: Vector4<f32>,
+    /// x: texel size, y: pcf radius, z: depth bias
+    pub shadow_params: Vector4<f32>,
+    pub spot_lights: [SpotLightData; MAX_SPOT_LIGHTS],
+}
+
+unsafe impl Pod for LightingUniforms {}
+unsafe impl Zeroable for LightingUniforms {}
+
+/// A shadow map layer which has to be rendered this frame.
+#[derive(Copy, Clone)]
+pub struct ShadowCaster {
+    pub layer: usize,
+    pub view_proj_matrix: Matrix4<f32>,
+}
+
+pub struct Lighting {
+    pub config: ShadowConfig,
+    pub ambient_color: Vector3<f32>,
+    pub shadow_texture: Texture,
+    pub layer_views: Vec<TextureView>,
+    pub array_view: TextureView,
+    pub sampler: Arc<Sampler>,
+    pub uniform_buffer: Buffer,
+    pub bind_group_layout: Arc<BindGroupLayout>,
+    pub bind_group: BindGroup,
+    pub casters: SmallVec<[ShadowCaster; MAX_SHADOW_LAYERS]>,
+}
+
+impl Lighting {
+    pub fn new(drivers: &Drivers, config: &ShadowConfig) -> Self {
+        let device = &drivers.device;
+        let config = ShadowConfig {
+            resolution: config.resolution.clamp(256, 8192),
+            cascade_count: config.cascade_count.clamp(1, MAX_CASCADES as u8),
+            ..*config
+        };
+        info!(
+            "Creating shadow maps: {}x{} with {} layers",
+            config.resolution, config.resolution, MAX_SHADOW_LAYERS
+        );
+
+        let shadow_texture = device.create_texture(&TextureDescriptor {
+            label: Some("shadow map"),
+            size: Extent3d {
+                width: config.resolution,
+                height: config.resolution,
+                depth: MAX_SHADOW_LAYERS as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::SAMPLED,
+        });
+
+        let layer_views = (0..MAX_SHADOW_LAYERS as u32)
+            .map(|layer| {
+                shadow_texture.create_view(&TextureViewDescriptor {
+                    label: Some("shadow map layer"),
+                    format: None,
+                    dimension: Some(TextureViewDimension::D2),
+                    aspect: TextureAspect::All,
+                    base_mip_level: 0,
+                    level_count: None,
+                    base_array_layer: layer,
+                    array_layer_count: NonZeroU32::new(1),
+                })
+            })
+            .collect();
+
+        let array_view = shadow_texture.create_view(&TextureViewDescriptor {
+            label: Some("shadow map array"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let sampler = drivers.pipeline_cache.sampler(
+            device,
+            &SamplerDescriptor {
+                label: Some("shadow"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Nearest,
+                compare: Some(CompareFunction::LessEqual),
+                ..Default::default()
+            },
+        );
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("lighting"),
+            size: std::mem::size_of::<LightingUniforms>() as BufferAddress,
+            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = drivers
+            .pipeline_cache
+            .bind_group_layout(device, LIGHTING_BIND_GROUP_LAYOUT_ENTRIES);
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("lighting"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&array_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            config,
+            ambient_color: Vector3::from_value(0.25),
+            shadow_texture,
+            layer_views,
+            array_view,
+            sampler,
+            uniform_buffer,
+            bind_group_layout,
+            bind_group,
+            casters: SmallVec::new(),
+        }
+    }
+
+    /// Gathers all lights, computes the shadow matrices and uploads the lighting uniforms.
+    pub fn update(
+        &mut self,
+        queue: &Queue,
+        world: &World,
+        camera: Option<&(Transform, Camera)>,
+        aspect_ratio: f32,
+    ) {
+        let mut uniforms = LightingUniforms {
+            shadow_matrices: [Matrix4::identity(); MAX_SHADOW_LAYERS],
+            cascade_splits: Vector4::zero(),
+            camera_position: Vector4::zero(),
+            camera_forward: Vector4::unit_z(),
+            directional_direction: Vector4::new(0.0, -1.0, 0.0, 0.0),
+            directional_color: Vector4::zero(),
+            ambient_color: self.ambient_color.extend(0.0),
+            shadow_params: Vector4::new(
+                1.0 / self.config.resolution as f32,
+                self.config.pcf_radius as f32,
+                self.config.depth_bias,
+                0.0,
+            ),
+            spot_lights: [SpotLightData {
+                position_range: Vector4::zero(),
+                direction_cutoff: Vector4::zero(),
+                color_layer: Vector4::new(0.0, 0.0, 0.0, -1.0),
+            }; MAX_SPOT_LIGHTS],
+        };
+        self.casters.clear();
+
+        if let Some((transform, camera)) = camera {
+            uniforms.camera_position = transform.position.extend(1.0);
+            uniforms.camera_forward = camera.forward.extend(0.0);
+        }
+
+        let mut directional_found = false;
+        let mut spot_count = 0;
+        let mut query = <(&Transform, &Light)>::query();
+        for (transform, light) in query.iter(world) {
+            let direction = light.direction(transform);
+            let color = light.color * light.intensity;
+            match light.kind {
+                LightKind::Directional => {
+                    if directional_found {
+                        warn!("Only a single directional light is supported!");
+                        continue;
+                    }
+                    directional_found = true;
+                    uniforms.directional_direction = direction.extend(0.0);
+                    uniforms.directional_color = color.extend(0.0);
+                    if let (true, true, Some((transform, camera))) =
+                        (self.config.enabled, light.cast_shadows, camera)
+                    {
+                        let cascade_count = self.config.cascade_count as usize;
+                        let splits = self.compute_cascades(
+                            transform,
+                            camera,
+                            aspect_ratio,
+                            direction,
+                            &mut uniforms.shadow_matrices,
+                        );
+                        uniforms.cascade_splits = splits;
+                        uniforms.directional_direction.w = 1.0;
+                        uniforms.directional_color.w = cascade_count as f32;
+                    }
+                }
+                LightKind::Spot { range, angle } => {
+                    if spot_count == MAX_SPOT_LIGHTS {
+                        warn!("Too many spot lights! Max: {}", MAX_SPOT_LIGHTS);
+                        continue;
+                    }
+                    let layer = MAX_CASCADES + spot_count;
+                    let half_angle = Rad::from(angle) / 2.0;
+                    let data = &mut uniforms.spot_lights[spot_count];
+                    data.position_range = transform.position.extend(range);
+                    data.direction_cutoff = direction.extend(half_angle.cos());
+                    data.color_layer = color.extend(-1.0);
+                    if self.config.enabled && light.cast_shadows {
+                        let view_proj_matrix = CORRECTION_MATRIX
+                            * perspective(angle, 1.0, SPOT_SHADOW_NEAR_CLIP, range)
+                            * Matrix4::look_to_rh(
+                                Point3::from_vec(transform.position),
+                                direction,
+                                up_vector(direction),
+                            );
+                        uniforms.shadow_matrices[layer] = view_proj_matrix;
+                        data.color_layer.w = layer as f32;
+                        self.casters.push(ShadowCaster {
+                            layer,
+                            view_proj_matrix,
+                        });
+                    }
+                    spot_count += 1;
+                }
+            }
+        }
+        uniforms.ambient_color.w = spot_count as f32;
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    /// Splits the camera frustum and fits a stable orthographic projection around every slice.
+    /// Returns the view space far distance of every cascade.
+    fn compute_cascades(
+        &mut self,
+        transform: &Transform,
+        camera: &Camera,
+        aspect_ratio: f32,
+        direction: Vector3<f32>,
+        matrices: &mut [Matrix4<f32>; MAX_SHADOW_LAYERS],
+    ) -> Vector4<f32> {
+        let cascade_count = self.config.cascade_count as usize;
+        let near = camera.near_clip;
+        let far = camera.far_clip.min(self.config.distance).max(near + 0.1);
+        let lambda = self.config.split_lambda.clamp(0.0, 1.0);
+        let view_matrix = camera::view_matrix(transform, camera);
+        let resolution = self.config.resolution as f32;
+
+        let mut splits = Vector4::from_value(far);
+        let mut prev = near;
+        for cascade in 0..cascade_count {
+            let ratio = (cascade + 1) as f32 / cascade_count as f32;
+            let logarithmic = near * (far / near).powf(ratio);
+            let uniform = near + (far - near) * ratio;
+            let split = lambda * logarithmic + (1.0 - lambda) * uniform;
+            splits[cascade] = split;
+
+            let inverse = (camera.projection.matrix(aspect_ratio, prev, split) * view_matrix)
+                .invert()
+                .unwrap_or_else(Matrix4::identity);
+            let mut corners = [Vector3::zero(); 8];
+            for (i, corner) in corners.iter_mut().enumerate() {
+                let ndc = Vector4::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                    1.0,
+                );
+                let world = inverse * ndc;
+                *corner = world.truncate() / world.w;
+            }
+            let center = corners.iter().fold(Vector3::zero(), |a, b| a + b) / 8.0;
+            let radius = corners
+                .iter()
+                .map(|c| (c - center).magnitude())
+                .fold(0.0_f32, f32::max);
+            // round to reduce shimmering when the camera rotates:
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let eye = center - direction * (radius + CASCADE_CASTER_MARGIN);
+            let light_view =
+                Matrix4::look_to_rh(Point3::from_vec(eye), direction, up_vector(direction));
+            let light_proj = ortho(
+                -radius,
+                radius,
+                -radius,
+                radius,
+                0.0,
+                2.0 * radius + CASCADE_CASTER_MARGIN,
+            );
+
+            // snap the projection to whole texels:
+            let mut view_proj_matrix = light_proj * light_view;
+            let origin = view_proj_matrix * Vector4::new(0.0, 0.0, 0.0, 1.0);
+            let texel = resolution / 2.0;
+            let snapped = Vector2::new(
+                (origin.x * texel).round() / texel,
+                (origin.y * texel).round() / texel,
+            );
+            view_proj_matrix.w.x += snapped.x - origin.x;
+            view_proj_matrix.w.y += snapped.y - origin.y;
+
+            let view_proj_matrix = CORRECTION_MATRIX * view_proj_matrix;
+            matrices[cascade] = view_proj_matrix;
+            self.casters.push(ShadowCaster {
+                layer: cascade,
+                view_proj_matrix,
+            });
+            prev = split;
+        }
+        splits
+    }
+}
+
+#[inline]
+fn up_vector(direction: Vector3<f32>) -> Vector3<f32> {
+    if direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    }
+}
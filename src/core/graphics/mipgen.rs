@@ -1,6 +1,84 @@
 use super::drivers::Drivers;
+use super::pipeline_cache::{CachedPipelineLayout, CachedRenderPipelineDescriptor, PipelineCache};
+use std::sync::Arc;
 use wgpu::*;
 
+pub const BLIT_VS_PATH: &str = "db/shaders/mipgen/blit.vert.glsl";
+pub const BLIT_FS_PATH: &str = "db/shaders/mipgen/blit.frag.glsl";
+
+/// The source mip and its sampler.
+const BLIT_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[
+    BindGroupLayoutEntry {
+        binding: 0,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 1,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Sampler {
+            comparison: false,
+            filtering: true,
+        },
+        count: None,
+    },
+];
+
+pub fn blit_layout(device: &Device, cache: &PipelineCache) -> CachedPipelineLayout {
+    cache.pipeline_layout(device, &[BLIT_BIND_GROUP_LAYOUT_ENTRIES], &[])
+}
+
+pub fn blit_sampler(device: &Device, cache: &PipelineCache) -> Arc<Sampler> {
+    cache.sampler(
+        device,
+        &SamplerDescriptor {
+            label: Some("mip"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        },
+    )
+}
+
+/// The blit pipeline rendering into the format, created on the first use.
+fn blit_pipeline(drivers: &Drivers, format: TextureFormat) -> Arc<RenderPipeline> {
+    drivers
+        .blit_pipelines
+        .borrow_mut()
+        .entry(format)
+        .or_insert_with(|| {
+            drivers.pipeline_cache.render_pipeline(
+                &drivers.device,
+                &CachedRenderPipelineDescriptor {
+                    label: Some("blit"),
+                    layout: &drivers.blit_layout,
+                    vertex_module: &drivers.blit_shader.0,
+                    vertex_entry: "main",
+                    fragment_module: &drivers.blit_shader.1,
+                    fragment_entry: "main",
+                    vertex_buffers: &[],
+                    primitive: PrimitiveState {
+                        topology: PrimitiveTopology::TriangleStrip,
+                        ..Default::default()
+                    },
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    targets: &[format.into()],
+                },
+            )
+        })
+        .clone()
+}
+
 pub fn generate_mipmaps(
     drivers: &Drivers,
     encoder: &mut CommandEncoder,
@@ -8,41 +86,7 @@ pub fn generate_mipmaps(
     format: TextureFormat,
     mip_count: u32,
 ) {
-    let pipeline = drivers
-        .device
-        .create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("blit"),
-            layout: None,
-            vertex: VertexState {
-                module: &drivers.blit_shader.0,
-                entry_point: "main",
-                buffers: &[],
-            },
-            fragment: Some(FragmentState {
-                module: &drivers.blit_shader.1,
-                entry_point: "main",
-                targets: &[format.into()],
-            }),
-            primitive: PrimitiveState {
-                topology: PrimitiveTopology::TriangleStrip,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-        });
-
-    let bind_group_layout = pipeline.get_bind_group_layout(0);
-
-    let sampler = drivers.device.create_sampler(&SamplerDescriptor {
-        label: Some("mip"),
-        address_mode_u: AddressMode::ClampToEdge,
-        address_mode_v: AddressMode::ClampToEdge,
-        address_mode_w: AddressMode::ClampToEdge,
-        mag_filter: FilterMode::Linear,
-        min_filter: FilterMode::Nearest,
-        mipmap_filter: FilterMode::Nearest,
-        ..Default::default()
-    });
+    let pipeline = blit_pipeline(drivers, format);
 
     let views = (0..mip_count)
         .map(|mip| {
@@ -61,7 +105,7 @@ pub fn generate_mipmaps(
 
     for target_mip in 1..mip_count as usize {
         let bind_group = drivers.device.create_bind_group(&BindGroupDescriptor {
-            layout: &bind_group_layout,
+            layout: &drivers.blit_layout.bind_group_layouts[0],
             entries: &[
                 BindGroupEntry {
                     binding: 0,
@@ -69,7 +113,7 @@ pub fn generate_mipmaps(
                 },
                 BindGroupEntry {
                     binding: 1,
-                    resource: BindingResource::Sampler(&sampler),
+                    resource: BindingResource::Sampler(&drivers.blit_sampler),
                 },
             ],
             label: None,
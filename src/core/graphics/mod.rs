@@ -1,10 +1,18 @@
 pub mod boot;
 pub mod camera;
 pub mod drivers;
+pub mod environment;
 pub mod frame;
 pub mod gui;
+pub mod lighting;
 pub mod mipgen;
 pub mod pass;
 pub mod pipeline;
+pub mod pipeline_cache;
+pub mod pipeline_definition;
 pub mod pipelines;
+pub mod post_processing;
+pub mod push_constants;
+pub mod render_graph;
 pub mod shader_compiler;
+pub mod shader_reflection;
@@ -1,19 +1,57 @@
-use super::pipeline::Pipeline;
-use crate::resources::mesh::Mesh;
+use super::pipeline::{Pipeline, ShaderPipeline};
+use super::push_constants::{PushConstantEmulation, SLOT_SIZE};
+use crate::resources::mesh::{Mesh, INDEX_FORMAT};
 use std::sync::Arc;
 use wgpu::*;
 
-pub struct Pass<'a>(pub RenderPass<'a>);
+pub struct Pass<'a>(pub RenderPass<'a>, Option<EmulatedPushConstants<'a>>);
+
+/// Push constants of the next draw, when they are emulated with uniform buffers.
+struct EmulatedPushConstants<'a> {
+    emulation: &'a PushConstantEmulation,
+    block: [u8; SLOT_SIZE],
+    /// Bind group index of the current pipeline, if it uses push constants.
+    group: Option<u32>,
+    dirty: bool,
+}
 
 impl<'a> Pass<'a> {
+    pub fn new(render_pass: RenderPass<'a>, emulation: Option<&'a PushConstantEmulation>) -> Self {
+        Self(
+            render_pass,
+            emulation.map(|emulation| EmulatedPushConstants {
+                emulation,
+                block: [0; SLOT_SIZE],
+                group: None,
+                dirty: false,
+            }),
+        )
+    }
+
     #[inline]
     pub fn set_push_constans(&mut self, stage: ShaderStage, offset: u32, data: &[u8]) {
-        self.0.set_push_constants(stage, offset, data)
+        match &mut self.1 {
+            Some(emulated) => {
+                let offset = offset as usize;
+                emulated.block[offset..offset + data.len()].copy_from_slice(data);
+                emulated.dirty = true;
+            }
+            None => self.0.set_push_constants(stage, offset, data),
+        }
     }
 
     #[inline]
     pub fn set_pipeline<T: Pipeline>(&mut self, pipe: &'a T) {
-        self.0.set_pipeline(&pipe.shader_pipeline().render_pipeline);
+        self.set_shader_pipeline(pipe.shader_pipeline());
+    }
+
+    #[inline]
+    pub fn set_shader_pipeline(&mut self, pipe: &'a ShaderPipeline) {
+        self.0.set_pipeline(&pipe.render_pipeline);
+        if let Some(emulated) = &mut self.1 {
+            emulated.group = pipe.push_constant_group;
+            emulated.dirty = true;
+        }
     }
 
     #[inline]
@@ -21,10 +59,32 @@ impl<'a> Pass<'a> {
         self.0.set_bind_group(index, group, &[]);
     }
 
+    #[inline]
+    pub fn draw_fullscreen_triangle(&mut self) {
+        self.bind_push_constants();
+        self.0.draw(0..3, 0..1)
+    }
+
     pub fn draw_indexed(&mut self, mesh: &'a Arc<Mesh>) {
+        self.bind_push_constants();
         self.0
-            .set_index_buffer(mesh.index_buffer().slice(..), IndexFormat::Uint16);
+            .set_index_buffer(mesh.index_buffer().slice(..), INDEX_FORMAT);
         self.0.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
         self.0.draw_indexed(0..mesh.indices().len() as u32, 0, 0..1)
     }
+
+    /// Binds the slot of the emulated push constants changed since the last draw.
+    fn bind_push_constants(&mut self) {
+        let emulated = match &mut self.1 {
+            Some(emulated) if emulated.dirty => emulated,
+            _ => return,
+        };
+        emulated.dirty = false;
+        if let Some(group) = emulated.group {
+            let emulation = emulated.emulation;
+            let offset = emulation.push(&emulated.block);
+            self.0
+                .set_bind_group(group, &emulation.bind_group, &[offset]);
+        }
+    }
 }
@@ -1,11 +1,19 @@
 use super::drivers::Drivers;
-use super::shader_compiler;
+use super::pipeline_cache::CachedRenderPipelineDescriptor;
+use super::push_constants;
+use super::shader_compiler::{
+    self, ShaderCompiler, ShaderDefines, ShaderDiagnostic, ShaderError, ShaderKind,
+};
+use super::shader_reflection::ShaderReflection;
 use crate::config::CoreConfig;
 use bytemuck::{Pod, Zeroable};
 use log::info;
-use shaderc::ShaderKind;
-use smallvec::{smallvec, SmallVec};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::fs;
+use std::marker::PhantomData;
 use std::path::PathBuf;
+use std::sync::Arc;
 use wgpu::*;
 
 pub trait Pipeline {
@@ -13,26 +21,93 @@ pub trait Pipeline {
     const NAME: &'static str;
     const IS_SURFACE_PIPELINE: bool;
     const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry];
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]];
     const PRIMITIVE_STATE: PrimitiveState;
     const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>];
     const PUSH_CONSTANT_RANGES: &'static [PushConstantRange];
     const DEPTH_STENCIL_STATE: Option<DepthStencilState>;
+    /// Macro definitions of both shaders.
+    const DEFINES: &'static [(&'static str, &'static str)] = &[];
+    /// Optional shader features, defined as macros in the variants enabling them.
+    const KEYWORDS: &'static [&'static str] = &[];
 
     fn shader_pipeline(&self) -> &ShaderPipeline;
     fn create(_drivers: &mut Drivers, _config: &CoreConfig) -> Self;
 }
 
+/// GPU objects are shared through the pipeline cache of the drivers.
 pub struct ShaderPipeline {
-    pub vs_module: ShaderModule,
-    pub fs_module: ShaderModule,
+    pub vs_module: Arc<ShaderModule>,
+    /// None if the vertex module has both entry points, like WGSL modules.
+    pub fs_module: Option<Arc<ShaderModule>>,
     pub fs_targets: SmallVec<[ColorTargetState; 8]>,
-    pub pipeline_layout: PipelineLayout,
-    pub render_pipeline: RenderPipeline,
-    pub per_material_bind_group_layout: BindGroupLayout,
+    pub pipeline_layout: Arc<PipelineLayout>,
+    pub render_pipeline: Arc<RenderPipeline>,
+    pub per_material_bind_group_layout: Arc<BindGroupLayout>,
+    pub shared_bind_group_layouts: SmallVec<[Arc<BindGroupLayout>; 4]>,
+    /// Bind group of the emulated push constants, after the regular ones.
+    pub push_constant_group: Option<u32>,
 }
 
+/// Keyword of the surface pipeline variants discarding fragments below the alpha cutoff.
+pub const ALPHA_TEST_KEYWORD: &str = "ALPHA_TEST";
+
+#[derive(Clone)]
 pub struct ShaderPipelineDescriptor {
     pub multi_sample_state: MultisampleState,
+    pub color_targets: SmallVec<[ColorTargetState; 8]>,
+}
+
+/// Color target with regular (non premultiplied) alpha blending, used by transparent pipeline variants.
+pub fn alpha_blended_color_target(format: TextureFormat) -> ColorTargetState {
+    ColorTargetState {
+        format,
+        alpha_blend: BlendState {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::OneMinusSrcAlpha,
+            operation: BlendOperation::Add,
+        },
+        color_blend: BlendState {
+            src_factor: BlendFactor::SrcAlpha,
+            dst_factor: BlendFactor::OneMinusSrcAlpha,
+            operation: BlendOperation::Add,
+        },
+        write_mask: ColorWrite::ALL,
+    }
+}
+
+/// Layout and fixed function state of a pipeline, given by the constants of a `Pipeline`
+/// or by a `pipeline.yaml` definition.
+pub struct PipelineDescription<'a> {
+    /// Directory of the shaders in `db/shaders/fixed_pipelines`, in any case.
+    pub name: &'a str,
+    pub per_material_bind_group_layout_entries: &'a [BindGroupLayoutEntry],
+    pub shared_bind_group_layout_entries: SmallVec<[&'a [BindGroupLayoutEntry]; 4]>,
+    pub primitive_state: PrimitiveState,
+    pub vertex_buffer_layouts: SmallVec<[VertexBufferLayout<'a>; 2]>,
+    pub push_constant_ranges: &'a [PushConstantRange],
+    pub depth_stencil_state: Option<DepthStencilState>,
+    pub defines: SmallVec<[(&'a str, &'a str); 4]>,
+    pub keywords: SmallVec<[&'a str; 4]>,
+}
+
+impl PipelineDescription<'static> {
+    pub fn of<T: Pipeline>() -> Self {
+        Self {
+            name: T::NAME,
+            per_material_bind_group_layout_entries: T::PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES,
+            shared_bind_group_layout_entries: T::SHARED_BIND_GROUP_LAYOUT_ENTRIES
+                .iter()
+                .copied()
+                .collect(),
+            primitive_state: T::PRIMITIVE_STATE,
+            vertex_buffer_layouts: T::VERTEX_BUFFER_LAYOUTS.iter().cloned().collect(),
+            push_constant_ranges: T::PUSH_CONSTANT_RANGES,
+            depth_stencil_state: T::DEPTH_STENCIL_STATE,
+            defines: T::DEFINES.iter().copied().collect(),
+            keywords: T::KEYWORDS.iter().copied().collect(),
+        }
+    }
 }
 
 impl ShaderPipeline {
@@ -40,75 +115,460 @@ impl ShaderPipeline {
         drivers: &mut Drivers,
         desc: ShaderPipelineDescriptor,
     ) -> Self {
-        let name = String::from(T::NAME).to_lowercase();
-        info!("Creating render pipeline \"{}\"...", name);
+        Self::create_shader_bundle_with_depth_stencil::<T>(drivers, desc, T::DEPTH_STENCIL_STATE)
+    }
 
-        let vs_bytecode_path = format!(
-            "db/shaders/fixed_pipelines/{}/shader.{}.glsl",
-            name,
-            shader_compiler::VS_ID
-        );
+    /// Creates a variant of the pipeline with a different depth stencil state (e.g. without depth writes).
+    pub fn create_shader_bundle_with_depth_stencil<T: Pipeline>(
+        drivers: &mut Drivers,
+        desc: ShaderPipelineDescriptor,
+        depth_stencil: Option<DepthStencilState>,
+    ) -> Self {
+        Self::create_shader_bundle_variant::<T>(drivers, desc, depth_stencil, KeywordSet::default())
+    }
 
-        let fs_bytecode_path = format!(
-            "db/shaders/fixed_pipelines/{}/shader.{}.glsl",
+    /// Creates the permutation of the pipeline with the shader features of `keywords` enabled.
+    pub fn create_shader_bundle_variant<T: Pipeline>(
+        drivers: &mut Drivers,
+        desc: ShaderPipelineDescriptor,
+        depth_stencil: Option<DepthStencilState>,
+        keywords: KeywordSet,
+    ) -> Self {
+        let mut description = PipelineDescription::of::<T>();
+        description.depth_stencil_state = depth_stencil;
+        Self::create(drivers, &description, desc, keywords)
+    }
+
+    pub fn create(
+        drivers: &mut Drivers,
+        description: &PipelineDescription,
+        desc: ShaderPipelineDescriptor,
+        keywords: KeywordSet,
+    ) -> Self {
+        let name = description.name.to_lowercase();
+        info!(
+            "Creating render pipeline \"{}\" {:?}...",
             name,
-            shader_compiler::FS_ID
+            keywords.names(&description.keywords)
         );
 
-        let vs_bytecode_path = PathBuf::from(vs_bytecode_path);
-        let fs_bytecode_path = PathBuf::from(fs_bytecode_path);
+        let material_bind_group_layout = drivers.pipeline_cache.bind_group_layout(
+            &drivers.device,
+            description.per_material_bind_group_layout_entries,
+        );
 
-        info!("Fragment shader: {:?}", fs_bytecode_path);
-        info!("Vertex shader: {:?}", vs_bytecode_path);
+        // pipelines without material bindings (e.g. depth only) start with the shared groups:
+        let material_groups = !description
+            .per_material_bind_group_layout_entries
+            .is_empty() as usize;
+        let mut bind_group_layouts = SmallVec::<[&[BindGroupLayoutEntry]; 4]>::new();
+        if material_groups != 0 {
+            bind_group_layouts.push(description.per_material_bind_group_layout_entries);
+        }
+        bind_group_layouts.extend(description.shared_bind_group_layout_entries.iter().copied());
 
-        let vs_module = drivers.compile_and_create_shader(vs_bytecode_path, ShaderKind::Vertex);
-        let fs_module = drivers.compile_and_create_shader(fs_bytecode_path, ShaderKind::Fragment);
+        let mut push_constant_ranges = description.push_constant_ranges;
+        let mut push_constant_group = None;
+        if drivers.push_constant_emulation.is_some() && !push_constant_ranges.is_empty() {
+            push_constant_group = Some(emulated_push_constant_group(description));
+            bind_group_layouts.push(push_constants::BIND_GROUP_LAYOUT_ENTRIES);
+            push_constant_ranges = &[];
+        }
 
-        let fs_targets = smallvec![drivers.swap_chain_format.into()];
+        let pipeline_layout = drivers.pipeline_cache.pipeline_layout(
+            &drivers.device,
+            &bind_group_layouts,
+            push_constant_ranges,
+        );
+        let shared_bind_group_layouts = pipeline_layout.bind_group_layouts[material_groups..]
+            .iter()
+            .take(description.shared_bind_group_layout_entries.len())
+            .cloned()
+            .collect::<SmallVec<[Arc<BindGroupLayout>; 4]>>();
 
-        let material_bind_group_layout =
-            drivers
-                .device
-                .create_bind_group_layout(&BindGroupLayoutDescriptor {
-                    label: None,
-                    entries: T::PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES,
-                });
+        let (vs_module, fs_module, vs_entry, fs_entry) = if is_wgsl(description.name) {
+            info!("WGSL shader: {:?}", wgsl_path(description.name));
+            let source = load_wgsl(description).unwrap_or_else(|error| panic!("{}", error));
+            let module = drivers.create_wgsl_module(source);
+            (module, None, WGSL_VS_ENTRY, WGSL_FS_ENTRY)
+        } else {
+            info!(
+                "Fragment shader: {:?}",
+                shader_path(description.name, ShaderKind::Fragment)
+            );
+            info!(
+                "Vertex shader: {:?}",
+                shader_path(description.name, ShaderKind::Vertex)
+            );
+            let mut create_shader_module = |kind| {
+                let shader = compile_shader(
+                    &mut drivers.shader_compiler,
+                    description,
+                    kind,
+                    push_constant_group,
+                    keywords,
+                )
+                .unwrap_or_else(|error| panic!("{}", error));
+                drivers.create_shader_module(&shader, kind)
+            };
+            let vs_module = create_shader_module(ShaderKind::Vertex);
+            let fs_module = create_shader_module(ShaderKind::Fragment);
+            (
+                vs_module,
+                Some(fs_module),
+                shader_compiler::SHADER_ENTRY,
+                shader_compiler::SHADER_ENTRY,
+            )
+        };
 
-        let pipeline_layout = drivers
-            .device
-            .create_pipeline_layout(&PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: &[&material_bind_group_layout][..],
-                push_constant_ranges: T::PUSH_CONSTANT_RANGES,
-            });
+        let fs_targets = desc.color_targets;
 
-        let render_pipeline = drivers
-            .device
-            .create_render_pipeline(&RenderPipelineDescriptor {
+        let render_pipeline = drivers.pipeline_cache.render_pipeline(
+            &drivers.device,
+            &CachedRenderPipelineDescriptor {
                 label: None,
-                layout: Some(&pipeline_layout),
-                vertex: VertexState {
-                    module: &vs_module,
-                    entry_point: shader_compiler::SHADER_ENTRY,
-                    buffers: T::VERTEX_BUFFER_LAYOUTS,
-                },
-                fragment: Some(FragmentState {
-                    module: &fs_module,
-                    entry_point: shader_compiler::SHADER_ENTRY,
-                    targets: &fs_targets[..],
-                }),
-                primitive: T::PRIMITIVE_STATE,
-                depth_stencil: T::DEPTH_STENCIL_STATE,
+                layout: &pipeline_layout,
+                vertex_module: &vs_module,
+                vertex_entry: vs_entry,
+                fragment_module: fs_module.as_ref().unwrap_or(&vs_module),
+                fragment_entry: fs_entry,
+                vertex_buffers: &description.vertex_buffer_layouts[..],
+                primitive: description.primitive_state.clone(),
+                depth_stencil: description.depth_stencil_state.clone(),
                 multisample: desc.multi_sample_state,
-            });
+                targets: &fs_targets[..],
+            },
+        );
 
         Self {
-            vs_module,
-            fs_module,
+            vs_module: vs_module.module,
+            fs_module: fs_module.map(|module| module.module),
             fs_targets,
-            pipeline_layout,
+            pipeline_layout: pipeline_layout.layout,
             render_pipeline,
             per_material_bind_group_layout: material_bind_group_layout,
+            shared_bind_group_layouts,
+            push_constant_group,
+        }
+    }
+}
+
+/// Entry points of the WGSL shaders, which hold both stages in one file.
+pub const WGSL_VS_ENTRY: &str = "vs_main";
+pub const WGSL_FS_ENTRY: &str = "fs_main";
+
+pub fn shader_path(name: &str, kind: ShaderKind) -> PathBuf {
+    PathBuf::from(format!(
+        "db/shaders/fixed_pipelines/{}/shader.{}.glsl",
+        name.to_lowercase(),
+        kind.id()
+    ))
+}
+
+pub fn wgsl_path(name: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "db/shaders/fixed_pipelines/{}/shader.wgsl",
+        name.to_lowercase()
+    ))
+}
+
+/// Pipelines with a `shader.wgsl` use it instead of the GLSL shaders, without shaderc.
+#[inline]
+pub fn is_wgsl(name: &str) -> bool {
+    wgsl_path(name).exists()
+}
+
+/// Source of a WGSL pipeline, parsed and validated against the layout of the pipeline.
+pub fn load_wgsl(description: &PipelineDescription) -> Result<String, ShaderError> {
+    let path = wgsl_path(description.name);
+    if !description.push_constant_ranges.is_empty()
+        || !description.defines.is_empty()
+        || !description.keywords.is_empty()
+    {
+        return Err(ShaderError::new(
+            &path,
+            String::from("WGSL has no push constants and no preprocessor, the pipeline must not declare push constant ranges, defines or keywords"),
+        ));
+    }
+    let source = fs::read_to_string(&path).map_err(|error| {
+        ShaderError::new(
+            &path,
+            format!("Failed to load shader source file: {}", error),
+        )
+    })?;
+    let module = naga::front::wgsl::parse_str(&source).map_err(|error| ShaderError {
+        path: path.clone(),
+        diagnostics: vec![ShaderDiagnostic {
+            file: path.display().to_string(),
+            line: Some(error.line as u32),
+            message: format!("{} (column {})", error.error, error.pos),
+        }],
+    })?;
+
+    let mut errors = Vec::new();
+    for &(stage, entry) in &[
+        (naga::ShaderStage::Vertex, WGSL_VS_ENTRY),
+        (naga::ShaderStage::Fragment, WGSL_FS_ENTRY),
+    ] {
+        if !module
+            .entry_points
+            .contains_key(&(stage, entry.to_string()))
+        {
+            errors.push(format!("Missing {:?} entry point \"{}\"", stage, entry));
+        }
+    }
+    let reflection = ShaderReflection::from_naga(&module);
+    errors.extend(validate_layout(
+        description,
+        &reflection,
+        ShaderStage::VERTEX | ShaderStage::FRAGMENT,
+        None,
+    ));
+    if !errors.is_empty() {
+        return Err(ShaderError::from_messages(&path, errors));
+    }
+    Ok(source)
+}
+
+/// Emulated push constants are bound after the material and shared bind groups.
+#[inline]
+pub fn emulated_push_constant_group(description: &PipelineDescription) -> u32 {
+    let material_groups = !description
+        .per_material_bind_group_layout_entries
+        .is_empty() as u32;
+    material_groups + description.shared_bind_group_layout_entries.len() as u32
+}
+
+/// Enabled shader features of a pipeline variant, bit `i` enables the `i`-th declared keyword.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct KeywordSet(u32);
+
+impl KeywordSet {
+    /// Panics on keywords the pipeline does not declare.
+    #[inline]
+    pub fn of<T: Pipeline>(keywords: &[&str]) -> Self {
+        Self::from_names(T::NAME, T::KEYWORDS, keywords)
+    }
+
+    /// Panics on keywords missing in the `declared` keywords of the pipeline.
+    pub fn from_names(pipeline: &str, declared: &[&str], keywords: &[&str]) -> Self {
+        let mut bits = 0;
+        for keyword in keywords {
+            let index = declared
+                .iter()
+                .position(|declared| declared == keyword)
+                .unwrap_or_else(|| {
+                    panic!("Pipeline \"{}\" has no keyword {}!", pipeline, keyword);
+                });
+            bits |= 1 << index;
+        }
+        Self(bits)
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The enabled keywords out of the `declared` keywords of the pipeline.
+    pub fn names<'a>(self, declared: &[&'a str]) -> SmallVec<[&'a str; 4]> {
+        declared
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.0 & (1 << index) != 0)
+            .map(|(_, keyword)| *keyword)
+            .collect()
+    }
+
+    /// Every combination of `count` declared keywords.
+    pub fn all(count: usize) -> impl Iterator<Item = Self> {
+        (0..1u32 << count).map(Self)
+    }
+}
+
+/// Variants of a pipeline by enabled keywords, created on first request.
+pub struct PipelineVariants<T: Pipeline> {
+    desc: ShaderPipelineDescriptor,
+    depth_stencil: Option<DepthStencilState>,
+    variants: HashMap<KeywordSet, ShaderPipeline>,
+    _pipeline: PhantomData<T>,
+}
+
+impl<T: Pipeline> PipelineVariants<T> {
+    pub fn new(desc: ShaderPipelineDescriptor, depth_stencil: Option<DepthStencilState>) -> Self {
+        Self {
+            desc,
+            depth_stencil,
+            variants: HashMap::new(),
+            _pipeline: PhantomData,
+        }
+    }
+
+    /// Creates the variant if it does not exist yet.
+    pub fn request(&mut self, drivers: &mut Drivers, keywords: KeywordSet) {
+        if self.variants.contains_key(&keywords) {
+            return;
+        }
+        let variant = ShaderPipeline::create_shader_bundle_variant::<T>(
+            drivers,
+            self.desc.clone(),
+            self.depth_stencil.clone(),
+            keywords,
+        );
+        self.variants.insert(keywords, variant);
+    }
+
+    #[inline]
+    pub fn get(&self, keywords: KeywordSet) -> Option<&ShaderPipeline> {
+        self.variants.get(&keywords)
+    }
+}
+
+/// Macros of the pipeline and the enabled keywords.
+pub fn shader_defines(
+    description: &PipelineDescription,
+    push_constant_group: Option<u32>,
+    keywords: KeywordSet,
+) -> ShaderDefines {
+    let mut defines = ShaderDefines {
+        push_constant_group,
+        ..Default::default()
+    };
+    for (name, value) in &description.defines {
+        defines.define(name, value);
+    }
+    for keyword in keywords.names(&description.keywords) {
+        defines.define(keyword, "1");
+    }
+    defines
+}
+
+/// Bytecode of a pipeline shader, validated against the layout of the pipeline.
+pub struct CompiledShader {
+    pub bytecode: Vec<u8>,
+    pub reflection: ShaderReflection,
+}
+
+pub fn compile_shader(
+    compiler: &mut ShaderCompiler,
+    description: &PipelineDescription,
+    kind: ShaderKind,
+    push_constant_group: Option<u32>,
+    keywords: KeywordSet,
+) -> Result<CompiledShader, ShaderError> {
+    let path = shader_path(description.name, kind);
+    let defines = shader_defines(description, push_constant_group, keywords);
+    let bytecode = compiler.try_compile(&path, kind, &defines)?;
+    let reflection =
+        ShaderReflection::reflect(&bytecode).map_err(|error| ShaderError::new(&path, error))?;
+    let stage = match kind {
+        ShaderKind::Vertex => ShaderStage::VERTEX,
+        ShaderKind::Fragment => ShaderStage::FRAGMENT,
+    };
+    let errors = validate_layout(description, &reflection, stage, push_constant_group);
+    if !errors.is_empty() {
+        return Err(ShaderError::from_messages(&path, errors));
+    }
+    Ok(CompiledShader {
+        bytecode,
+        reflection,
+    })
+}
+
+/// Checks the bindings and push constants of the shader against the layout of the pipeline.
+fn validate_layout(
+    description: &PipelineDescription,
+    reflection: &ShaderReflection,
+    stage: ShaderStage,
+    push_constant_group: Option<u32>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    let material_entries = description.per_material_bind_group_layout_entries;
+    let material_groups = !material_entries.is_empty() as u32;
+    if material_groups != 0 {
+        reflection.validate_group(stage, 0, material_entries, &mut errors);
+    }
+    let shared_entries = &description.shared_bind_group_layout_entries;
+    for (index, entries) in shared_entries.iter().enumerate() {
+        reflection.validate_group(stage, material_groups + index as u32, entries, &mut errors);
+    }
+    let group_count = material_groups + shared_entries.len() as u32;
+    for binding in &reflection.bindings {
+        if binding.group >= group_count && Some(binding.group) != push_constant_group {
+            errors.push(format!(
+                "Group {} of binding {} is not in the pipeline layout",
+                binding.group, binding.binding
+            ));
+        }
+    }
+    reflection.validate_push_constants(stage, description.push_constant_ranges, &mut errors);
+    errors
+}
+
+pub fn check<T: Pipeline>(
+    compiler: &mut ShaderCompiler,
+    emulated_push_constants: bool,
+    errors: &mut Vec<ShaderError>,
+) {
+    check_description(
+        compiler,
+        &PipelineDescription::of::<T>(),
+        emulated_push_constants,
+        errors,
+    );
+}
+
+/// Compiles and validates every variant of the pipeline, without creating it.
+pub fn check_description(
+    compiler: &mut ShaderCompiler,
+    description: &PipelineDescription,
+    emulated_push_constants: bool,
+    errors: &mut Vec<ShaderError>,
+) {
+    if is_wgsl(description.name) {
+        if let Err(error) = load_wgsl(description) {
+            errors.push(error);
+        }
+        return;
+    }
+    let push_constant_group =
+        if emulated_push_constants && !description.push_constant_ranges.is_empty() {
+            Some(emulated_push_constant_group(description))
+        } else {
+            None
+        };
+    for keywords in KeywordSet::all(description.keywords.len()) {
+        for &kind in &[ShaderKind::Vertex, ShaderKind::Fragment] {
+            if let Err(error) =
+                compile_shader(compiler, description, kind, push_constant_group, keywords)
+            {
+                errors.push(error);
+            }
+        }
+    }
+}
+
+pub fn precompile<T: Pipeline>(compiler: &mut ShaderCompiler) {
+    precompile_description(compiler, &PipelineDescription::of::<T>());
+}
+
+/// Compiles the shaders of every variant of the pipeline into the shader cache,
+/// with and without emulated push constants.
+pub fn precompile_description(compiler: &mut ShaderCompiler, description: &PipelineDescription) {
+    // wgsl is loaded by wgpu itself:
+    if is_wgsl(description.name) {
+        return;
+    }
+    let mut push_constant_groups = vec![None];
+    if !description.push_constant_ranges.is_empty() {
+        push_constant_groups.push(Some(emulated_push_constant_group(description)));
+    }
+    for keywords in KeywordSet::all(description.keywords.len()) {
+        for &push_constant_group in &push_constant_groups {
+            let defines = shader_defines(description, push_constant_group, keywords);
+            for &kind in &[ShaderKind::Vertex, ShaderKind::Fragment] {
+                compiler.compile(&shader_path(description.name, kind), kind, &defines);
+            }
         }
     }
 }
@@ -0,0 +1,330 @@
+//! Deduplicates GPU objects by descriptor, so pipelines, mip generation and imgui share render
+//! pipelines, layouts and samplers instead of creating their own.
+//! Shader modules are keyed by a hash of their source, render pipelines by the keys of their
+//! modules and layout together with the fixed function state.
+
+use smallvec::SmallVec;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU8;
+use std::sync::Arc;
+use wgpu::*;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PipelineCacheStats {
+    pub render_pipelines: usize,
+    pub render_pipeline_hits: u64,
+    pub bind_group_layouts: usize,
+    pub bind_group_layout_hits: u64,
+    pub samplers: usize,
+    pub sampler_hits: u64,
+}
+
+/// A cached shader module with the hash of its source.
+#[derive(Clone)]
+pub struct CachedShaderModule {
+    key: u64,
+    pub module: Arc<ShaderModule>,
+}
+
+/// A cached pipeline layout with its bind group layouts.
+#[derive(Clone)]
+pub struct CachedPipelineLayout {
+    key: PipelineLayoutKey,
+    pub layout: Arc<PipelineLayout>,
+    pub bind_group_layouts: SmallVec<[Arc<BindGroupLayout>; 4]>,
+}
+
+pub struct CachedRenderPipelineDescriptor<'a> {
+    pub label: Option<&'a str>,
+    pub layout: &'a CachedPipelineLayout,
+    pub vertex_module: &'a CachedShaderModule,
+    pub vertex_entry: &'a str,
+    pub fragment_module: &'a CachedShaderModule,
+    pub fragment_entry: &'a str,
+    pub vertex_buffers: &'a [VertexBufferLayout<'a>],
+    pub primitive: PrimitiveState,
+    pub depth_stencil: Option<DepthStencilState>,
+    pub multisample: MultisampleState,
+    pub targets: &'a [ColorTargetState],
+}
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+struct PipelineLayoutKey {
+    bind_group_layouts: Vec<Vec<BindGroupLayoutEntry>>,
+    push_constant_ranges: Vec<PushConstantRange>,
+}
+
+type PrimitiveKey = (
+    PrimitiveTopology,
+    Option<IndexFormat>,
+    FrontFace,
+    CullMode,
+    PolygonMode,
+);
+
+/// Format, depth write, compare, stencil, bias (constant, slope scale and clamp bits), clamp depth.
+type DepthStencilKey = (
+    TextureFormat,
+    bool,
+    CompareFunction,
+    StencilState,
+    i32,
+    u32,
+    u32,
+    bool,
+);
+
+#[derive(Eq, PartialEq, Hash)]
+struct RenderPipelineKey {
+    layout: PipelineLayoutKey,
+    vertex: (u64, String),
+    fragment: (u64, String),
+    vertex_buffers: Vec<(BufferAddress, InputStepMode, Vec<VertexAttribute>)>,
+    primitive: PrimitiveKey,
+    depth_stencil: Option<DepthStencilKey>,
+    multisample: (u32, u64, bool),
+    targets: Vec<ColorTargetState>,
+}
+
+impl RenderPipelineKey {
+    fn new(desc: &CachedRenderPipelineDescriptor) -> Self {
+        let primitive = &desc.primitive;
+        Self {
+            layout: desc.layout.key.clone(),
+            vertex: (desc.vertex_module.key, desc.vertex_entry.to_string()),
+            fragment: (desc.fragment_module.key, desc.fragment_entry.to_string()),
+            vertex_buffers: desc
+                .vertex_buffers
+                .iter()
+                .map(|buffer| {
+                    (
+                        buffer.array_stride,
+                        buffer.step_mode,
+                        buffer.attributes.to_vec(),
+                    )
+                })
+                .collect(),
+            primitive: (
+                primitive.topology,
+                primitive.strip_index_format,
+                primitive.front_face,
+                primitive.cull_mode,
+                primitive.polygon_mode,
+            ),
+            depth_stencil: desc.depth_stencil.as_ref().map(|state| {
+                (
+                    state.format,
+                    state.depth_write_enabled,
+                    state.depth_compare,
+                    state.stencil.clone(),
+                    state.bias.constant,
+                    state.bias.slope_scale.to_bits(),
+                    state.bias.clamp.to_bits(),
+                    state.clamp_depth,
+                )
+            }),
+            multisample: (
+                desc.multisample.count,
+                desc.multisample.mask,
+                desc.multisample.alpha_to_coverage_enabled,
+            ),
+            targets: desc.targets.to_vec(),
+        }
+    }
+}
+
+/// Address modes, filters, lod clamp bits, compare, anisotropy and border color.
+#[derive(Eq, PartialEq, Hash)]
+struct SamplerKey {
+    address_modes: [AddressMode; 3],
+    filters: [FilterMode; 3],
+    lod_clamp: (u32, u32),
+    compare: Option<CompareFunction>,
+    anisotropy_clamp: Option<NonZeroU8>,
+    border_color: Option<SamplerBorderColor>,
+}
+
+impl SamplerKey {
+    fn new(desc: &SamplerDescriptor) -> Self {
+        Self {
+            address_modes: [
+                desc.address_mode_u,
+                desc.address_mode_v,
+                desc.address_mode_w,
+            ],
+            filters: [desc.mag_filter, desc.min_filter, desc.mipmap_filter],
+            lod_clamp: (desc.lod_min_clamp.to_bits(), desc.lod_max_clamp.to_bits()),
+            compare: desc.compare,
+            anisotropy_clamp: desc.anisotropy_clamp,
+            border_color: desc.border_color,
+        }
+    }
+}
+
+/// Owned by the drivers, objects are created on the first request of their descriptor.
+#[derive(Default)]
+pub struct PipelineCache {
+    shader_modules: RefCell<HashMap<u64, Arc<ShaderModule>>>,
+    bind_group_layouts: RefCell<HashMap<Vec<BindGroupLayoutEntry>, Arc<BindGroupLayout>>>,
+    pipeline_layouts: RefCell<HashMap<PipelineLayoutKey, Arc<PipelineLayout>>>,
+    render_pipelines: RefCell<HashMap<RenderPipelineKey, Arc<RenderPipeline>>>,
+    samplers: RefCell<HashMap<SamplerKey, Arc<Sampler>>>,
+    render_pipeline_hits: Cell<u64>,
+    bind_group_layout_hits: Cell<u64>,
+    sampler_hits: Cell<u64>,
+}
+
+impl PipelineCache {
+    pub fn stats(&self) -> PipelineCacheStats {
+        PipelineCacheStats {
+            render_pipelines: self.render_pipelines.borrow().len(),
+            render_pipeline_hits: self.render_pipeline_hits.get(),
+            bind_group_layouts: self.bind_group_layouts.borrow().len(),
+            bind_group_layout_hits: self.bind_group_layout_hits.get(),
+            samplers: self.samplers.borrow().len(),
+            sampler_hits: self.sampler_hits.get(),
+        }
+    }
+
+    pub fn shader_module(
+        &self,
+        device: &Device,
+        desc: &ShaderModuleDescriptor,
+    ) -> CachedShaderModule {
+        let mut hasher = DefaultHasher::new();
+        match &desc.source {
+            ShaderSource::SpirV(words) => words.hash(&mut hasher),
+            ShaderSource::Wgsl(source) => source.hash(&mut hasher),
+        }
+        desc.flags.bits().hash(&mut hasher);
+        let key = hasher.finish();
+        let module = self
+            .shader_modules
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| Arc::new(device.create_shader_module(desc)))
+            .clone();
+        CachedShaderModule { key, module }
+    }
+
+    pub fn bind_group_layout(
+        &self,
+        device: &Device,
+        entries: &[BindGroupLayoutEntry],
+    ) -> Arc<BindGroupLayout> {
+        let mut layouts = self.bind_group_layouts.borrow_mut();
+        if let Some(layout) = layouts.get(entries) {
+            self.bind_group_layout_hits
+                .set(self.bind_group_layout_hits.get() + 1);
+            return layout.clone();
+        }
+        let layout = Arc::new(device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: None,
+            entries,
+        }));
+        layouts.insert(entries.to_vec(), layout.clone());
+        layout
+    }
+
+    pub fn pipeline_layout(
+        &self,
+        device: &Device,
+        bind_group_layouts: &[&[BindGroupLayoutEntry]],
+        push_constant_ranges: &[PushConstantRange],
+    ) -> CachedPipelineLayout {
+        let key = PipelineLayoutKey {
+            bind_group_layouts: bind_group_layouts
+                .iter()
+                .map(|entries| entries.to_vec())
+                .collect(),
+            push_constant_ranges: push_constant_ranges.to_vec(),
+        };
+        let group_layouts = bind_group_layouts
+            .iter()
+            .map(|entries| self.bind_group_layout(device, entries))
+            .collect::<SmallVec<[Arc<BindGroupLayout>; 4]>>();
+        let layout = self
+            .pipeline_layouts
+            .borrow_mut()
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let group_layouts = group_layouts
+                    .iter()
+                    .map(|layout| &**layout)
+                    .collect::<SmallVec<[&BindGroupLayout; 4]>>();
+                Arc::new(device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: None,
+                    bind_group_layouts: &group_layouts[..],
+                    push_constant_ranges,
+                }))
+            })
+            .clone();
+        CachedPipelineLayout {
+            key,
+            layout,
+            bind_group_layouts: group_layouts,
+        }
+    }
+
+    pub fn render_pipeline(
+        &self,
+        device: &Device,
+        desc: &CachedRenderPipelineDescriptor,
+    ) -> Arc<RenderPipeline> {
+        let key = RenderPipelineKey::new(desc);
+        let mut pipelines = self.render_pipelines.borrow_mut();
+        if let Some(pipeline) = pipelines.get(&key) {
+            self.render_pipeline_hits
+                .set(self.render_pipeline_hits.get() + 1);
+            return pipeline.clone();
+        }
+        let pipeline = Arc::new(device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: desc.label,
+            layout: Some(&desc.layout.layout),
+            vertex: VertexState {
+                module: &desc.vertex_module.module,
+                entry_point: desc.vertex_entry,
+                buffers: desc.vertex_buffers,
+            },
+            fragment: Some(FragmentState {
+                module: &desc.fragment_module.module,
+                entry_point: desc.fragment_entry,
+                targets: desc.targets,
+            }),
+            primitive: desc.primitive.clone(),
+            depth_stencil: desc.depth_stencil.clone(),
+            multisample: desc.multisample.clone(),
+        }));
+        pipelines.insert(key, pipeline.clone());
+        pipeline
+    }
+
+    /// The label of the first request names the sampler.
+    pub fn sampler(&self, device: &Device, desc: &SamplerDescriptor) -> Arc<Sampler> {
+        let key = SamplerKey::new(desc);
+        let mut samplers = self.samplers.borrow_mut();
+        if let Some(sampler) = samplers.get(&key) {
+            self.sampler_hits.set(self.sampler_hits.get() + 1);
+            return sampler.clone();
+        }
+        let sampler = Arc::new(device.create_sampler(desc));
+        samplers.insert(key, sampler.clone());
+        sampler
+    }
+
+    /// Drops the objects nothing but the cache uses anymore, e.g. after reloading shaders.
+    pub fn purge_unused(&self) {
+        fn retain_used<K, V>(map: &RefCell<HashMap<K, Arc<V>>>) {
+            map.borrow_mut()
+                .retain(|_, object| Arc::strong_count(object) > 1);
+        }
+        retain_used(&self.render_pipelines);
+        retain_used(&self.pipeline_layouts);
+        retain_used(&self.shader_modules);
+        retain_used(&self.bind_group_layouts);
+        retain_used(&self.samplers);
+    }
+}
@@ -0,0 +1,756 @@
+//! Pipelines described by a `pipeline.yaml` next to their shaders in
+//! `db/shaders/fixed_pipelines/<name>/` instead of a `Pipeline` implementation, e.g.:
+//!
+//! ```yaml
+//! primitive:
+//!   topology: TriangleList
+//!   cull_mode: None
+//! material_bind_group:
+//!   - binding: 0
+//!     ty: { Texture: { dimension: D2 } }
+//!   - binding: 1
+//!     ty: { Sampler: {} }
+//! push_constants:
+//!   - stages: [Fragment]
+//!     size: 16
+//! color_targets:
+//!   - format: Hdr
+//!     blend: AlphaBlending
+//! ```
+//!
+//! The wgpu descriptors are not deserializable, the file is read into the mirror types at the
+//! bottom and converted on load.
+
+use super::boot::{depth_compare, DEPTH_FORMAT, HDR_FORMAT};
+use super::drivers::Drivers;
+use super::environment::ENVIRONMENT_BIND_GROUP_LAYOUT_ENTRIES;
+use super::lighting::LIGHTING_BIND_GROUP_LAYOUT_ENTRIES;
+use super::pipeline::{
+    alpha_blended_color_target, KeywordSet, PipelineDescription, ShaderPipeline,
+    ShaderPipelineDescriptor,
+};
+use super::shader_compiler::{ShaderDiagnostic, ShaderError};
+use crate::config::DepthMode;
+use crate::resources::mesh;
+use serde::Deserialize;
+use smallvec::SmallVec;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use wgpu::*;
+
+const FIXED_PIPELINES_DIR: &str = "db/shaders/fixed_pipelines";
+
+pub fn definition_path(name: &str) -> PathBuf {
+    PathBuf::from(FIXED_PIPELINES_DIR)
+        .join(name.to_lowercase())
+        .join("pipeline.yaml")
+}
+
+/// Names of the pipelines with a definition file.
+pub fn find_definitions() -> Vec<String> {
+    let mut names = fs::read_dir(FIXED_PIPELINES_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().join("pipeline.yaml").is_file())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// A loaded `pipeline.yaml`, converted into wgpu descriptors.
+pub struct PipelineDefinition {
+    pub name: String,
+    per_material_bind_group_layout_entries: Vec<BindGroupLayoutEntry>,
+    shared_bind_group_layout_entries: Vec<Vec<BindGroupLayoutEntry>>,
+    primitive_state: PrimitiveState,
+    vertex_buffers: Vec<(BufferAddress, InputStepMode, Vec<VertexAttribute>)>,
+    push_constant_ranges: Vec<PushConstantRange>,
+    depth_stencil: Option<DepthStencilFile>,
+    color_targets: Vec<ColorTargetFile>,
+    multisampled: bool,
+    defines: Vec<(String, String)>,
+    keywords: Vec<String>,
+}
+
+impl PipelineDefinition {
+    pub fn load(name: &str) -> Result<Self, ShaderError> {
+        let path = definition_path(name);
+        let text = fs::read_to_string(&path).map_err(|error| {
+            ShaderError::new(
+                &path,
+                format!("Failed to load pipeline definition: {}", error),
+            )
+        })?;
+        let file = serde_yaml::from_str::<PipelineFile>(&text).map_err(|error| ShaderError {
+            path: path.clone(),
+            diagnostics: vec![ShaderDiagnostic {
+                file: path.display().to_string(),
+                line: error.location().map(|location| location.line() as u32),
+                message: error.to_string(),
+            }],
+        })?;
+        let strip = matches!(
+            file.primitive.topology,
+            TopologyFile::LineStrip | TopologyFile::TriangleStrip
+        );
+        if file.primitive.strip_index_format.is_some() && !strip {
+            return Err(ShaderError::new(
+                &path,
+                String::from("strip_index_format only applies to LineStrip and TriangleStrip"),
+            ));
+        }
+        if file.color_targets.is_empty() {
+            return Err(ShaderError::new(
+                &path,
+                String::from("A pipeline needs at least one color target"),
+            ));
+        }
+
+        let vertex_buffers = file
+            .vertex_buffers
+            .iter()
+            .map(|buffer| {
+                // attributes are tightly packed in declaration order:
+                let mut offset = 0;
+                let attributes = buffer
+                    .attributes
+                    .iter()
+                    .map(|attribute| {
+                        let format = attribute.format.into();
+                        let attribute = VertexAttribute {
+                            format,
+                            offset,
+                            shader_location: attribute.location,
+                        };
+                        offset += format.size();
+                        attribute
+                    })
+                    .collect::<Vec<_>>();
+                let step_mode = if buffer.instanced {
+                    InputStepMode::Instance
+                } else {
+                    InputStepMode::Vertex
+                };
+                (offset, step_mode, attributes)
+            })
+            .collect();
+
+        Ok(Self {
+            name: name.to_lowercase(),
+            per_material_bind_group_layout_entries: bind_group_layout_entries(
+                &file.material_bind_group,
+            ),
+            shared_bind_group_layout_entries: file
+                .shared_bind_groups
+                .iter()
+                .map(|group| match group {
+                    SharedBindGroupFile::Lighting => LIGHTING_BIND_GROUP_LAYOUT_ENTRIES.to_vec(),
+                    SharedBindGroupFile::Environment => {
+                        ENVIRONMENT_BIND_GROUP_LAYOUT_ENTRIES.to_vec()
+                    }
+                    SharedBindGroupFile::Bindings(bindings) => bind_group_layout_entries(bindings),
+                })
+                .collect(),
+            primitive_state: file.primitive.into(),
+            vertex_buffers,
+            push_constant_ranges: file
+                .push_constants
+                .iter()
+                .map(|range| PushConstantRange {
+                    stages: shader_stages(&range.stages),
+                    range: range.offset..range.offset + range.size,
+                })
+                .collect(),
+            depth_stencil: file.depth_stencil,
+            color_targets: file.color_targets,
+            multisampled: file.multisampled,
+            defines: file.defines.into_iter().collect(),
+            keywords: file.keywords,
+        })
+    }
+
+    /// Layout and fixed function state, the depth mode only affects the depth compare function.
+    pub fn description(&self, depth_mode: DepthMode) -> PipelineDescription<'_> {
+        PipelineDescription {
+            name: &self.name,
+            per_material_bind_group_layout_entries: &self.per_material_bind_group_layout_entries,
+            shared_bind_group_layout_entries: self
+                .shared_bind_group_layout_entries
+                .iter()
+                .map(|entries| &entries[..])
+                .collect(),
+            primitive_state: self.primitive_state.clone(),
+            vertex_buffer_layouts: self
+                .vertex_buffers
+                .iter()
+                .map(|(array_stride, step_mode, attributes)| VertexBufferLayout {
+                    array_stride: *array_stride,
+                    step_mode: *step_mode,
+                    attributes,
+                })
+                .collect(),
+            push_constant_ranges: &self.push_constant_ranges,
+            depth_stencil_state: self.depth_stencil.as_ref().map(|state| DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: state.write,
+                depth_compare: state
+                    .compare
+                    .map_or_else(|| depth_compare(depth_mode), Into::into),
+                stencil: StencilState::default(),
+                bias: DepthBiasState {
+                    constant: state.bias_constant,
+                    slope_scale: state.bias_slope_scale,
+                    clamp: 0.0,
+                },
+                clamp_depth: false,
+            }),
+            defines: self
+                .defines
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect(),
+            keywords: self.keywords.iter().map(String::as_str).collect(),
+        }
+    }
+
+    pub fn descriptor(&self, drivers: &Drivers) -> ShaderPipelineDescriptor {
+        let count = if self.multisampled {
+            drivers.msaa_samples as u32
+        } else {
+            1
+        };
+        ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState {
+                count,
+                ..Default::default()
+            },
+            color_targets: self
+                .color_targets
+                .iter()
+                .map(|target| {
+                    let format = target.format.resolve(drivers);
+                    match &target.blend {
+                        None => format.into(),
+                        Some(BlendFile::AlphaBlending) => alpha_blended_color_target(format),
+                        Some(BlendFile::Custom { color, alpha }) => ColorTargetState {
+                            format,
+                            alpha_blend: alpha.into(),
+                            color_blend: color.into(),
+                            write_mask: ColorWrite::ALL,
+                        },
+                    }
+                })
+                .collect::<SmallVec<_>>(),
+        }
+    }
+
+    /// Creates the permutation of the pipeline with the shader features of `keywords` enabled.
+    pub fn create(&self, drivers: &mut Drivers, keywords: &[&str]) -> ShaderPipeline {
+        let desc = self.descriptor(drivers);
+        let description = self.description(drivers.depth_mode);
+        let keywords = KeywordSet::from_names(&self.name, &description.keywords, keywords);
+        ShaderPipeline::create(drivers, &description, desc, keywords)
+    }
+}
+
+fn bind_group_layout_entries(bindings: &[BindingFile]) -> Vec<BindGroupLayoutEntry> {
+    bindings
+        .iter()
+        .map(|binding| BindGroupLayoutEntry {
+            binding: binding.binding,
+            visibility: shader_stages(&binding.visibility),
+            ty: match binding.ty {
+                BindingTypeFile::UniformBuffer { dynamic } => BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: dynamic,
+                    min_binding_size: None,
+                },
+                BindingTypeFile::StorageBuffer { read_only } => BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                BindingTypeFile::Texture {
+                    dimension,
+                    sample_type,
+                    multisampled,
+                } => BindingType::Texture {
+                    multisampled,
+                    sample_type: sample_type
+                        .map_or(TextureSampleType::Float { filterable: true }, Into::into),
+                    view_dimension: dimension.into(),
+                },
+                BindingTypeFile::Sampler { comparison } => BindingType::Sampler {
+                    comparison,
+                    filtering: !comparison,
+                },
+            },
+            count: None,
+        })
+        .collect()
+}
+
+fn shader_stages(stages: &[StageFile]) -> ShaderStage {
+    stages
+        .iter()
+        .fold(ShaderStage::NONE, |visibility, stage| match stage {
+            StageFile::Vertex => visibility | ShaderStage::VERTEX,
+            StageFile::Fragment => visibility | ShaderStage::FRAGMENT,
+        })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PipelineFile {
+    #[serde(default)]
+    primitive: PrimitiveFile,
+    #[serde(default)]
+    vertex_buffers: Vec<VertexBufferFile>,
+    /// Group 0, left out of the layout when empty.
+    #[serde(default)]
+    material_bind_group: Vec<BindingFile>,
+    #[serde(default)]
+    shared_bind_groups: Vec<SharedBindGroupFile>,
+    #[serde(default)]
+    push_constants: Vec<PushConstantFile>,
+    #[serde(default)]
+    depth_stencil: Option<DepthStencilFile>,
+    color_targets: Vec<ColorTargetFile>,
+    /// Renders with the MSAA samples of the scene.
+    #[serde(default)]
+    multisampled: bool,
+    #[serde(default)]
+    defines: BTreeMap<String, String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct PrimitiveFile {
+    topology: TopologyFile,
+    /// Index format of the strips, the one of the meshes if not set.
+    strip_index_format: Option<IndexFormatFile>,
+    front_face: FrontFaceFile,
+    cull_mode: CullModeFile,
+    polygon_mode: PolygonModeFile,
+}
+
+impl Default for PrimitiveFile {
+    fn default() -> Self {
+        Self {
+            topology: TopologyFile::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFaceFile::Ccw,
+            cull_mode: CullModeFile::Back,
+            polygon_mode: PolygonModeFile::Fill,
+        }
+    }
+}
+
+impl From<PrimitiveFile> for PrimitiveState {
+    fn from(primitive: PrimitiveFile) -> Self {
+        let topology = match primitive.topology {
+            TopologyFile::PointList => PrimitiveTopology::PointList,
+            TopologyFile::LineList => PrimitiveTopology::LineList,
+            TopologyFile::LineStrip => PrimitiveTopology::LineStrip,
+            TopologyFile::TriangleList => PrimitiveTopology::TriangleList,
+            TopologyFile::TriangleStrip => PrimitiveTopology::TriangleStrip,
+        };
+        Self {
+            topology,
+            strip_index_format: match primitive.topology {
+                TopologyFile::LineStrip | TopologyFile::TriangleStrip => {
+                    Some(match primitive.strip_index_format {
+                        Some(IndexFormatFile::Uint16) => IndexFormat::Uint16,
+                        Some(IndexFormatFile::Uint32) => IndexFormat::Uint32,
+                        None => mesh::INDEX_FORMAT,
+                    })
+                }
+                _ => None,
+            },
+            front_face: match primitive.front_face {
+                FrontFaceFile::Ccw => FrontFace::Ccw,
+                FrontFaceFile::Cw => FrontFace::Cw,
+            },
+            cull_mode: match primitive.cull_mode {
+                CullModeFile::None => CullMode::None,
+                CullModeFile::Front => CullMode::Front,
+                CullModeFile::Back => CullMode::Back,
+            },
+            polygon_mode: match primitive.polygon_mode {
+                PolygonModeFile::Fill => PolygonMode::Fill,
+                PolygonModeFile::Line => PolygonMode::Line,
+                PolygonModeFile::Point => PolygonMode::Point,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum TopologyFile {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum IndexFormatFile {
+    Uint16,
+    Uint32,
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum FrontFaceFile {
+    Ccw,
+    Cw,
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum CullModeFile {
+    None,
+    Front,
+    Back,
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum PolygonModeFile {
+    Fill,
+    Line,
+    Point,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VertexBufferFile {
+    /// Advances per instance instead of per vertex.
+    #[serde(default)]
+    instanced: bool,
+    attributes: Vec<VertexAttributeFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VertexAttributeFile {
+    location: u32,
+    format: VertexFormatFile,
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum VertexFormatFile {
+    Uchar4Norm,
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    Uint,
+    Uint2,
+    Uint3,
+    Uint4,
+    Int,
+    Int2,
+    Int3,
+    Int4,
+}
+
+impl From<VertexFormatFile> for VertexFormat {
+    fn from(format: VertexFormatFile) -> Self {
+        match format {
+            VertexFormatFile::Uchar4Norm => VertexFormat::Uchar4Norm,
+            VertexFormatFile::Float => VertexFormat::Float,
+            VertexFormatFile::Float2 => VertexFormat::Float2,
+            VertexFormatFile::Float3 => VertexFormat::Float3,
+            VertexFormatFile::Float4 => VertexFormat::Float4,
+            VertexFormatFile::Uint => VertexFormat::Uint,
+            VertexFormatFile::Uint2 => VertexFormat::Uint2,
+            VertexFormatFile::Uint3 => VertexFormat::Uint3,
+            VertexFormatFile::Uint4 => VertexFormat::Uint4,
+            VertexFormatFile::Int => VertexFormat::Int,
+            VertexFormatFile::Int2 => VertexFormat::Int2,
+            VertexFormatFile::Int3 => VertexFormat::Int3,
+            VertexFormatFile::Int4 => VertexFormat::Int4,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum StageFile {
+    Vertex,
+    Fragment,
+}
+
+fn fragment_stage() -> Vec<StageFile> {
+    vec![StageFile::Fragment]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingFile {
+    binding: u32,
+    #[serde(default = "fragment_stage")]
+    visibility: Vec<StageFile>,
+    ty: BindingTypeFile,
+}
+
+#[derive(Copy, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+enum BindingTypeFile {
+    UniformBuffer {
+        #[serde(default)]
+        dynamic: bool,
+    },
+    StorageBuffer {
+        #[serde(default)]
+        read_only: bool,
+    },
+    Texture {
+        dimension: TextureDimensionFile,
+        /// Filterable float without a sample type.
+        #[serde(default)]
+        sample_type: Option<SampleTypeFile>,
+        #[serde(default)]
+        multisampled: bool,
+    },
+    Sampler {
+        #[serde(default)]
+        comparison: bool,
+    },
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum TextureDimensionFile {
+    D1,
+    D2,
+    D2Array,
+    Cube,
+    CubeArray,
+    D3,
+}
+
+impl From<TextureDimensionFile> for TextureViewDimension {
+    fn from(dimension: TextureDimensionFile) -> Self {
+        match dimension {
+            TextureDimensionFile::D1 => TextureViewDimension::D1,
+            TextureDimensionFile::D2 => TextureViewDimension::D2,
+            TextureDimensionFile::D2Array => TextureViewDimension::D2Array,
+            TextureDimensionFile::Cube => TextureViewDimension::Cube,
+            TextureDimensionFile::CubeArray => TextureViewDimension::CubeArray,
+            TextureDimensionFile::D3 => TextureViewDimension::D3,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum SampleTypeFile {
+    Float,
+    UnfilterableFloat,
+    Depth,
+    Sint,
+    Uint,
+}
+
+impl From<SampleTypeFile> for TextureSampleType {
+    fn from(sample_type: SampleTypeFile) -> Self {
+        match sample_type {
+            SampleTypeFile::Float => TextureSampleType::Float { filterable: true },
+            SampleTypeFile::UnfilterableFloat => TextureSampleType::Float { filterable: false },
+            SampleTypeFile::Depth => TextureSampleType::Depth,
+            SampleTypeFile::Sint => TextureSampleType::Sint,
+            SampleTypeFile::Uint => TextureSampleType::Uint,
+        }
+    }
+}
+
+/// Shared bind groups are either the layouts of the scene or custom bindings.
+#[derive(Deserialize)]
+enum SharedBindGroupFile {
+    Lighting,
+    Environment,
+    Bindings(Vec<BindingFile>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PushConstantFile {
+    stages: Vec<StageFile>,
+    #[serde(default)]
+    offset: u32,
+    size: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DepthStencilFile {
+    #[serde(default = "default_true")]
+    write: bool,
+    /// Follows the depth mode of the graphics config without a compare function.
+    #[serde(default)]
+    compare: Option<CompareFile>,
+    #[serde(default)]
+    bias_constant: i32,
+    #[serde(default)]
+    bias_slope_scale: f32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum CompareFile {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl From<CompareFile> for CompareFunction {
+    fn from(compare: CompareFile) -> Self {
+        match compare {
+            CompareFile::Never => CompareFunction::Never,
+            CompareFile::Less => CompareFunction::Less,
+            CompareFile::Equal => CompareFunction::Equal,
+            CompareFile::LessEqual => CompareFunction::LessEqual,
+            CompareFile::Greater => CompareFunction::Greater,
+            CompareFile::NotEqual => CompareFunction::NotEqual,
+            CompareFile::GreaterEqual => CompareFunction::GreaterEqual,
+            CompareFile::Always => CompareFunction::Always,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColorTargetFile {
+    format: TargetFormatFile,
+    /// Replaces the target without a blend state.
+    #[serde(default)]
+    blend: Option<BlendFile>,
+}
+
+/// `Hdr` is the scene color format, `SwapChain` the preferred format of the surface.
+#[derive(Copy, Clone, Deserialize)]
+enum TargetFormatFile {
+    Hdr,
+    SwapChain,
+    R8Unorm,
+    Rg8Unorm,
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Bgra8Unorm,
+    Bgra8UnormSrgb,
+    R16Float,
+    Rg16Float,
+    Rgba16Float,
+    R32Float,
+    Rg32Float,
+    Rgba32Float,
+}
+
+impl TargetFormatFile {
+    fn resolve(self, drivers: &Drivers) -> TextureFormat {
+        match self {
+            TargetFormatFile::Hdr => HDR_FORMAT,
+            TargetFormatFile::SwapChain => drivers.swap_chain_format,
+            TargetFormatFile::R8Unorm => TextureFormat::R8Unorm,
+            TargetFormatFile::Rg8Unorm => TextureFormat::Rg8Unorm,
+            TargetFormatFile::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+            TargetFormatFile::Rgba8UnormSrgb => TextureFormat::Rgba8UnormSrgb,
+            TargetFormatFile::Bgra8Unorm => TextureFormat::Bgra8Unorm,
+            TargetFormatFile::Bgra8UnormSrgb => TextureFormat::Bgra8UnormSrgb,
+            TargetFormatFile::R16Float => TextureFormat::R16Float,
+            TargetFormatFile::Rg16Float => TextureFormat::Rg16Float,
+            TargetFormatFile::Rgba16Float => TextureFormat::Rgba16Float,
+            TargetFormatFile::R32Float => TextureFormat::R32Float,
+            TargetFormatFile::Rg32Float => TextureFormat::Rg32Float,
+            TargetFormatFile::Rgba32Float => TextureFormat::Rgba32Float,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+enum BlendFile {
+    /// Regular (non premultiplied) alpha blending.
+    AlphaBlending,
+    Custom {
+        color: BlendComponentFile,
+        alpha: BlendComponentFile,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlendComponentFile {
+    src_factor: BlendFactorFile,
+    dst_factor: BlendFactorFile,
+    /// Adds without an operation.
+    #[serde(default)]
+    operation: Option<BlendOperationFile>,
+}
+
+impl From<&BlendComponentFile> for BlendState {
+    fn from(component: &BlendComponentFile) -> Self {
+        Self {
+            src_factor: component.src_factor.into(),
+            dst_factor: component.dst_factor.into(),
+            operation: match component.operation {
+                None | Some(BlendOperationFile::Add) => BlendOperation::Add,
+                Some(BlendOperationFile::Subtract) => BlendOperation::Subtract,
+                Some(BlendOperationFile::ReverseSubtract) => BlendOperation::ReverseSubtract,
+                Some(BlendOperationFile::Min) => BlendOperation::Min,
+                Some(BlendOperationFile::Max) => BlendOperation::Max,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum BlendFactorFile {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstColor,
+    OneMinusDstColor,
+    DstAlpha,
+    OneMinusDstAlpha,
+}
+
+impl From<BlendFactorFile> for BlendFactor {
+    fn from(factor: BlendFactorFile) -> Self {
+        match factor {
+            BlendFactorFile::Zero => BlendFactor::Zero,
+            BlendFactorFile::One => BlendFactor::One,
+            BlendFactorFile::SrcColor => BlendFactor::SrcColor,
+            BlendFactorFile::OneMinusSrcColor => BlendFactor::OneMinusSrcColor,
+            BlendFactorFile::SrcAlpha => BlendFactor::SrcAlpha,
+            BlendFactorFile::OneMinusSrcAlpha => BlendFactor::OneMinusSrcAlpha,
+            BlendFactorFile::DstColor => BlendFactor::DstColor,
+            BlendFactorFile::OneMinusDstColor => BlendFactor::OneMinusDstColor,
+            BlendFactorFile::DstAlpha => BlendFactor::DstAlpha,
+            BlendFactorFile::OneMinusDstAlpha => BlendFactor::OneMinusDstAlpha,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Deserialize)]
+enum BlendOperationFile {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
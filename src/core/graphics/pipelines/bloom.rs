@@ -0,0 +1,135 @@
+use super::fullscreen::*;
+use super::prelude::*;
+
+pub use super::fullscreen::PushConstantData;
+
+/// Extracts the bright parts of the hdr image into the first bloom mip.
+pub struct BloomPrefilterPipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+/// Downsamples one bloom mip into the next smaller one.
+pub struct BloomDownsamplePipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+/// Blurs a bloom mip and adds it onto the next bigger one.
+pub struct BloomUpsamplePipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+impl Pipeline for BloomPrefilterPipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "BloomPrefilter";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        SINGLE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = PUSH_CONSTANT_RANGES;
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![HDR_FORMAT.into()],
+        });
+
+        Self { shader_pipeline }
+    }
+}
+
+impl Pipeline for BloomDownsamplePipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "BloomDownsample";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        SINGLE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = &[];
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![HDR_FORMAT.into()],
+        });
+
+        Self { shader_pipeline }
+    }
+}
+
+impl Pipeline for BloomUpsamplePipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "BloomUpsample";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        SINGLE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = &[];
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        // additive blending onto the bigger mip:
+        let additive = BlendState {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![ColorTargetState {
+                format: HDR_FORMAT,
+                alpha_blend: additive.clone(),
+                color_blend: additive,
+                write_mask: ColorWrite::ALL,
+            }],
+        });
+
+        Self { shader_pipeline }
+    }
+}
@@ -0,0 +1,69 @@
+use super::prelude::*;
+
+/// Fullscreen passes draw a single triangle generated from the vertex index.
+pub const FULLSCREEN_PRIMITIVE_STATE: PrimitiveState = PrimitiveState {
+    topology: PrimitiveTopology::TriangleList,
+    strip_index_format: None,
+    front_face: FrontFace::Ccw,
+    cull_mode: CullMode::None,
+    polygon_mode: PolygonMode::Fill,
+};
+
+pub const fn texture_entry(binding: u32) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+        },
+        count: None,
+    }
+}
+
+pub const fn cube_texture_entry(binding: u32) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::Cube,
+        },
+        count: None,
+    }
+}
+
+pub const fn sampler_entry(binding: u32) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Sampler {
+            comparison: false,
+            filtering: true,
+        },
+        count: None,
+    }
+}
+
+/// A single sampled texture followed by its sampler.
+pub const SINGLE_INPUT_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] =
+    &[texture_entry(0), sampler_entry(1)];
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PushConstantData {
+    pub params: Vector4<f32>,
+}
+
+unsafe impl Pod for PushConstantData {}
+unsafe impl Zeroable for PushConstantData {}
+
+pub const PUSH_CONSTANT_RANGES: &[PushConstantRange] = &[
+    // vec4 - pass parameters
+    PushConstantRange {
+        stages: ShaderStage::FRAGMENT,
+        range: (0..16),
+    },
+];
@@ -0,0 +1,44 @@
+use super::fullscreen::*;
+use super::prelude::*;
+
+pub use super::fullscreen::PushConstantData;
+
+pub struct FxaaPipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+impl Pipeline for FxaaPipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "Fxaa";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        SINGLE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = PUSH_CONSTANT_RANGES;
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let color_targets = smallvec![drivers.swap_chain_format.into()];
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets,
+        });
+
+        Self { shader_pipeline }
+    }
+}
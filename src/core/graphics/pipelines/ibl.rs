@@ -0,0 +1,129 @@
+use super::fullscreen::*;
+use super::prelude::*;
+
+pub use super::fullscreen::PushConstantData;
+
+pub const BRDF_LUT_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+
+/// Projects an equirectangular environment map onto a cubemap face.
+pub struct EquirectToCubePipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+/// Convolves the environment cubemap into diffuse irradiance.
+pub struct IrradiancePipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+/// Prefilters the environment cubemap for one specular roughness level.
+pub struct PrefilterPipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+const CUBE_INPUT_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] =
+    &[cube_texture_entry(0), sampler_entry(1)];
+
+impl Pipeline for EquirectToCubePipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "EquirectToCube";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        SINGLE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = PUSH_CONSTANT_RANGES;
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![HDR_FORMAT.into()],
+        });
+
+        Self { shader_pipeline }
+    }
+}
+
+impl Pipeline for IrradiancePipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "Irradiance";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        CUBE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = PUSH_CONSTANT_RANGES;
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![HDR_FORMAT.into()],
+        });
+
+        Self { shader_pipeline }
+    }
+}
+
+impl Pipeline for PrefilterPipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "Prefilter";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        CUBE_INPUT_BIND_GROUP_LAYOUT_ENTRIES;
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = PUSH_CONSTANT_RANGES;
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![HDR_FORMAT.into()],
+        });
+
+        Self { shader_pipeline }
+    }
+}
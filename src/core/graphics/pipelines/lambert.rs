@@ -2,6 +2,10 @@ use super::prelude::*;
 
 pub struct LambertPipeline {
     pub shader_pipeline: ShaderPipeline,
+    /// Alpha blended variant without depth writes, for the transparent render queue.
+    pub transparent_pipeline: ShaderPipeline,
+    /// Keyword variants of the opaque pipeline, like the alpha tested one.
+    pub variants: PipelineVariants<Self>,
 }
 
 #[derive(Copy, Clone)]
@@ -13,6 +17,18 @@ pub struct PushConstantData {
 unsafe impl Pod for PushConstantData {}
 unsafe impl Zeroable for PushConstantData {}
 
+pub const MATERIAL_PUSH_CONSTANT_OFFSET: u32 = 128;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct MaterialPushConstantData {
+    /// x: receive shadows, y: alpha cutoff, z: alpha to coverage, w: alpha blended
+    pub params: Vector4<f32>,
+}
+
+unsafe impl Pod for MaterialPushConstantData {}
+unsafe impl Zeroable for MaterialPushConstantData {}
+
 impl Pipeline for LambertPipeline {
     type PushConstantData = PushConstantData;
 
@@ -42,6 +58,9 @@ impl Pipeline for LambertPipeline {
         },
     ];
 
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] =
+        &[LIGHTING_BIND_GROUP_LAYOUT_ENTRIES];
+
     const PRIMITIVE_STATE: PrimitiveState = PrimitiveState {
         topology: PrimitiveTopology::TriangleList,
         strip_index_format: None,
@@ -55,7 +74,8 @@ impl Pipeline for LambertPipeline {
         step_mode: InputStepMode::Vertex,
         attributes: &vertex_attr_array![
             0 => Float4,
-            1 => Float2
+            1 => Float2,
+            2 => Float3
         ],
     }];
 
@@ -65,6 +85,11 @@ impl Pipeline for LambertPipeline {
             stages: ShaderStage::VERTEX,
             range: (0..128),
         },
+        // vec4 - material parameters
+        PushConstantRange {
+            stages: ShaderStage::FRAGMENT,
+            range: (128..144),
+        },
     ];
 
     const DEPTH_STENCIL_STATE: Option<DepthStencilState> = Some(DepthStencilState {
@@ -85,21 +110,58 @@ impl Pipeline for LambertPipeline {
         clamp_depth: false,
     });
 
+    const KEYWORDS: &'static [&'static str] = &[ALPHA_TEST_KEYWORD];
+
     #[inline]
     fn shader_pipeline(&self) -> &ShaderPipeline {
         &self.shader_pipeline
     }
 
     fn create(drivers: &mut Drivers, config: &CoreConfig) -> Self {
+        // alpha tested materials use alpha to coverage when multi sampling:
         let multi_sample_state = MultisampleState {
             count: config.graphics_config.msaa_mode as u32,
             mask: !0,
-            alpha_to_coverage_enabled: false,
+            alpha_to_coverage_enabled: config.graphics_config.msaa_mode != MsaaMode::Off,
         };
 
-        let shader_pipeline =
-            drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor { multi_sample_state });
+        let color_targets = smallvec![HDR_FORMAT.into()];
+
+        let depth_stencil_state = Self::DEPTH_STENCIL_STATE.map(|state| DepthStencilState {
+            depth_compare: depth_compare(config.graphics_config.depth_mode),
+            ..state
+        });
+
+        let desc = ShaderPipelineDescriptor {
+            multi_sample_state: multi_sample_state.clone(),
+            color_targets,
+        };
+        let shader_pipeline = ShaderPipeline::create_shader_bundle_with_depth_stencil::<Self>(
+            drivers,
+            desc.clone(),
+            depth_stencil_state.clone(),
+        );
+        let variants = PipelineVariants::new(desc, depth_stencil_state.clone());
+
+        let transparent_pipeline = ShaderPipeline::create_shader_bundle_with_depth_stencil::<Self>(
+            drivers,
+            ShaderPipelineDescriptor {
+                multi_sample_state: MultisampleState {
+                    alpha_to_coverage_enabled: false,
+                    ..multi_sample_state
+                },
+                color_targets: smallvec![alpha_blended_color_target(HDR_FORMAT)],
+            },
+            depth_stencil_state.map(|state| DepthStencilState {
+                depth_write_enabled: false,
+                ..state
+            }),
+        );
 
-        Self { shader_pipeline }
+        Self {
+            shader_pipeline,
+            transparent_pipeline,
+            variants,
+        }
     }
 }
@@ -1,2 +1,77 @@
+pub mod bloom;
+mod fullscreen;
+pub mod fxaa;
+pub mod ibl;
 pub mod lambert;
+pub mod pbr;
 mod prelude;
+pub mod shadow;
+pub mod tonemap;
+
+use super::mipgen;
+use super::pipeline::{check, check_description, precompile, precompile_description};
+use super::pipeline_definition::{find_definitions, PipelineDefinition};
+use super::shader_compiler::{ShaderCompiler, ShaderDefines, ShaderError, ShaderKind};
+use crate::config::DepthMode;
+use std::path::Path;
+
+/// Compiles the shaders of every pipeline, including the ones of pipeline definition files, and the mipgen blit shaders into the shader cache.
+pub fn precompile_all(compiler: &mut ShaderCompiler) {
+    precompile::<lambert::LambertPipeline>(compiler);
+    precompile::<pbr::PbrPipeline>(compiler);
+    precompile::<shadow::ShadowPipeline>(compiler);
+    precompile::<bloom::BloomPrefilterPipeline>(compiler);
+    precompile::<bloom::BloomDownsamplePipeline>(compiler);
+    precompile::<bloom::BloomUpsamplePipeline>(compiler);
+    precompile::<tonemap::TonemapPipeline>(compiler);
+    precompile::<fxaa::FxaaPipeline>(compiler);
+    precompile::<ibl::EquirectToCubePipeline>(compiler);
+    precompile::<ibl::IrradiancePipeline>(compiler);
+    precompile::<ibl::PrefilterPipeline>(compiler);
+    for name in find_definitions() {
+        let definition =
+            PipelineDefinition::load(&name).unwrap_or_else(|error| panic!("{}", error));
+        precompile_description(compiler, &definition.description(DepthMode::Standard));
+    }
+    let defines = ShaderDefines::default();
+    compiler.compile(
+        Path::new(mipgen::BLIT_VS_PATH),
+        ShaderKind::Vertex,
+        &defines,
+    );
+    compiler.compile(
+        Path::new(mipgen::BLIT_FS_PATH),
+        ShaderKind::Fragment,
+        &defines,
+    );
+}
+
+/// Compiles and validates the shaders and definition files of every pipeline for the push
+/// constant support of the adapter, returning the errors instead of panicking.
+pub fn check_all(compiler: &mut ShaderCompiler, emulated_push_constants: bool) -> Vec<ShaderError> {
+    let mut errors = Vec::new();
+    let emulated = emulated_push_constants;
+    check::<lambert::LambertPipeline>(compiler, emulated, &mut errors);
+    check::<pbr::PbrPipeline>(compiler, emulated, &mut errors);
+    check::<shadow::ShadowPipeline>(compiler, emulated, &mut errors);
+    check::<bloom::BloomPrefilterPipeline>(compiler, emulated, &mut errors);
+    check::<bloom::BloomDownsamplePipeline>(compiler, emulated, &mut errors);
+    check::<bloom::BloomUpsamplePipeline>(compiler, emulated, &mut errors);
+    check::<tonemap::TonemapPipeline>(compiler, emulated, &mut errors);
+    check::<fxaa::FxaaPipeline>(compiler, emulated, &mut errors);
+    check::<ibl::EquirectToCubePipeline>(compiler, emulated, &mut errors);
+    check::<ibl::IrradiancePipeline>(compiler, emulated, &mut errors);
+    check::<ibl::PrefilterPipeline>(compiler, emulated, &mut errors);
+    for name in find_definitions() {
+        match PipelineDefinition::load(&name) {
+            Ok(definition) => check_description(
+                compiler,
+                &definition.description(DepthMode::Standard),
+                emulated,
+                &mut errors,
+            ),
+            Err(error) => errors.push(error),
+        }
+    }
+    errors
+}
@@ -0,0 +1,204 @@
+use super::fullscreen::{sampler_entry, texture_entry};
+use super::prelude::*;
+
+pub struct PbrPipeline {
+    pub shader_pipeline: ShaderPipeline,
+    /// Alpha blended variant without depth writes, for the transparent render queue.
+    pub transparent_pipeline: ShaderPipeline,
+    /// Keyword variants of the opaque pipeline, like the alpha tested one.
+    pub variants: PipelineVariants<Self>,
+    /// Bound in place of missing albedo, metallic-roughness and occlusion maps.
+    pub fallback_white: TextureView,
+    /// Bound in place of a missing normal map.
+    pub fallback_normal: TextureView,
+    /// Bound in place of a missing emissive map.
+    pub fallback_black: TextureView,
+}
+
+#[derive(Copy, Clone)]
+pub struct PushConstantData {
+    pub world_matrix: Matrix4<f32>,
+    pub view_proj_matrix: Matrix4<f32>,
+}
+
+unsafe impl Pod for PushConstantData {}
+unsafe impl Zeroable for PushConstantData {}
+
+pub const MATERIAL_PUSH_CONSTANT_OFFSET: u32 = 128;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct MaterialPushConstantData {
+    pub base_color: Vector4<f32>,
+    /// x: metallic, y: roughness, z: occlusion strength, w: normal scale
+    pub params: Vector4<f32>,
+    /// w: receive shadows
+    pub emissive: Vector4<f32>,
+    /// x: alpha cutoff, y: alpha to coverage, z: alpha blended
+    pub alpha: Vector4<f32>,
+}
+
+unsafe impl Pod for MaterialPushConstantData {}
+unsafe impl Zeroable for MaterialPushConstantData {}
+
+impl Pipeline for PbrPipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "Pbr";
+
+    const IS_SURFACE_PIPELINE: bool = true;
+
+    // albedo, normal, metallic-roughness, occlusion, emissive, sampler
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] = &[
+        texture_entry(0),
+        texture_entry(1),
+        texture_entry(2),
+        texture_entry(3),
+        texture_entry(4),
+        sampler_entry(5),
+    ];
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[
+        LIGHTING_BIND_GROUP_LAYOUT_ENTRIES,
+        ENVIRONMENT_BIND_GROUP_LAYOUT_ENTRIES,
+    ];
+
+    const PRIMITIVE_STATE: PrimitiveState = PrimitiveState {
+        topology: PrimitiveTopology::TriangleList,
+        strip_index_format: None,
+        front_face: FrontFace::Ccw,
+        cull_mode: CullMode::Back,
+        polygon_mode: PolygonMode::Fill,
+    };
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[VertexBufferLayout {
+        array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
+        step_mode: InputStepMode::Vertex,
+        attributes: &vertex_attr_array![
+            0 => Float4,
+            1 => Float2,
+            2 => Float3
+        ],
+    }];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = &[
+        // 2 * mat4x4 - word matrix, view projection matrix
+        PushConstantRange {
+            stages: ShaderStage::VERTEX,
+            range: (0..128),
+        },
+        // 4 * vec4 - base color, material parameters, emissive, alpha
+        PushConstantRange {
+            stages: ShaderStage::FRAGMENT,
+            range: (128..192),
+        },
+    ];
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = Some(DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: true,
+        depth_compare: CompareFunction::Less,
+        stencil: StencilState {
+            front: StencilFaceState::IGNORE,
+            back: StencilFaceState::IGNORE,
+            write_mask: 0,
+            read_mask: 0,
+        },
+        bias: DepthBiasState {
+            constant: 0,
+            slope_scale: 0.0,
+            clamp: 0.0,
+        },
+        clamp_depth: false,
+    });
+
+    const KEYWORDS: &'static [&'static str] = &[ALPHA_TEST_KEYWORD];
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, config: &CoreConfig) -> Self {
+        // alpha tested materials use alpha to coverage when multi sampling:
+        let multi_sample_state = MultisampleState {
+            count: config.graphics_config.msaa_mode as u32,
+            mask: !0,
+            alpha_to_coverage_enabled: config.graphics_config.msaa_mode != MsaaMode::Off,
+        };
+
+        let color_targets = smallvec![HDR_FORMAT.into()];
+
+        let depth_stencil_state = Self::DEPTH_STENCIL_STATE.map(|state| DepthStencilState {
+            depth_compare: depth_compare(config.graphics_config.depth_mode),
+            ..state
+        });
+
+        let desc = ShaderPipelineDescriptor {
+            multi_sample_state: multi_sample_state.clone(),
+            color_targets,
+        };
+        let shader_pipeline = ShaderPipeline::create_shader_bundle_with_depth_stencil::<Self>(
+            drivers,
+            desc.clone(),
+            depth_stencil_state.clone(),
+        );
+        let variants = PipelineVariants::new(desc, depth_stencil_state.clone());
+
+        let transparent_pipeline = ShaderPipeline::create_shader_bundle_with_depth_stencil::<Self>(
+            drivers,
+            ShaderPipelineDescriptor {
+                multi_sample_state: MultisampleState {
+                    alpha_to_coverage_enabled: false,
+                    ..multi_sample_state
+                },
+                color_targets: smallvec![alpha_blended_color_target(HDR_FORMAT)],
+            },
+            depth_stencil_state.map(|state| DepthStencilState {
+                depth_write_enabled: false,
+                ..state
+            }),
+        );
+
+        Self {
+            shader_pipeline,
+            transparent_pipeline,
+            variants,
+            fallback_white: create_fallback_texture(drivers, [255, 255, 255, 255]),
+            fallback_normal: create_fallback_texture(drivers, [128, 128, 255, 255]),
+            fallback_black: create_fallback_texture(drivers, [0, 0, 0, 255]),
+        }
+    }
+}
+
+fn create_fallback_texture(drivers: &Drivers, texel: [u8; 4]) -> TextureView {
+    let extent = Extent3d {
+        width: 1,
+        height: 1,
+        depth: 1,
+    };
+    let texture = drivers.device.create_texture(&TextureDescriptor {
+        label: Some("fallback"),
+        size: extent,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+    });
+    drivers.queue.write_texture(
+        TextureCopyView {
+            texture: &texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+        },
+        &texel,
+        TextureDataLayout {
+            offset: 0,
+            bytes_per_row: 4,
+            rows_per_image: 0,
+        },
+        extent,
+    );
+    texture.create_view(&TextureViewDescriptor::default())
+}
@@ -1,8 +1,14 @@
-pub use crate::config::CoreConfig;
-pub use crate::core::graphics::boot::DEPTH_FORMAT;
+pub use crate::config::{CoreConfig, MsaaMode};
+pub use crate::core::graphics::boot::{depth_compare, DEPTH_FORMAT, HDR_FORMAT};
 pub use crate::core::graphics::drivers::Drivers;
-pub use crate::core::graphics::pipeline::{Pipeline, ShaderPipeline, ShaderPipelineDescriptor};
+pub use crate::core::graphics::environment::ENVIRONMENT_BIND_GROUP_LAYOUT_ENTRIES;
+pub use crate::core::graphics::lighting::LIGHTING_BIND_GROUP_LAYOUT_ENTRIES;
+pub use crate::core::graphics::pipeline::{
+    alpha_blended_color_target, Pipeline, PipelineVariants, ShaderPipeline,
+    ShaderPipelineDescriptor, ALPHA_TEST_KEYWORD,
+};
 pub use crate::resources::{material::*, mesh::*, texture::*};
 pub use bytemuck::{Pod, Zeroable};
 pub use cgmath::*;
+pub use smallvec::smallvec;
 pub use wgpu::*;
@@ -0,0 +1,130 @@
+use super::prelude::*;
+
+pub struct ShadowPipeline {
+    pub shader_pipeline: ShaderPipeline,
+    /// Keyword variants, like the alpha tested one which discards by the albedo alpha.
+    pub variants: PipelineVariants<Self>,
+}
+
+#[derive(Copy, Clone)]
+pub struct PushConstantData {
+    pub world_matrix: Matrix4<f32>,
+    pub light_view_proj_matrix: Matrix4<f32>,
+}
+
+unsafe impl Pod for PushConstantData {}
+unsafe impl Zeroable for PushConstantData {}
+
+pub const MATERIAL_PUSH_CONSTANT_OFFSET: u32 = 128;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct MaterialPushConstantData {
+    /// x: alpha cutoff, y: alpha factor, like the base color alpha of pbr materials
+    pub params: Vector4<f32>,
+}
+
+unsafe impl Pod for MaterialPushConstantData {}
+unsafe impl Zeroable for MaterialPushConstantData {}
+
+impl Pipeline for ShadowPipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "Shadow";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    /// Albedo texture and sampler, only sampled by the alpha tested variant.
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] = &[
+        BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStage::FRAGMENT,
+            ty: BindingType::Texture {
+                multisampled: false,
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+            },
+            count: None,
+        },
+        BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStage::FRAGMENT,
+            ty: BindingType::Sampler {
+                comparison: false,
+                filtering: true,
+            },
+            count: None,
+        },
+    ];
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] = &[];
+
+    const PRIMITIVE_STATE: PrimitiveState = PrimitiveState {
+        topology: PrimitiveTopology::TriangleList,
+        strip_index_format: None,
+        front_face: FrontFace::Ccw,
+        cull_mode: CullMode::Back,
+        polygon_mode: PolygonMode::Fill,
+    };
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[VertexBufferLayout {
+        array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
+        step_mode: InputStepMode::Vertex,
+        attributes: &vertex_attr_array![
+            0 => Float4,
+            1 => Float2
+        ],
+    }];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = &[
+        // 2 * mat4x4 - word matrix, light view projection matrix
+        PushConstantRange {
+            stages: ShaderStage::VERTEX,
+            range: (0..128),
+        },
+        // vec4 - material parameters
+        PushConstantRange {
+            stages: ShaderStage::FRAGMENT,
+            range: (128..144),
+        },
+    ];
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = Some(DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: true,
+        depth_compare: CompareFunction::LessEqual,
+        stencil: StencilState {
+            front: StencilFaceState::IGNORE,
+            back: StencilFaceState::IGNORE,
+            write_mask: 0,
+            read_mask: 0,
+        },
+        bias: DepthBiasState {
+            constant: 2,
+            slope_scale: 2.0,
+            clamp: 0.0,
+        },
+        clamp_depth: false,
+    });
+
+    const KEYWORDS: &'static [&'static str] = &[ALPHA_TEST_KEYWORD];
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let desc = ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets: smallvec![],
+        };
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(desc.clone());
+        let variants = PipelineVariants::new(desc, Self::DEPTH_STENCIL_STATE);
+
+        Self {
+            shader_pipeline,
+            variants,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use super::fullscreen::*;
+use super::prelude::*;
+
+pub struct TonemapPipeline {
+    pub shader_pipeline: ShaderPipeline,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PushConstantData {
+    /// x: exposure, y: 1 / gamma, z: bloom intensity, w: tonemapper
+    pub params: Vector4<f32>,
+    /// x: color grading intensity
+    pub grading: Vector4<f32>,
+}
+
+unsafe impl Pod for PushConstantData {}
+unsafe impl Zeroable for PushConstantData {}
+
+/// Color grading lookup table, a 256x16 strip of 16 slices.
+pub const LUT_BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] =
+    &[texture_entry(0), sampler_entry(1)];
+
+impl Pipeline for TonemapPipeline {
+    type PushConstantData = PushConstantData;
+
+    const NAME: &'static str = "Tonemap";
+
+    const IS_SURFACE_PIPELINE: bool = false;
+
+    // hdr color, bloom, sampler
+    const PER_MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &'static [BindGroupLayoutEntry] =
+        &[texture_entry(0), texture_entry(1), sampler_entry(2)];
+
+    const SHARED_BIND_GROUP_LAYOUT_ENTRIES: &'static [&'static [BindGroupLayoutEntry]] =
+        &[LUT_BIND_GROUP_LAYOUT_ENTRIES];
+
+    const PRIMITIVE_STATE: PrimitiveState = FULLSCREEN_PRIMITIVE_STATE;
+
+    const VERTEX_BUFFER_LAYOUTS: &'static [VertexBufferLayout<'static>] = &[];
+
+    const PUSH_CONSTANT_RANGES: &'static [PushConstantRange] = &[
+        // 2 * vec4 - parameters, color grading
+        PushConstantRange {
+            stages: ShaderStage::FRAGMENT,
+            range: (0..32),
+        },
+    ];
+
+    const DEPTH_STENCIL_STATE: Option<DepthStencilState> = None;
+
+    #[inline]
+    fn shader_pipeline(&self) -> &ShaderPipeline {
+        &self.shader_pipeline
+    }
+
+    fn create(drivers: &mut Drivers, _config: &CoreConfig) -> Self {
+        let color_targets = smallvec![drivers.swap_chain_format.into()];
+        let shader_pipeline = drivers.create_shader_pipeline::<Self>(ShaderPipelineDescriptor {
+            multi_sample_state: MultisampleState::default(),
+            color_targets,
+        });
+
+        Self { shader_pipeline }
+    }
+}
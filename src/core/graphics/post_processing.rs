@@ -0,0 +1,358 @@
+use super::boot::HDR_FORMAT;
+use super::drivers::Drivers;
+use super::pipeline::Pipeline;
+use super::pipelines::{bloom, fxaa, tonemap};
+use super::render_graph::{RenderGraph, TextureHandle, TextureSize, TransientTextureDescriptor};
+use crate::config::{CoreConfig, PostProcessingConfig, Tonemapper};
+use cgmath::Vector4;
+use log::{info, warn};
+use smallvec::SmallVec;
+use std::path::Path;
+use std::sync::Arc;
+use wgpu::*;
+
+pub const MAX_BLOOM_PASSES: u8 = 8;
+
+/// Width of one slice (and number of slices) in the color grading lut.
+pub const LUT_SIZE: u32 = 16;
+
+/// Gives post processing passes access to their pipelines while the graph executes.
+pub trait PostProcessingContext {
+    fn post_processing(&self) -> &PostProcessing;
+}
+
+pub struct PostProcessing {
+    pub config: PostProcessingConfig,
+    pub gamma: f32,
+    pub output_format: TextureFormat,
+    pub bloom_prefilter_pipeline: bloom::BloomPrefilterPipeline,
+    pub bloom_downsample_pipeline: bloom::BloomDownsamplePipeline,
+    pub bloom_upsample_pipeline: bloom::BloomUpsamplePipeline,
+    pub tonemap_pipeline: tonemap::TonemapPipeline,
+    pub fxaa_pipeline: fxaa::FxaaPipeline,
+    pub sampler: Arc<Sampler>,
+    pub lut_texture: Texture,
+    pub lut_bind_group: BindGroup,
+}
+
+impl PostProcessing {
+    pub fn new(drivers: &mut Drivers, config: &CoreConfig) -> Self {
+        let mut post_config = config.graphics_config.post_processing.clone();
+        post_config.bloom_passes = post_config.bloom_passes.min(MAX_BLOOM_PASSES);
+        let gamma = if config.display_config.gamma_offset > 0.0 {
+            config.display_config.gamma_offset
+        } else {
+            warn!(
+                "Invalid gamma offset: {}, using 1.0",
+                config.display_config.gamma_offset
+            );
+            1.0
+        };
+
+        let bloom_prefilter_pipeline = bloom::BloomPrefilterPipeline::create(drivers, config);
+        let bloom_downsample_pipeline = bloom::BloomDownsamplePipeline::create(drivers, config);
+        let bloom_upsample_pipeline = bloom::BloomUpsamplePipeline::create(drivers, config);
+        let tonemap_pipeline = tonemap::TonemapPipeline::create(drivers, config);
+        let fxaa_pipeline = fxaa::FxaaPipeline::create(drivers, config);
+
+        let sampler = drivers.pipeline_cache.sampler(
+            &drivers.device,
+            &SamplerDescriptor {
+                label: Some("post processing"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Nearest,
+                ..Default::default()
+            },
+        );
+
+        let lut_texels = match &post_config.color_grading_lut {
+            Some(path) => load_lut(Path::new(path)).unwrap_or_else(|| {
+                post_config.color_grading_intensity = 0.0;
+                identity_lut()
+            }),
+            None => identity_lut(),
+        };
+        let lut_extent = Extent3d {
+            width: LUT_SIZE * LUT_SIZE,
+            height: LUT_SIZE,
+            depth: 1,
+        };
+        let lut_texture = drivers.device.create_texture(&TextureDescriptor {
+            label: Some("color grading lut"),
+            size: lut_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+        });
+        drivers.queue.write_texture(
+            TextureCopyView {
+                texture: &lut_texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+            },
+            &lut_texels,
+            TextureDataLayout {
+                offset: 0,
+                bytes_per_row: 4 * lut_extent.width,
+                rows_per_image: 0,
+            },
+            lut_extent,
+        );
+        let lut_view = lut_texture.create_view(&TextureViewDescriptor::default());
+        let lut_bind_group = drivers.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("color grading lut"),
+            layout: &tonemap_pipeline.shader_pipeline.shared_bind_group_layouts[0],
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&lut_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            config: post_config,
+            gamma,
+            output_format: drivers.swap_chain_format,
+            bloom_prefilter_pipeline,
+            bloom_downsample_pipeline,
+            bloom_upsample_pipeline,
+            tonemap_pipeline,
+            fxaa_pipeline,
+            sampler,
+            lut_texture,
+            lut_bind_group,
+        }
+    }
+
+    /// Adds the post processing chain, which resolves the hdr scene color into the output.
+    pub fn build<'a, C: PostProcessingContext>(
+        &'a self,
+        graph: &mut RenderGraph<'a, C>,
+        hdr: TextureHandle,
+        output: TextureHandle,
+    ) {
+        let config = &self.config;
+        let enabled = config.enabled;
+
+        // without bloom the hdr image is bound in its place and weighted with zero:
+        let mut bloom = hdr;
+        if enabled && config.bloom && config.bloom_passes > 0 {
+            let mips = (0..config.bloom_passes)
+                .map(|i| {
+                    graph.create_texture(
+                        "bloom",
+                        TransientTextureDescriptor {
+                            size: TextureSize::Relative(2 << i),
+                            format: HDR_FORMAT,
+                            sample_count: 1,
+                        },
+                    )
+                })
+                .collect::<SmallVec<[TextureHandle; MAX_BLOOM_PASSES as usize]>>();
+
+            let push_constant_data = bloom::PushConstantData {
+                params: Vector4::new(config.bloom_threshold, config.bloom_knee, 0.0, 0.0),
+            };
+            graph
+                .add_pass("bloom prefilter")
+                .color(mips[0], LoadOp::Clear(Color::BLACK))
+                .sample(hdr)
+                .bind_inputs(
+                    0,
+                    &self
+                        .bloom_prefilter_pipeline
+                        .shader_pipeline
+                        .per_material_bind_group_layout,
+                    &self.sampler,
+                )
+                .execute(move |pass, ctx| {
+                    pass.set_pipeline(&ctx.post_processing().bloom_prefilter_pipeline);
+                    pass.set_push_constans(
+                        ShaderStage::FRAGMENT,
+                        0,
+                        bytemuck::bytes_of(&push_constant_data),
+                    );
+                    pass.draw_fullscreen_triangle();
+                });
+
+            for i in 1..mips.len() {
+                graph
+                    .add_pass("bloom downsample")
+                    .color(mips[i], LoadOp::Clear(Color::BLACK))
+                    .sample(mips[i - 1])
+                    .bind_inputs(
+                        0,
+                        &self
+                            .bloom_downsample_pipeline
+                            .shader_pipeline
+                            .per_material_bind_group_layout,
+                        &self.sampler,
+                    )
+                    .execute(|pass, ctx| {
+                        pass.set_pipeline(&ctx.post_processing().bloom_downsample_pipeline);
+                        pass.draw_fullscreen_triangle();
+                    });
+            }
+
+            // accumulate from the smallest mip upwards:
+            for i in (1..mips.len()).rev() {
+                graph
+                    .add_pass("bloom upsample")
+                    .color(mips[i - 1], LoadOp::Load)
+                    .sample(mips[i])
+                    .bind_inputs(
+                        0,
+                        &self
+                            .bloom_upsample_pipeline
+                            .shader_pipeline
+                            .per_material_bind_group_layout,
+                        &self.sampler,
+                    )
+                    .execute(|pass, ctx| {
+                        pass.set_pipeline(&ctx.post_processing().bloom_upsample_pipeline);
+                        pass.draw_fullscreen_triangle();
+                    });
+            }
+
+            bloom = mips[0];
+        }
+
+        let fxaa = enabled && config.fxaa;
+        let tonemap_target = if fxaa {
+            graph.create_texture(
+                "ldr color",
+                TransientTextureDescriptor {
+                    size: TextureSize::Relative(1),
+                    format: self.output_format,
+                    sample_count: 1,
+                },
+            )
+        } else {
+            output
+        };
+
+        let push_constant_data = if enabled {
+            tonemap::PushConstantData {
+                params: Vector4::new(
+                    config.exposure,
+                    1.0 / self.gamma,
+                    if bloom == hdr {
+                        0.0
+                    } else {
+                        config.bloom_intensity
+                    },
+                    config.tonemapper as u8 as f32,
+                ),
+                grading: Vector4::new(config.color_grading_intensity, 0.0, 0.0, 0.0),
+            }
+        } else {
+            tonemap::PushConstantData {
+                params: Vector4::new(1.0, 1.0 / self.gamma, 0.0, Tonemapper::Clamp as u8 as f32),
+                grading: Vector4::new(0.0, 0.0, 0.0, 0.0),
+            }
+        };
+        graph
+            .add_pass("tonemap")
+            .color(tonemap_target, LoadOp::Clear(Color::BLACK))
+            .sample(hdr)
+            .sample(bloom)
+            .bind_inputs(
+                0,
+                &self
+                    .tonemap_pipeline
+                    .shader_pipeline
+                    .per_material_bind_group_layout,
+                &self.sampler,
+            )
+            .execute(move |pass, ctx| {
+                let post_processing = ctx.post_processing();
+                pass.set_pipeline(&post_processing.tonemap_pipeline);
+                pass.set_bind_group(1, &post_processing.lut_bind_group);
+                pass.set_push_constans(
+                    ShaderStage::FRAGMENT,
+                    0,
+                    bytemuck::bytes_of(&push_constant_data),
+                );
+                pass.draw_fullscreen_triangle();
+            });
+
+        if fxaa {
+            // x: subpixel blend, y: edge threshold, z: minimum edge threshold
+            let push_constant_data = fxaa::PushConstantData {
+                params: Vector4::new(0.75, 0.125, 0.0312, 0.0),
+            };
+            graph
+                .add_pass("fxaa")
+                .color(output, LoadOp::Clear(Color::BLACK))
+                .sample(tonemap_target)
+                .bind_inputs(
+                    0,
+                    &self
+                        .fxaa_pipeline
+                        .shader_pipeline
+                        .per_material_bind_group_layout,
+                    &self.sampler,
+                )
+                .execute(move |pass, ctx| {
+                    pass.set_pipeline(&ctx.post_processing().fxaa_pipeline);
+                    pass.set_push_constans(
+                        ShaderStage::FRAGMENT,
+                        0,
+                        bytemuck::bytes_of(&push_constant_data),
+                    );
+                    pass.draw_fullscreen_triangle();
+                });
+        }
+    }
+}
+
+/// Red grows along x inside each slice, green along y (top to bottom) and blue across the slices.
+fn identity_lut() -> Vec<u8> {
+    let max = (LUT_SIZE - 1) as f32;
+    let mut texels = Vec::with_capacity((LUT_SIZE * LUT_SIZE * LUT_SIZE * 4) as usize);
+    for g in 0..LUT_SIZE {
+        for b in 0..LUT_SIZE {
+            for r in 0..LUT_SIZE {
+                texels.push((r as f32 / max * 255.0).round() as u8);
+                texels.push((g as f32 / max * 255.0).round() as u8);
+                texels.push((b as f32 / max * 255.0).round() as u8);
+                texels.push(255);
+            }
+        }
+    }
+    texels
+}
+
+fn load_lut(path: &Path) -> Option<Vec<u8>> {
+    info!("Loading color grading lut: {:?}", path);
+    let image = match image::open(path) {
+        Ok(image) => image.into_rgba8(),
+        Err(error) => {
+            warn!("Failed to load color grading lut {:?}: {}", path, error);
+            return None;
+        }
+    };
+    if image.width() != LUT_SIZE * LUT_SIZE || image.height() != LUT_SIZE {
+        warn!(
+            "Color grading lut {:?} must be {}x{}, but is {}x{}",
+            path,
+            LUT_SIZE * LUT_SIZE,
+            LUT_SIZE,
+            image.width(),
+            image.height()
+        );
+        return None;
+    }
+    Some(image.into_raw())
+}
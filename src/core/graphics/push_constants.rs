@@ -0,0 +1,103 @@
+//! Uniform buffer emulation of push constants, for adapters without `Features::PUSH_CONSTANTS`
+//! (e.g. software or GL adapters).
+//! Every draw gets its own slot of a per frame uniform buffer, bound with a dynamic offset to the
+//! bind group after the regular ones. The shaders declare their push constant blocks with the
+//! `PUSH_CONSTANTS` layout macro, which the shader compiler defines for either path.
+
+use log::warn;
+use std::cell::{Cell, RefCell};
+use std::num::NonZeroU64;
+use wgpu::*;
+
+/// Size of a slot, covering the largest push constant range and the uniform offset alignment.
+pub const SLOT_SIZE: usize = 256;
+
+/// Slots per submission. Draws beyond it overwrite the last slot.
+pub const SLOT_COUNT: usize = 4096;
+
+/// Push constant space the pipelines need, adapters with less use the emulation.
+pub const MIN_PUSH_CONSTANT_SIZE: u32 = 192;
+
+/// Layout of the slot buffer, bound after the regular bind groups.
+pub const BIND_GROUP_LAYOUT_ENTRIES: &[BindGroupLayoutEntry] = &[BindGroupLayoutEntry {
+    binding: 0,
+    visibility: ShaderStage::from_bits_truncate(
+        ShaderStage::VERTEX.bits() | ShaderStage::FRAGMENT.bits(),
+    ),
+    ty: BindingType::Buffer {
+        ty: BufferBindingType::Uniform,
+        has_dynamic_offset: true,
+        min_binding_size: None,
+    },
+    count: None,
+}];
+
+pub struct PushConstantEmulation {
+    pub bind_group_layout: BindGroupLayout,
+    pub bind_group: BindGroup,
+    buffer: Buffer,
+    slots: RefCell<Vec<u8>>,
+    overflowed: Cell<bool>,
+}
+
+impl PushConstantEmulation {
+    pub fn new(device: &Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("push constants"),
+            entries: BIND_GROUP_LAYOUT_ENTRIES,
+        });
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("push constants"),
+            size: (SLOT_SIZE * SLOT_COUNT) as BufferAddress,
+            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("push constants"),
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(SLOT_SIZE as u64),
+                },
+            }],
+        });
+        Self {
+            bind_group_layout,
+            bind_group,
+            buffer,
+            slots: RefCell::new(Vec::with_capacity(SLOT_SIZE * SLOT_COUNT)),
+            overflowed: Cell::new(false),
+        }
+    }
+
+    /// Stores the block of a draw and returns its dynamic offset.
+    pub fn push(&self, block: &[u8; SLOT_SIZE]) -> u32 {
+        let mut slots = self.slots.borrow_mut();
+        if slots.len() == SLOT_SIZE * SLOT_COUNT {
+            if !self.overflowed.replace(true) {
+                warn!(
+                    "More than {} draws with emulated push constants, reusing the last slot!",
+                    SLOT_COUNT
+                );
+            }
+            let last = slots.len() - SLOT_SIZE;
+            slots[last..].copy_from_slice(block);
+            return last as u32;
+        }
+        let offset = slots.len();
+        slots.extend_from_slice(block);
+        offset as u32
+    }
+
+    /// Writes the blocks of the recorded draws, must be called before submitting them.
+    pub fn upload(&self, queue: &Queue) {
+        let mut slots = self.slots.borrow_mut();
+        if !slots.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &slots[..]);
+            slots.clear();
+        }
+    }
+}
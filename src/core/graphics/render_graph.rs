@@ -0,0 +1,557 @@
+use super::frame::Frame;
+use super::pass::Pass;
+use log::info;
+use smallvec::SmallVec;
+use wgpu::*;
+
+/// Number of frames a pooled transient texture may stay unused before it gets released.
+pub const TRANSIENT_TEXTURE_LIFETIME: u64 = 8;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TextureHandle(usize);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TextureSize {
+    /// Size of the swap chain divided by the given factor.
+    Relative(u32),
+    /// Fixed size in pixels.
+    Absolute(u32, u32),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TransientTextureDescriptor {
+    pub size: TextureSize,
+    pub format: TextureFormat,
+    pub sample_count: u32,
+}
+
+impl TransientTextureDescriptor {
+    #[inline]
+    pub fn extent(&self, width: u32, height: u32) -> Extent3d {
+        let (width, height) = match self.size {
+            TextureSize::Relative(divisor) => {
+                let divisor = divisor.max(1);
+                ((width / divisor).max(1), (height / divisor).max(1))
+            }
+            TextureSize::Absolute(width, height) => (width, height),
+        };
+        Extent3d {
+            width,
+            height,
+            depth: 1,
+        }
+    }
+}
+
+enum TextureNode<'a> {
+    Backbuffer,
+    Imported(&'a TextureView),
+    Transient(TransientTextureDescriptor),
+}
+
+struct TextureEntry<'a> {
+    name: &'static str,
+    node: TextureNode<'a>,
+}
+
+struct ColorAttachment {
+    target: TextureHandle,
+    resolve_target: Option<TextureHandle>,
+    load: LoadOp<Color>,
+}
+
+struct DepthAttachment {
+    target: TextureHandle,
+    load: LoadOp<f32>,
+}
+
+struct PassInputs<'a> {
+    group: u32,
+    layout: &'a BindGroupLayout,
+    sampler: &'a Sampler,
+}
+
+type PassCallback<'a, C> = Box<dyn for<'p> FnOnce(&mut Pass<'p>, &'p mut C) + 'a>;
+
+struct PassNode<'a, C> {
+    name: &'static str,
+    color_attachments: SmallVec<[ColorAttachment; 4]>,
+    depth_attachment: Option<DepthAttachment>,
+    samples: SmallVec<[TextureHandle; 4]>,
+    dependencies: SmallVec<[TextureHandle; 8]>,
+    inputs: Option<PassInputs<'a>>,
+    callback: PassCallback<'a, C>,
+}
+
+impl<'a, C> PassNode<'a, C> {
+    fn writes(&self) -> impl Iterator<Item = TextureHandle> + '_ {
+        self.color_attachments
+            .iter()
+            .flat_map(|a| std::iter::once(a.target).chain(a.resolve_target))
+            .chain(self.depth_attachment.as_ref().map(|a| a.target))
+    }
+
+    fn loads(&self, handle: TextureHandle) -> bool {
+        self.color_attachments
+            .iter()
+            .any(|a| a.target == handle && a.load == LoadOp::Load)
+            || matches!(&self.depth_attachment, Some(a) if a.target == handle && a.load == LoadOp::Load)
+    }
+
+    fn reads(&self, handle: TextureHandle) -> bool {
+        self.samples.contains(&handle) || self.dependencies.contains(&handle) || self.loads(handle)
+    }
+
+    fn touches(&self, handle: TextureHandle) -> bool {
+        self.reads(handle) || self.writes().any(|h| h == handle)
+    }
+}
+
+/// Declares the attachments and inputs of a single pass.
+pub struct PassBuilder<'g, 'a, C> {
+    graph: &'g mut RenderGraph<'a, C>,
+    name: &'static str,
+    color_attachments: SmallVec<[ColorAttachment; 4]>,
+    depth_attachment: Option<DepthAttachment>,
+    samples: SmallVec<[TextureHandle; 4]>,
+    dependencies: SmallVec<[TextureHandle; 8]>,
+    inputs: Option<PassInputs<'a>>,
+}
+
+impl<'g, 'a, C> PassBuilder<'g, 'a, C> {
+    pub fn color(mut self, target: TextureHandle, load: LoadOp<Color>) -> Self {
+        self.color_attachments.push(ColorAttachment {
+            target,
+            resolve_target: None,
+            load,
+        });
+        self
+    }
+
+    pub fn color_resolved(
+        mut self,
+        target: TextureHandle,
+        resolve_target: TextureHandle,
+        load: LoadOp<Color>,
+    ) -> Self {
+        self.color_attachments.push(ColorAttachment {
+            target,
+            resolve_target: Some(resolve_target),
+            load,
+        });
+        self
+    }
+
+    pub fn depth(mut self, target: TextureHandle, load: LoadOp<f32>) -> Self {
+        self.depth_attachment = Some(DepthAttachment { target, load });
+        self
+    }
+
+    /// Samples the texture in this pass.
+    /// Sampled textures are bound in declaration order at binding 0..n,
+    /// followed by the sampler given to `bind_inputs`.
+    pub fn sample(mut self, texture: TextureHandle) -> Self {
+        self.samples.push(texture);
+        self
+    }
+
+    /// Declares that this pass reads the texture through its own bindings (no input binding is created).
+    pub fn read(mut self, texture: TextureHandle) -> Self {
+        self.dependencies.push(texture);
+        self
+    }
+
+    pub fn bind_inputs(
+        mut self,
+        group: u32,
+        layout: &'a BindGroupLayout,
+        sampler: &'a Sampler,
+    ) -> Self {
+        self.inputs = Some(PassInputs {
+            group,
+            layout,
+            sampler,
+        });
+        self
+    }
+
+    pub fn execute<F>(self, callback: F)
+    where
+        F: for<'p> FnOnce(&mut Pass<'p>, &'p mut C) + 'a,
+    {
+        self.graph.passes.push(PassNode {
+            name: self.name,
+            color_attachments: self.color_attachments,
+            depth_attachment: self.depth_attachment,
+            samples: self.samples,
+            dependencies: self.dependencies,
+            inputs: self.inputs,
+            callback: Box::new(callback),
+        });
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RenderGraphStats {
+    pub passes: usize,
+    pub culled_passes: usize,
+    pub transient_textures: usize,
+    pub physical_textures: usize,
+}
+
+struct PooledTexture {
+    desc: TransientTextureDescriptor,
+    extent: Extent3d,
+    view: TextureView,
+    last_used_frame: u64,
+}
+
+/// Owns the physical textures backing transient graph resources across frames.
+#[derive(Default)]
+pub struct TransientTexturePool {
+    textures: Vec<PooledTexture>,
+    frame: u64,
+    stats: RenderGraphStats,
+}
+
+impl TransientTexturePool {
+    #[inline]
+    pub fn stats(&self) -> &RenderGraphStats {
+        &self.stats
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    fn acquire(
+        &mut self,
+        device: &Device,
+        desc: &TransientTextureDescriptor,
+        extent: Extent3d,
+        in_use: &[bool],
+    ) -> usize {
+        let frame = self.frame;
+        if let Some(index) = self.textures.iter().enumerate().position(|(i, t)| {
+            !in_use.get(i).copied().unwrap_or(false) && t.desc == *desc && t.extent == extent
+        }) {
+            self.textures[index].last_used_frame = frame;
+            return index;
+        }
+
+        info!(
+            "Allocating transient texture {:?} {}x{}",
+            desc.format, extent.width, extent.height
+        );
+        let usage = if desc.sample_count > 1 {
+            TextureUsage::RENDER_ATTACHMENT
+        } else {
+            TextureUsage::RENDER_ATTACHMENT | TextureUsage::SAMPLED
+        };
+        let view = device
+            .create_texture(&TextureDescriptor {
+                label: Some("transient"),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: desc.sample_count,
+                dimension: TextureDimension::D2,
+                format: desc.format,
+                usage,
+            })
+            .create_view(&TextureViewDescriptor::default());
+        self.textures.push(PooledTexture {
+            desc: *desc,
+            extent,
+            view,
+            last_used_frame: frame,
+        });
+        self.textures.len() - 1
+    }
+
+    fn evict(&mut self) {
+        let frame = self.frame;
+        self.textures
+            .retain(|t| frame - t.last_used_frame <= TRANSIENT_TEXTURE_LIFETIME);
+    }
+}
+
+/// Collects passes for a single frame, orders them by their resource dependencies,
+/// allocates (and aliases) transient textures and records everything into the frame encoder.
+pub struct RenderGraph<'a, C> {
+    textures: Vec<TextureEntry<'a>>,
+    passes: Vec<PassNode<'a, C>>,
+}
+
+impl<'a, C> RenderGraph<'a, C> {
+    pub fn new() -> Self {
+        Self {
+            textures: vec![TextureEntry {
+                name: "backbuffer",
+                node: TextureNode::Backbuffer,
+            }],
+            passes: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn backbuffer(&self) -> TextureHandle {
+        TextureHandle(0)
+    }
+
+    pub fn create_texture(
+        &mut self,
+        name: &'static str,
+        desc: TransientTextureDescriptor,
+    ) -> TextureHandle {
+        self.textures.push(TextureEntry {
+            name,
+            node: TextureNode::Transient(desc),
+        });
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    pub fn import_texture(&mut self, name: &'static str, view: &'a TextureView) -> TextureHandle {
+        self.textures.push(TextureEntry {
+            name,
+            node: TextureNode::Imported(view),
+        });
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    pub fn add_pass<'g>(&'g mut self, name: &'static str) -> PassBuilder<'g, 'a, C> {
+        PassBuilder {
+            graph: self,
+            name,
+            color_attachments: SmallVec::new(),
+            depth_attachment: None,
+            samples: SmallVec::new(),
+            dependencies: SmallVec::new(),
+            inputs: None,
+        }
+    }
+
+    /// Returns the indices of all passes which contribute to the backbuffer or an imported texture.
+    fn cull(&self) -> Vec<bool> {
+        let mut alive = vec![false; self.passes.len()];
+        let mut required = vec![false; self.textures.len()];
+        for (i, texture) in self.textures.iter().enumerate() {
+            required[i] = !matches!(texture.node, TextureNode::Transient(_));
+        }
+        for (i, pass) in self.passes.iter().enumerate().rev() {
+            if pass.writes().any(|h| required[h.0]) {
+                alive[i] = true;
+                for (t, flag) in required.iter_mut().enumerate() {
+                    if pass.reads(TextureHandle(t)) {
+                        *flag = true;
+                    }
+                }
+            }
+        }
+        alive
+    }
+
+    /// Orders the alive passes so that every pass runs after the passes producing its inputs.
+    /// Other passes touching the same texture keep their declaration order if one of them writes it.
+    fn schedule(&self, alive: &[bool]) -> Vec<usize> {
+        let count = self.passes.len();
+        let mut edges = vec![SmallVec::<[usize; 8]>::new(); count];
+        let mut in_degree = vec![0usize; count];
+
+        for texture in 0..self.textures.len() {
+            let handle = TextureHandle(texture);
+            let users = (0..count)
+                .filter(|&i| alive[i] && self.passes[i].touches(handle))
+                .collect::<SmallVec<[usize; 8]>>();
+            let is_writer = |i: usize| self.passes[i].writes().any(|h| h == handle);
+            let is_producer = |i: usize| is_writer(i) && !self.passes[i].reads(handle);
+            for &a in users.iter() {
+                for &b in users.iter() {
+                    let edge = a != b
+                        && ((is_producer(a) && !is_producer(b))
+                            || (a < b
+                                && (is_writer(a) || is_writer(b))
+                                && (is_producer(a) || !is_producer(b))));
+                    if edge && !edges[a].contains(&b) {
+                        edges[a].push(b);
+                        in_degree[b] += 1;
+                    }
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(count);
+        let mut ready = (0..count)
+            .filter(|&i| alive[i] && in_degree[i] == 0)
+            .collect::<Vec<_>>();
+        while !ready.is_empty() {
+            // always pick the earliest declared pass to keep the order stable:
+            let (slot, &next) = ready
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, &i)| i)
+                .expect("Ready list is empty!");
+            ready.swap_remove(slot);
+            order.push(next);
+            for &dependent in edges[next].iter() {
+                in_degree[dependent] -= 1;
+                if in_degree[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+
+        let alive_count = alive.iter().filter(|&&a| a).count();
+        if order.len() != alive_count {
+            panic!("Render graph contains a dependency cycle!");
+        }
+        order
+    }
+
+    pub fn execute(
+        self,
+        frame: &mut Frame,
+        device: &Device,
+        pool: &mut TransientTexturePool,
+        context: &mut C,
+    ) {
+        let alive = self.cull();
+        let order = self.schedule(&alive);
+        let (width, height) = (frame.width, frame.height);
+
+        // compute the lifetime of every transient texture in execution order:
+        let mut first_use = vec![usize::MAX; self.textures.len()];
+        let mut last_use = vec![0usize; self.textures.len()];
+        for (step, &pass) in order.iter().enumerate() {
+            for texture in 0..self.textures.len() {
+                if self.passes[pass].touches(TextureHandle(texture)) {
+                    first_use[texture] = first_use[texture].min(step);
+                    last_use[texture] = last_use[texture].max(step);
+                }
+            }
+        }
+
+        // assign physical textures, reusing them once their previous user is done:
+        pool.frame += 1;
+        pool.evict();
+        let mut physical = vec![None; self.textures.len()];
+        let mut in_use = Vec::<bool>::new();
+        let mut transient_count = 0;
+        for step in 0..order.len() {
+            for (texture, entry) in self.textures.iter().enumerate() {
+                if let TextureNode::Transient(desc) = &entry.node {
+                    if first_use[texture] == step {
+                        let index = pool.acquire(device, desc, desc.extent(width, height), &in_use);
+                        in_use.resize(pool.textures.len(), false);
+                        in_use[index] = true;
+                        physical[texture] = Some(index);
+                        transient_count += 1;
+                    }
+                }
+            }
+            for texture in 0..self.textures.len() {
+                if last_use[texture] == step {
+                    if let Some(index) = physical[texture] {
+                        in_use[index] = false;
+                    }
+                }
+            }
+        }
+
+        pool.stats = RenderGraphStats {
+            passes: order.len(),
+            culled_passes: self.passes.len() - order.len(),
+            transient_textures: transient_count,
+            physical_textures: pool.textures.len(),
+        };
+
+        let RenderGraph { textures, passes } = self;
+        let pool = &*pool;
+        let backbuffer = &frame.view.view;
+        let resolve = |handle: TextureHandle| -> &TextureView {
+            match &textures[handle.0].node {
+                TextureNode::Backbuffer => backbuffer,
+                TextureNode::Imported(view) => view,
+                TextureNode::Transient(_) => {
+                    &pool.textures[physical[handle.0].unwrap_or_else(|| {
+                        panic!(
+                            "Transient texture {} was never allocated!",
+                            textures[handle.0].name
+                        )
+                    })]
+                    .view
+                }
+            }
+        };
+
+        // attachments are only stored if anything after this pass needs them:
+        let store = |handle: TextureHandle, step: usize| {
+            !matches!(textures[handle.0].node, TextureNode::Transient(_))
+                || last_use[handle.0] > step
+        };
+
+        let bind_groups = order
+            .iter()
+            .map(|&i| {
+                passes[i].inputs.as_ref().map(|inputs| {
+                    let mut entries = passes[i]
+                        .samples
+                        .iter()
+                        .enumerate()
+                        .map(|(binding, &handle)| BindGroupEntry {
+                            binding: binding as u32,
+                            resource: BindingResource::TextureView(resolve(handle)),
+                        })
+                        .collect::<SmallVec<[BindGroupEntry; 8]>>();
+                    entries.push(BindGroupEntry {
+                        binding: entries.len() as u32,
+                        resource: BindingResource::Sampler(inputs.sampler),
+                    });
+                    device.create_bind_group(&BindGroupDescriptor {
+                        label: Some(passes[i].name),
+                        layout: inputs.layout,
+                        entries: &entries[..],
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut passes = passes.into_iter().map(Some).collect::<Vec<_>>();
+        let push_constants = frame.push_constants;
+        for (step, &index) in order.iter().enumerate() {
+            let node = passes[index].take().expect("Pass scheduled twice!");
+            let color_attachments = node
+                .color_attachments
+                .iter()
+                .map(|a| RenderPassColorAttachmentDescriptor {
+                    attachment: resolve(a.target),
+                    resolve_target: a.resolve_target.map(resolve),
+                    ops: Operations {
+                        load: a.load,
+                        store: store(a.target, step),
+                    },
+                })
+                .collect::<SmallVec<[_; 4]>>();
+            let depth_stencil_attachment = node.depth_attachment.as_ref().map(|a| {
+                RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: resolve(a.target),
+                    depth_ops: Some(Operations {
+                        load: a.load,
+                        store: store(a.target, step),
+                    }),
+                    stencil_ops: None,
+                }
+            });
+            let render_pass = frame.encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some(node.name),
+                color_attachments: &color_attachments[..],
+                depth_stencil_attachment,
+            });
+            let mut pass = Pass::new(render_pass, push_constants);
+            if let (Some(inputs), Some(bind_group)) = (&node.inputs, &bind_groups[step]) {
+                pass.0.set_bind_group(inputs.group, bind_group, &[]);
+            }
+            (node.callback)(&mut pass, context);
+        }
+    }
+}
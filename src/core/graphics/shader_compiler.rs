@@ -1,35 +1,438 @@
-use super::drivers::Drivers;
-use log::info;
-use shaderc::{CompilationArtifact, CompileOptions, ShaderKind};
+//! GLSL to SPIR-V compilation through an on disk cache of the bytecode.
+//! Cache entries are keyed by a hash of the source, its includes and the compile options, so
+//! edited sources are recompiled and stale entries are never loaded. Without the
+//! `runtime-shader-compiler` feature only cached bytecode can be used, precompiled with
+//! `--compile-shaders`.
+//! `#include "path"` directives resolve relative to `db/shaders`, e.g. `#include "include/alpha.glsl"`.
+
+use crate::config::ShaderOptimization;
+use log::{info, warn};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 pub const SHADER_ENTRY: &str = "main";
 pub const VS_ID: &str = "vert";
 pub const FS_ID: &str = "frag";
 
-pub fn compile_to_bytecode(
-    drivers: &mut Drivers,
-    path: PathBuf,
-    shader_type: ShaderKind,
-) -> CompilationArtifact {
-    let source = fs::read_to_string(&path).unwrap_or_else(|_| {
-        panic!("Failed to load shader source file: {:?}", path);
-    });
-    let sh_opt = &CompileOptions::new().expect("Failed to create compile options!");
-    info!("Compiling shader: {:?}", path);
-    drivers
-        .shader_compiler
-        .compile_into_spirv(
-            source.as_str(),
-            shader_type,
-            path.as_os_str().to_str().unwrap_or_else(|| {
-                panic!("Failed to convert path to string: {:?}", path);
-            }),
-            "main",
-            Some(sh_opt),
-        )
-        .unwrap_or_else(|_| {
-            panic!("Failed to compile shader source file: {:?}", path);
+/// Layout qualifier macro of the push constant blocks, like `layout(PUSH_CONSTANTS) uniform ...`.
+pub const PUSH_CONSTANTS_MACRO: &str = "PUSH_CONSTANTS";
+
+pub const SHADER_DIR: &str = "db/shaders";
+pub const CACHE_DIR: &str = "db/shaders/cache";
+
+/// Nesting limit of includes, which also stops include cycles.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Interval of the modification checks of the shader sources.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Bumped whenever the compilation changes in a way the cache key does not cover.
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ShaderKind {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderKind {
+    /// File name suffix of the shader sources.
+    #[inline]
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Vertex => VS_ID,
+            Self::Fragment => FS_ID,
+        }
+    }
+}
+
+/// Macro definitions of a compilation.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderDefines {
+    /// Bind group the push constants are emulated in, if the adapter has none.
+    pub push_constant_group: Option<u32>,
+    pub macros: Vec<(String, String)>,
+}
+
+impl ShaderDefines {
+    #[inline]
+    pub fn define(&mut self, name: &str, value: &str) {
+        self.macros.push((name.to_string(), value.to_string()));
+    }
+
+    /// All macros, including the push constant layout.
+    fn resolve(&self) -> Vec<(&str, String)> {
+        let push_constant_layout = match self.push_constant_group {
+            Some(group) => format!("std140, set = {}, binding = 0", group),
+            None => String::from("push_constant"),
+        };
+        let mut macros = vec![(PUSH_CONSTANTS_MACRO, push_constant_layout)];
+        macros.extend(
+            self.macros
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone())),
+        );
+        macros
+    }
+}
+
+/// A single compiler message, like "db/shaders/include/alpha.glsl:12: 'cutof' : undeclared identifier".
+#[derive(Clone, Debug)]
+pub struct ShaderDiagnostic {
+    pub file: String,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Failed compilation or validation of a shader.
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    pub path: PathBuf,
+    pub diagnostics: Vec<ShaderDiagnostic>,
+}
+
+impl ShaderError {
+    /// An error without a source location, like a validation error.
+    pub fn new(path: &Path, message: String) -> Self {
+        Self::from_messages(path, vec![message])
+    }
+
+    pub fn from_messages(path: &Path, messages: Vec<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            diagnostics: messages
+                .into_iter()
+                .map(|message| ShaderDiagnostic {
+                    file: path.display().to_string(),
+                    line: None,
+                    message,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to compile shader {:?}:", self.path)?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+/// shaderc is only created on the first cache miss.
+pub struct ShaderCompiler {
+    optimization: ShaderOptimization,
+    #[cfg(feature = "runtime-shader-compiler")]
+    compiler: Option<shaderc::Compiler>,
+}
+
+impl ShaderCompiler {
+    pub fn new(optimization: ShaderOptimization) -> Self {
+        Self {
+            optimization,
+            #[cfg(feature = "runtime-shader-compiler")]
+            compiler: None,
+        }
+    }
+
+    /// Returns the SPIR-V of the shader with the given macro definitions, panics on errors.
+    pub fn compile(&mut self, path: &Path, kind: ShaderKind, defines: &ShaderDefines) -> Vec<u8> {
+        self.try_compile(path, kind, defines)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_compile(
+        &mut self,
+        path: &Path,
+        kind: ShaderKind,
+        defines: &ShaderDefines,
+    ) -> Result<Vec<u8>, ShaderError> {
+        let source = fs::read_to_string(path).map_err(|error| {
+            ShaderError::new(
+                path,
+                format!("Failed to load shader source file: {}", error),
+            )
+        })?;
+        let defines = defines.resolve();
+
+        let cache_path = cache_path(path, kind, self.optimization, &source, &defines);
+        if let Ok(bytecode) = fs::read(&cache_path) {
+            info!("Loaded cached shader: {:?}", cache_path);
+            return Ok(bytecode);
+        }
+
+        let bytecode = self.compile_source(path, kind, &source, &defines)?;
+        let stored = fs::create_dir_all(CACHE_DIR).and_then(|_| fs::write(&cache_path, &bytecode));
+        if let Err(error) = stored {
+            warn!("Failed to cache shader {:?}: {}", cache_path, error);
+        }
+        Ok(bytecode)
+    }
+
+    #[cfg(feature = "runtime-shader-compiler")]
+    fn compile_source(
+        &mut self,
+        path: &Path,
+        kind: ShaderKind,
+        source: &str,
+        defines: &[(&str, String)],
+    ) -> Result<Vec<u8>, ShaderError> {
+        let compiler = self.compiler.get_or_insert_with(|| {
+            shaderc::Compiler::new().expect("Failed to create shader compiler!")
+        });
+        let mut options =
+            shaderc::CompileOptions::new().expect("Failed to create compile options!");
+        for (name, value) in defines {
+            options.add_macro_definition(name, Some(value));
+        }
+        options.set_optimization_level(match self.optimization {
+            ShaderOptimization::Zero => shaderc::OptimizationLevel::Zero,
+            ShaderOptimization::Size => shaderc::OptimizationLevel::Size,
+            ShaderOptimization::Performance => shaderc::OptimizationLevel::Performance,
+        });
+        options.set_include_callback(|name, _, _, depth| {
+            if depth > MAX_INCLUDE_DEPTH {
+                return Err(format!("Include depth of {} exceeded", MAX_INCLUDE_DEPTH));
+            }
+            let (path, content) = resolve_include(name)?;
+            Ok(shaderc::ResolvedInclude {
+                resolved_name: path.to_string_lossy().into_owned(),
+                content,
+            })
+        });
+        let shader_kind = match kind {
+            ShaderKind::Vertex => shaderc::ShaderKind::Vertex,
+            ShaderKind::Fragment => shaderc::ShaderKind::Fragment,
+        };
+        info!("Compiling shader: {:?}", path);
+        let file_name = path.display().to_string();
+        let artifact = compiler
+            .compile_into_spirv(
+                source,
+                shader_kind,
+                &file_name,
+                SHADER_ENTRY,
+                Some(&options),
+            )
+            .map_err(|error| ShaderError {
+                path: path.to_path_buf(),
+                diagnostics: match error {
+                    shaderc::Error::CompilationError(_, messages) => {
+                        parse_diagnostics(&file_name, &messages)
+                    }
+                    error => parse_diagnostics(&file_name, &error.to_string()),
+                },
+            })?;
+        if artifact.get_num_warnings() > 0 {
+            for diagnostic in parse_diagnostics(&file_name, &artifact.get_warning_messages()) {
+                warn!("Shader warning: {}", diagnostic);
+            }
+        }
+        Ok(artifact.as_binary_u8().to_vec())
+    }
+
+    #[cfg(not(feature = "runtime-shader-compiler"))]
+    fn compile_source(
+        &mut self,
+        path: &Path,
+        _kind: ShaderKind,
+        _source: &str,
+        _defines: &[(&str, String)],
+    ) -> Result<Vec<u8>, ShaderError> {
+        Err(ShaderError::new(
+            path,
+            String::from("Not in the shader cache and the runtime shader compiler is disabled! Precompile the shaders with --compile-shaders."),
+        ))
+    }
+}
+
+/// Splits compiler output like "file:12: error: 'x' : undeclared identifier" into its messages.
+fn parse_diagnostics(file_name: &str, messages: &str) -> Vec<ShaderDiagnostic> {
+    messages
+        .lines()
+        .map(str::trim)
+        // skip summaries like "1 error generated.":
+        .filter(|line| !line.is_empty() && !line.ends_with(" generated."))
+        .map(|line| {
+            // the location is the part before the severity:
+            for severity in &[": error: ", ": warning: "] {
+                if let Some(index) = line.find(severity) {
+                    let location = &line[..index];
+                    let message = line[index + 2..].to_string();
+                    if let Some(colon) = location.rfind(':') {
+                        if let Ok(line) = location[colon + 1..].parse() {
+                            return ShaderDiagnostic {
+                                file: location[..colon].to_string(),
+                                line: Some(line),
+                                message,
+                            };
+                        }
+                    }
+                    return ShaderDiagnostic {
+                        file: location.to_string(),
+                        line: None,
+                        message,
+                    };
+                }
+            }
+            ShaderDiagnostic {
+                file: file_name.to_string(),
+                line: None,
+                message: line.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Detects edited shader sources by the newest modification time below the shader directory.
+pub struct ShaderWatcher {
+    last_check: Instant,
+    stamp: Option<SystemTime>,
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> Self {
+        Self {
+            last_check: Instant::now(),
+            stamp: newest_modification(Path::new(SHADER_DIR)),
+        }
+    }
+}
+
+impl ShaderWatcher {
+    /// Returns true once after any shader source changed, checking at most once per interval.
+    pub fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let stamp = newest_modification(Path::new(SHADER_DIR));
+        if stamp == self.stamp {
+            return false;
+        }
+        self.stamp = stamp;
+        true
+    }
+}
+
+/// Newest source in the directory tree, the written cache entries are not sources.
+fn newest_modification(dir: &Path) -> Option<SystemTime> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path() != Path::new(CACHE_DIR))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if metadata.is_dir() {
+                newest_modification(&entry.path())
+            } else {
+                metadata.modified().ok()
+            }
         })
+        .max()
+}
+
+/// Loads an included file, relative to the shader directory.
+fn resolve_include(name: &str) -> Result<(PathBuf, String), String> {
+    let path = Path::new(SHADER_DIR).join(name);
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok((path, content)),
+        Err(error) => Err(format!("Failed to include {:?}: {}", path, error)),
+    }
+}
+
+/// Name of an `#include "name"` or `#include <name>` directive.
+fn include_name(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("include")?.trim();
+    let close = match rest.chars().next()? {
+        '"' => '"',
+        '<' => '>',
+        _ => return None,
+    };
+    let rest = &rest[1..];
+    rest.find(close).map(|end| &rest[..end])
+}
+
+/// Hashes the included files too, so edits of an include invalidate its includers.
+/// Unresolved includes are skipped here and reported by the compiler.
+fn hash_includes(hash: &mut Fnv1a, source: &str, depth: usize) {
+    if depth > MAX_INCLUDE_DEPTH {
+        return;
+    }
+    for name in source.lines().filter_map(include_name) {
+        if let Ok((_, content)) = resolve_include(name) {
+            hash.write(name.as_bytes());
+            hash.write(content.as_bytes());
+            hash_includes(hash, &content, depth + 1);
+        }
+    }
+}
+
+/// Removes all cached bytecode, before precompiling the shaders again.
+pub fn clear_cache() -> std::io::Result<()> {
+    match fs::remove_dir_all(CACHE_DIR) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// Cache file like "lambert.vert.<hash>.spv", named after the directory of the source.
+fn cache_path(
+    path: &Path,
+    kind: ShaderKind,
+    optimization: ShaderOptimization,
+    source: &str,
+    defines: &[(&str, String)],
+) -> PathBuf {
+    let mut hash = Fnv1a::default();
+    hash.write(&CACHE_VERSION.to_le_bytes());
+    hash.write(kind.id().as_bytes());
+    hash.write(&[optimization as u8]);
+    hash.write(source.as_bytes());
+    hash_includes(&mut hash, source, 1);
+    for (name, value) in defines {
+        hash.write(name.as_bytes());
+        hash.write(b"=");
+        hash.write(value.as_bytes());
+        hash.write(b"\n");
+    }
+    let name = path
+        .parent()
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str())
+        .unwrap_or("shader");
+    Path::new(CACHE_DIR).join(format!("{}.{}.{:016x}.spv", name, kind.id(), hash.0))
+}
+
+/// 64 bit FNV-1a, stable across builds and platforms unlike the std hashers.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
 }
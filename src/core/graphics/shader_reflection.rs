@@ -0,0 +1,405 @@
+//! Minimal SPIR-V reflection of the resource bindings and push constants of a shader,
+//! to validate the compiled shaders against the layouts declared by their `Pipeline`.
+//! WGSL shaders are reflected from the naga module of their source instead.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use wgpu::{
+    BindGroupLayoutEntry, BindingType, PushConstantRange, ShaderStage, TextureViewDimension,
+};
+
+const MAGIC: u32 = 0x0723_0203;
+
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResourceType {
+    Buffer,
+    Texture(TextureViewDimension),
+    Sampler,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ResourceBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub ty: ResourceType,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShaderReflection {
+    pub bindings: Vec<ResourceBinding>,
+    /// Byte range of the members of the push constant block.
+    pub push_constants: Option<Range<u32>>,
+}
+
+#[derive(Clone)]
+enum Type {
+    Scalar { size: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { element: u32, length: u32 },
+    Struct { members: Vec<u32> },
+    Image { dim: u32, arrayed: bool },
+    Sampler,
+    Pointer { storage_class: u32, pointee: u32 },
+}
+
+#[derive(Default)]
+struct Decorations {
+    binding: Option<u32>,
+    group: Option<u32>,
+    array_stride: Option<u32>,
+}
+
+/// Collects the declarations of the module, ignoring everything else.
+#[derive(Default)]
+struct Module {
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    decorations: HashMap<u32, Decorations>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    variables: Vec<(u32, u32, u32)>,
+}
+
+impl ShaderReflection {
+    pub fn reflect(bytecode: &[u8]) -> Result<Self, String> {
+        let chunks = bytecode.chunks_exact(4);
+        if !chunks.remainder().is_empty() || bytecode.len() < 20 {
+            return Err(String::from("Invalid SPIR-V size"));
+        }
+        let words = chunks
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<_>>();
+        if words[0] != MAGIC {
+            return Err(String::from("Invalid SPIR-V magic number"));
+        }
+
+        let mut module = Module::default();
+        let mut offset = 5;
+        while offset < words.len() {
+            let count = (words[offset] >> 16) as usize;
+            let opcode = words[offset] & 0xffff;
+            if count == 0 || offset + count > words.len() {
+                return Err(format!("Invalid SPIR-V instruction at word {}", offset));
+            }
+            module.parse(opcode, &words[offset + 1..offset + count]);
+            offset += count;
+        }
+        Ok(module.reflection())
+    }
+
+    /// Bindings of a WGSL module, which cannot declare push constants.
+    pub fn from_naga(module: &naga::Module) -> Self {
+        let bindings = module
+            .global_variables
+            .iter()
+            .filter_map(|(_, variable)| {
+                let (group, binding) = match variable.binding {
+                    Some(naga::Binding::Resource { group, binding }) => (group, binding),
+                    _ => return None,
+                };
+                let ty = match variable.class {
+                    naga::StorageClass::Uniform | naga::StorageClass::Storage => {
+                        ResourceType::Buffer
+                    }
+                    naga::StorageClass::Handle => match module.types[variable.ty].inner {
+                        naga::TypeInner::Image { dim, arrayed, .. } => {
+                            let dim = match dim {
+                                naga::ImageDimension::D1 => 0,
+                                naga::ImageDimension::D2 => 1,
+                                naga::ImageDimension::D3 => 2,
+                                naga::ImageDimension::Cube => 3,
+                            };
+                            ResourceType::Texture(view_dimension(dim, arrayed))
+                        }
+                        naga::TypeInner::Sampler { .. } => ResourceType::Sampler,
+                        _ => return None,
+                    },
+                    _ => return None,
+                };
+                Some(ResourceBinding { group, binding, ty })
+            })
+            .collect();
+        Self {
+            bindings,
+            push_constants: None,
+        }
+    }
+
+    /// Checks the bindings of the bind group against its layout entries.
+    pub fn validate_group(
+        &self,
+        stage: ShaderStage,
+        group: u32,
+        entries: &[BindGroupLayoutEntry],
+        errors: &mut Vec<String>,
+    ) {
+        for binding in self
+            .bindings
+            .iter()
+            .filter(|binding| binding.group == group)
+        {
+            let entry = match entries
+                .iter()
+                .find(|entry| entry.binding == binding.binding)
+            {
+                Some(entry) => entry,
+                None => {
+                    errors.push(format!(
+                        "Binding {} of group {} is not in the pipeline layout",
+                        binding.binding, group
+                    ));
+                    continue;
+                }
+            };
+            // a wgsl module validates both stages at once:
+            if !entry.visibility.intersects(stage) {
+                errors.push(format!(
+                    "Binding {} of group {} is not visible to the {:?} stage",
+                    binding.binding, group, stage
+                ));
+            }
+            let matches = match (binding.ty, entry.ty) {
+                (ResourceType::Buffer, BindingType::Buffer { .. }) => true,
+                (ResourceType::Sampler, BindingType::Sampler { .. }) => true,
+                (ResourceType::Texture(dimension), BindingType::Texture { view_dimension, .. }) => {
+                    dimension == view_dimension
+                }
+                (
+                    ResourceType::Texture(dimension),
+                    BindingType::StorageTexture { view_dimension, .. },
+                ) => dimension == view_dimension,
+                _ => false,
+            };
+            if !matches {
+                errors.push(format!(
+                    "Binding {} of group {} is a {:?} in the shader but a {:?} in the pipeline layout",
+                    binding.binding, group, binding.ty, entry.ty
+                ));
+            }
+        }
+    }
+
+    /// Checks that the push constant block is covered by the ranges of the stage.
+    pub fn validate_push_constants(
+        &self,
+        stage: ShaderStage,
+        ranges: &[PushConstantRange],
+        errors: &mut Vec<String>,
+    ) {
+        let block = match &self.push_constants {
+            Some(block) => block,
+            None => return,
+        };
+        let covered = ranges.iter().any(|range| {
+            range.stages.contains(stage)
+                && range.range.start <= block.start
+                && range.range.end >= block.end
+        });
+        if !covered {
+            errors.push(format!(
+                "Push constants {:?} of the {:?} stage are not covered by the push constant ranges",
+                block, stage
+            ));
+        }
+    }
+}
+
+impl Module {
+    fn parse(&mut self, opcode: u32, operands: &[u32]) {
+        let operand = |index: usize| operands.get(index).copied().unwrap_or_default();
+        match opcode {
+            OP_TYPE_INT | OP_TYPE_FLOAT => {
+                self.types.insert(
+                    operand(0),
+                    Type::Scalar {
+                        size: operand(1) / 8,
+                    },
+                );
+            }
+            OP_TYPE_VECTOR => {
+                self.types.insert(
+                    operand(0),
+                    Type::Vector {
+                        component: operand(1),
+                        count: operand(2),
+                    },
+                );
+            }
+            OP_TYPE_MATRIX => {
+                self.types.insert(
+                    operand(0),
+                    Type::Matrix {
+                        column: operand(1),
+                        count: operand(2),
+                    },
+                );
+            }
+            OP_TYPE_IMAGE => {
+                self.types.insert(
+                    operand(0),
+                    Type::Image {
+                        dim: operand(2),
+                        arrayed: operand(4) != 0,
+                    },
+                );
+            }
+            OP_TYPE_SAMPLER => {
+                self.types.insert(operand(0), Type::Sampler);
+            }
+            OP_TYPE_ARRAY => {
+                let length = self.constants.get(&operand(2)).copied().unwrap_or(1);
+                self.types.insert(
+                    operand(0),
+                    Type::Array {
+                        element: operand(1),
+                        length,
+                    },
+                );
+            }
+            OP_TYPE_STRUCT => {
+                let members = operands.get(1..).unwrap_or_default().to_vec();
+                self.types.insert(operand(0), Type::Struct { members });
+            }
+            OP_TYPE_POINTER => {
+                self.types.insert(
+                    operand(0),
+                    Type::Pointer {
+                        storage_class: operand(1),
+                        pointee: operand(2),
+                    },
+                );
+            }
+            OP_CONSTANT => {
+                self.constants.insert(operand(1), operand(2));
+            }
+            OP_VARIABLE => {
+                self.variables.push((operand(0), operand(1), operand(2)));
+            }
+            OP_DECORATE => {
+                let decorations = self.decorations.entry(operand(0)).or_default();
+                match operand(1) {
+                    DECORATION_BINDING => decorations.binding = Some(operand(2)),
+                    DECORATION_DESCRIPTOR_SET => decorations.group = Some(operand(2)),
+                    DECORATION_ARRAY_STRIDE => decorations.array_stride = Some(operand(2)),
+                    _ => {}
+                }
+            }
+            OP_MEMBER_DECORATE if operand(2) == DECORATION_OFFSET => {
+                self.member_offsets
+                    .insert((operand(0), operand(1)), operand(3));
+            }
+            _ => {}
+        }
+    }
+
+    fn reflection(&self) -> ShaderReflection {
+        let mut reflection = ShaderReflection::default();
+        for &(pointer, id, storage_class) in &self.variables {
+            let ty = match self.types.get(&pointer) {
+                Some(Type::Pointer { pointee, .. }) => *pointee,
+                _ => continue,
+            };
+            if storage_class == STORAGE_CLASS_PUSH_CONSTANT {
+                reflection.push_constants = self.struct_range(ty);
+                continue;
+            }
+            let resource_type = match storage_class {
+                STORAGE_CLASS_UNIFORM | STORAGE_CLASS_STORAGE_BUFFER => ResourceType::Buffer,
+                STORAGE_CLASS_UNIFORM_CONSTANT => match self.types.get(&ty) {
+                    Some(Type::Image { dim, arrayed }) => {
+                        ResourceType::Texture(view_dimension(*dim, *arrayed))
+                    }
+                    Some(Type::Sampler) => ResourceType::Sampler,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let decorations = match self.decorations.get(&id) {
+                Some(decorations) => decorations,
+                None => continue,
+            };
+            if let (Some(group), Some(binding)) = (decorations.group, decorations.binding) {
+                reflection.bindings.push(ResourceBinding {
+                    group,
+                    binding,
+                    ty: resource_type,
+                });
+            }
+        }
+        reflection
+    }
+
+    /// Range from the first member offset to the end of the last member.
+    fn struct_range(&self, ty: u32) -> Option<Range<u32>> {
+        let members = match self.types.get(&ty) {
+            Some(Type::Struct { members }) => members,
+            _ => return None,
+        };
+        members
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &member)| {
+                let offset = *self.member_offsets.get(&(ty, index as u32))?;
+                Some(offset..offset + self.size(member))
+            })
+            .fold(None, |range: Option<Range<u32>>, member| match range {
+                Some(range) => Some(range.start.min(member.start)..range.end.max(member.end)),
+                None => Some(member),
+            })
+    }
+
+    fn size(&self, ty: u32) -> u32 {
+        match self.types.get(&ty) {
+            Some(Type::Scalar { size }) => *size,
+            Some(Type::Vector { component, count }) => self.size(*component) * count,
+            Some(Type::Matrix { column, count }) => self.size(*column) * count,
+            Some(Type::Array { element, length }) => {
+                let stride = self
+                    .decorations
+                    .get(&ty)
+                    .and_then(|decorations| decorations.array_stride)
+                    .unwrap_or_else(|| self.size(*element));
+                stride * length
+            }
+            Some(Type::Struct { .. }) => self.struct_range(ty).map_or(0, |range| range.end),
+            _ => 0,
+        }
+    }
+}
+
+/// `dim` is the SPIR-V `Dim` of the image.
+fn view_dimension(dim: u32, arrayed: bool) -> TextureViewDimension {
+    match (dim, arrayed) {
+        (0, _) => TextureViewDimension::D1,
+        (1, false) => TextureViewDimension::D2,
+        (1, true) => TextureViewDimension::D2Array,
+        (2, _) => TextureViewDimension::D3,
+        (3, false) => TextureViewDimension::Cube,
+        (3, true) => TextureViewDimension::CubeArray,
+        _ => TextureViewDimension::D2,
+    }
+}
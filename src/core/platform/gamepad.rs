@@ -0,0 +1,186 @@
+use super::prelude::{
+    Action, GamepadAxis, GamepadButton, JoystickId, GAMEPAD_AXIS_COUNT, GAMEPAD_BUTTON_COUNT,
+    GAMEPAD_COUNT,
+};
+use glfw::Glfw;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Gamepad {
+    pub name: String,
+    pub buttons: [bool; GAMEPAD_BUTTON_COUNT],
+    /// Sticks are in [-1, 1] with y pointing down, triggers in [0, 1].
+    /// Deadzones are already applied.
+    pub axes: [f32; GAMEPAD_AXIS_COUNT],
+}
+
+impl Gamepad {
+    #[inline]
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    #[inline]
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GamepadEvent {
+    Connected(JoystickId),
+    Disconnected(JoystickId),
+}
+
+/// Joysticks with a gamepad mapping, indexed by their joystick id.
+#[derive(Default)]
+pub struct GamepadState {
+    pads: [Option<Gamepad>; GAMEPAD_COUNT],
+    events: Vec<GamepadEvent>,
+}
+
+impl GamepadState {
+    #[inline]
+    pub fn gamepad(&self, id: JoystickId) -> Option<&Gamepad> {
+        self.pads[id as usize].as_ref()
+    }
+
+    #[inline]
+    pub fn gamepads(&self) -> impl Iterator<Item = &Gamepad> {
+        self.pads.iter().flatten()
+    }
+
+    /// Connected gamepads with their joystick index.
+    #[inline]
+    pub fn indexed_gamepads(&self) -> impl Iterator<Item = (usize, &Gamepad)> {
+        self.pads
+            .iter()
+            .enumerate()
+            .filter_map(|(index, pad)| pad.as_ref().map(|pad| (index, pad)))
+    }
+
+    /// Connections and disconnections since the last frame.
+    #[inline]
+    pub fn events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    /// True if the button is held on any gamepad.
+    #[inline]
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepads().any(|pad| pad.is_button_pressed(button))
+    }
+
+    /// The axis value with the largest magnitude across all gamepads.
+    #[inline]
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepads()
+            .map(|pad| pad.axis(axis))
+            .fold(0.0, |a, b| if b.abs() > a.abs() { b } else { a })
+    }
+
+    /// Replaces the connected gamepads, used to replay recorded input.
+    pub fn replace(&mut self, pads: &[(usize, Gamepad)]) {
+        self.events.clear();
+        for (index, slot) in self.pads.iter_mut().enumerate() {
+            let id = JoystickId::from_i32(index as i32).unwrap();
+            let pad = pads
+                .iter()
+                .find(|(pad_index, _)| *pad_index == index)
+                .map(|(_, pad)| pad.clone());
+            match (slot.is_some(), pad.is_some()) {
+                (false, true) => self.events.push(GamepadEvent::Connected(id)),
+                (true, false) => self.events.push(GamepadEvent::Disconnected(id)),
+                _ => (),
+            }
+            *slot = pad;
+        }
+    }
+
+    pub fn poll(&mut self, context: &Glfw, deadzone: f32, trigger_deadzone: f32) {
+        let events = &mut self.events;
+        events.clear();
+        for (index, slot) in self.pads.iter_mut().enumerate() {
+            let id = JoystickId::from_i32(index as i32).unwrap();
+            let joystick = context.get_joystick(id);
+            let state = if joystick.is_present() && joystick.is_gamepad() {
+                joystick.get_gamepad_state()
+            } else {
+                None
+            };
+
+            let state = match state {
+                Some(state) => state,
+                None => {
+                    if slot.take().is_some() {
+                        info!("Gamepad disconnected: {:?}", id);
+                        events.push(GamepadEvent::Disconnected(id));
+                    }
+                    continue;
+                }
+            };
+
+            let pad = slot.get_or_insert_with(|| {
+                let name = joystick
+                    .get_gamepad_name()
+                    .unwrap_or_else(|| String::from("Unknown"));
+                info!("Gamepad connected: {:?} - {}", id, name);
+                events.push(GamepadEvent::Connected(id));
+                Gamepad {
+                    name,
+                    buttons: [false; GAMEPAD_BUTTON_COUNT],
+                    axes: [0.0; GAMEPAD_AXIS_COUNT],
+                }
+            });
+
+            for (i, button) in pad.buttons.iter_mut().enumerate() {
+                *button = state.get_button_state(GamepadButton::from_i32(i as i32).unwrap())
+                    != Action::Release;
+            }
+
+            let (left_x, left_y) = apply_radial_deadzone(
+                state.get_axis(GamepadAxis::AxisLeftX),
+                state.get_axis(GamepadAxis::AxisLeftY),
+                deadzone,
+            );
+            let (right_x, right_y) = apply_radial_deadzone(
+                state.get_axis(GamepadAxis::AxisRightX),
+                state.get_axis(GamepadAxis::AxisRightY),
+                deadzone,
+            );
+            pad.axes[GamepadAxis::AxisLeftX as usize] = left_x;
+            pad.axes[GamepadAxis::AxisLeftY as usize] = left_y;
+            pad.axes[GamepadAxis::AxisRightX as usize] = right_x;
+            pad.axes[GamepadAxis::AxisRightY as usize] = right_y;
+            pad.axes[GamepadAxis::AxisLeftTrigger as usize] = apply_trigger_deadzone(
+                state.get_axis(GamepadAxis::AxisLeftTrigger),
+                trigger_deadzone,
+            );
+            pad.axes[GamepadAxis::AxisRightTrigger as usize] = apply_trigger_deadzone(
+                state.get_axis(GamepadAxis::AxisRightTrigger),
+                trigger_deadzone,
+            );
+        }
+    }
+}
+
+/// Zeroes the stick inside the deadzone and rescales the rest to start at zero.
+fn apply_radial_deadzone(x: f32, y: f32, deadzone: f32) -> (f32, f32) {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude <= deadzone || deadzone >= 1.0 {
+        return (0.0, 0.0);
+    }
+    let scale = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0) / magnitude;
+    (x * scale, y * scale)
+}
+
+/// Maps the trigger from [-1, 1] to [0, 1] and applies the deadzone.
+fn apply_trigger_deadzone(value: f32, deadzone: f32) -> f32 {
+    let value = (value + 1.0) * 0.5;
+    if value <= deadzone || deadzone >= 1.0 {
+        0.0
+    } else {
+        ((value - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
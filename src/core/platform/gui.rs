@@ -0,0 +1,96 @@
+//! Platform side of the imgui integration, feeding glfw input into the imgui io.
+
+use glfw::{Action, Key, Modifiers, MouseButton, Window, WindowEvent};
+use imgui::{Context, ImString, Io};
+
+/// Smallest delta time imgui accepts, it asserts on zero.
+const MIN_DELTA_TIME: f32 = 1.0 / 10_000.0;
+
+/// Sets up the key map and display metrics.
+/// The window is not resizable, so the metrics only change with the window mode.
+pub fn configure(imgui: &mut Context, window: &Window) {
+    imgui.set_platform_name(Some(ImString::new("glfw")));
+
+    let io = imgui.io_mut();
+    set_display_metrics(io, window.get_size(), window.get_framebuffer_size());
+
+    io[imgui::Key::Tab] = Key::Tab as _;
+    io[imgui::Key::LeftArrow] = Key::Left as _;
+    io[imgui::Key::RightArrow] = Key::Right as _;
+    io[imgui::Key::UpArrow] = Key::Up as _;
+    io[imgui::Key::DownArrow] = Key::Down as _;
+    io[imgui::Key::PageUp] = Key::PageUp as _;
+    io[imgui::Key::PageDown] = Key::PageDown as _;
+    io[imgui::Key::Home] = Key::Home as _;
+    io[imgui::Key::End] = Key::End as _;
+    io[imgui::Key::Insert] = Key::Insert as _;
+    io[imgui::Key::Delete] = Key::Delete as _;
+    io[imgui::Key::Backspace] = Key::Backspace as _;
+    io[imgui::Key::Space] = Key::Space as _;
+    io[imgui::Key::Enter] = Key::Enter as _;
+    io[imgui::Key::Escape] = Key::Escape as _;
+    io[imgui::Key::KeyPadEnter] = Key::KpEnter as _;
+    io[imgui::Key::A] = Key::A as _;
+    io[imgui::Key::C] = Key::C as _;
+    io[imgui::Key::V] = Key::V as _;
+    io[imgui::Key::X] = Key::X as _;
+    io[imgui::Key::Y] = Key::Y as _;
+    io[imgui::Key::Z] = Key::Z as _;
+}
+
+pub fn set_display_metrics(io: &mut Io, size: (i32, i32), framebuffer_size: (i32, i32)) {
+    let (width, height) = size;
+    let (framebuffer_width, framebuffer_height) = framebuffer_size;
+    io.display_size = [width as f32, height as f32];
+    if width > 0 && height > 0 {
+        io.display_framebuffer_scale = [
+            framebuffer_width as f32 / width as f32,
+            framebuffer_height as f32 / height as f32,
+        ];
+    }
+}
+
+pub fn handle_event(io: &mut Io, event: &WindowEvent) {
+    match *event {
+        WindowEvent::CursorPos(x, y) => {
+            io.mouse_pos = [x as f32, y as f32];
+        }
+        WindowEvent::CursorEnter(false) => {
+            io.mouse_pos = [-f32::MAX, -f32::MAX];
+        }
+        WindowEvent::MouseButton(button, action, _) => {
+            let index = match button {
+                MouseButton::Button1 => 0,
+                MouseButton::Button2 => 1,
+                MouseButton::Button3 => 2,
+                MouseButton::Button4 => 3,
+                MouseButton::Button5 => 4,
+                _ => return,
+            };
+            io.mouse_down[index] = action != Action::Release;
+        }
+        WindowEvent::Scroll(x, y) => {
+            io.mouse_wheel_h += x as f32;
+            io.mouse_wheel += y as f32;
+        }
+        WindowEvent::Key(key, _, action, modifiers) => {
+            if key != Key::Unknown {
+                io.keys_down[key as usize] = action != Action::Release;
+            }
+            io.key_shift = modifiers.contains(Modifiers::Shift);
+            io.key_ctrl = modifiers.contains(Modifiers::Control);
+            io.key_alt = modifiers.contains(Modifiers::Alt);
+            io.key_super = modifiers.contains(Modifiers::Super);
+        }
+        WindowEvent::Char(character) => {
+            io.add_input_character(character);
+        }
+        _ => (),
+    }
+}
+
+/// Must be called before every `imgui::Context::frame`.
+#[inline]
+pub fn prepare_frame(io: &mut Io, delta_time: f32) {
+    io.delta_time = delta_time.max(MIN_DELTA_TIME);
+}
@@ -0,0 +1,474 @@
+use super::gamepad::GamepadState;
+use super::prelude::{GamepadAxis, GamepadButton, Key, Modifiers, MouseButton};
+use crate::config::InputConfig;
+use crate::scenery_resources::{CursorPos, KeyInputStateCollection, MouseInputStateCollection};
+use log::warn;
+use smallvec::SmallVec;
+use std::collections::HashMap;
+
+/// Actions which still fire while the gui captures the keyboard, so the gui can be closed again.
+pub const UNCAPTURED_ACTIONS: &[&str] = &["toggle_debug_ui"];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MouseAxis {
+    X,
+    Y,
+    ScrollX,
+    ScrollY,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InputSource {
+    Key(Key),
+    MouseButton(MouseButton),
+    /// Matches the left or the right variant of the modifier key.
+    Modifier(Modifiers),
+    MouseAxis(MouseAxis),
+    /// Held on any connected gamepad.
+    GamepadButton(GamepadButton),
+    /// Largest deflection across all connected gamepads.
+    GamepadAxis(GamepadAxis),
+}
+
+/// Inputs which must all be active at once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chord(SmallVec<[InputSource; 2]>);
+
+impl Chord {
+    pub fn parse(text: &str) -> Option<Self> {
+        text.split('+')
+            .map(|name| parse_source(name.trim()))
+            .collect::<Option<SmallVec<[InputSource; 2]>>>()
+            .filter(|sources| !sources.is_empty())
+            .map(Self)
+    }
+
+    #[inline]
+    pub fn sources(&self) -> &[InputSource] {
+        &self.0
+    }
+
+    /// 0.0 while any button of the chord is up, otherwise the axis value or 1.0.
+    fn value(&self, state: &InputFrame) -> f32 {
+        let mut value = 1.0;
+        for source in self.0.iter() {
+            let held = match *source {
+                InputSource::Key(key) => state.keys.is_key_pressed(key),
+                InputSource::MouseButton(button) => state.mouse.is_key_pressed(button),
+                InputSource::Modifier(modifier) => state.is_modifier_held(modifier),
+                InputSource::MouseAxis(axis) => {
+                    value *= state.axis(axis);
+                    true
+                }
+                InputSource::GamepadButton(button) => state.gamepads.is_button_pressed(button),
+                InputSource::GamepadAxis(axis) => {
+                    value *= state.gamepads.axis(axis);
+                    true
+                }
+            };
+            if !held {
+                return 0.0;
+            }
+        }
+        value
+    }
+}
+
+/// Left and right key of a modifier.
+fn modifier_keys(modifier: Modifiers) -> (Key, Key) {
+    if modifier == Modifiers::Shift {
+        (Key::LeftShift, Key::RightShift)
+    } else if modifier == Modifiers::Control {
+        (Key::LeftControl, Key::RightControl)
+    } else if modifier == Modifiers::Alt {
+        (Key::LeftAlt, Key::RightAlt)
+    } else {
+        (Key::LeftSuper, Key::RightSuper)
+    }
+}
+
+struct InputFrame<'a> {
+    keys: &'a KeyInputStateCollection,
+    mouse: &'a MouseInputStateCollection,
+    gamepads: &'a GamepadState,
+    cursor_delta: (f32, f32),
+    scroll: (f32, f32),
+}
+
+impl InputFrame<'_> {
+    fn is_modifier_held(&self, modifier: Modifiers) -> bool {
+        let (left, right) = modifier_keys(modifier);
+        self.keys.is_key_pressed(left) || self.keys.is_key_pressed(right)
+    }
+
+    #[inline]
+    fn axis(&self, axis: MouseAxis) -> f32 {
+        match axis {
+            MouseAxis::X => self.cursor_delta.0,
+            MouseAxis::Y => self.cursor_delta.1,
+            MouseAxis::ScrollX => self.scroll.0,
+            MouseAxis::ScrollY => self.scroll.1,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ActionState {
+    pub pressed: bool,
+    /// Pressed this frame.
+    pub just_pressed: bool,
+    /// Released this frame.
+    pub just_released: bool,
+}
+
+struct ActionEntry {
+    chords: Vec<Chord>,
+    state: ActionState,
+}
+
+struct AxisEntry {
+    positive: Vec<Chord>,
+    negative: Vec<Chord>,
+    scale: f32,
+    value: f32,
+}
+
+/// Named actions and axes, evaluated once per frame from the raw input state.
+/// Unknown names read as released or 0.0.
+#[derive(Default)]
+pub struct InputMap {
+    actions: HashMap<String, ActionEntry>,
+    axes: HashMap<String, AxisEntry>,
+    last_cursor_pos: Option<CursorPos>,
+}
+
+impl InputMap {
+    pub fn from_config(config: &InputConfig) -> Self {
+        let parse_all = |owner: &str, inputs: &[String]| {
+            inputs
+                .iter()
+                .filter_map(|input| {
+                    let chord = Chord::parse(input);
+                    if chord.is_none() {
+                        warn!("Invalid input binding \"{}\" for \"{}\"", input, owner);
+                    }
+                    chord
+                })
+                .collect::<Vec<Chord>>()
+        };
+
+        let actions = config
+            .actions
+            .iter()
+            .map(|binding| {
+                let entry = ActionEntry {
+                    chords: parse_all(&binding.name, &binding.inputs),
+                    state: ActionState::default(),
+                };
+                (binding.name.clone(), entry)
+            })
+            .collect();
+        let axes = config
+            .axes
+            .iter()
+            .map(|binding| {
+                let entry = AxisEntry {
+                    positive: parse_all(&binding.name, &binding.positive),
+                    negative: parse_all(&binding.name, &binding.negative),
+                    scale: binding.scale,
+                    value: 0.0,
+                };
+                (binding.name.clone(), entry)
+            })
+            .collect();
+
+        Self {
+            actions,
+            axes,
+            last_cursor_pos: None,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        keys: &KeyInputStateCollection,
+        mouse: &MouseInputStateCollection,
+        gamepads: &GamepadState,
+        cursor_pos: CursorPos,
+        scroll: (f32, f32),
+    ) {
+        let cursor_delta = self.last_cursor_pos.map_or((0.0, 0.0), |last| {
+            (cursor_pos.0 - last.0, cursor_pos.1 - last.1)
+        });
+        self.last_cursor_pos = Some(cursor_pos);
+        let frame = InputFrame {
+            keys,
+            mouse,
+            gamepads,
+            cursor_delta,
+            scroll,
+        };
+
+        for action in self.actions.values_mut() {
+            let pressed = action.chords.iter().any(|chord| chord.value(&frame) != 0.0);
+            let was_pressed = action.state.pressed;
+            action.state = ActionState {
+                pressed,
+                just_pressed: pressed && !was_pressed,
+                just_released: !pressed && was_pressed,
+            };
+        }
+
+        for axis in self.axes.values_mut() {
+            let positive = axis.positive.iter().map(|chord| chord.value(&frame));
+            let negative = axis.negative.iter().map(|chord| chord.value(&frame));
+            axis.value = (positive.sum::<f32>() - negative.sum::<f32>()) * axis.scale;
+        }
+    }
+
+    /// The held keys bound to the uncaptured actions, used instead of all keys
+    /// while the gui captures the keyboard.
+    pub fn uncaptured_keys(&self, keys: &KeyInputStateCollection) -> KeyInputStateCollection {
+        let mut uncaptured = KeyInputStateCollection::default();
+        let mut keep = |key: Key| {
+            if keys.is_key_pressed(key) {
+                uncaptured.push(key);
+            }
+        };
+        let chords = UNCAPTURED_ACTIONS
+            .iter()
+            .filter_map(|name| self.actions.get(*name))
+            .flat_map(|action| action.chords.iter());
+        for chord in chords {
+            for source in chord.sources() {
+                match *source {
+                    InputSource::Key(key) => keep(key),
+                    InputSource::Modifier(modifier) => {
+                        let (left, right) = modifier_keys(modifier);
+                        keep(left);
+                        keep(right);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        uncaptured
+    }
+
+    #[inline]
+    pub fn action(&self, name: &str) -> ActionState {
+        self.actions
+            .get(name)
+            .map_or(ActionState::default(), |action| action.state)
+    }
+
+    #[inline]
+    pub fn is_pressed(&self, name: &str) -> bool {
+        self.action(name).pressed
+    }
+
+    #[inline]
+    pub fn was_pressed(&self, name: &str) -> bool {
+        self.action(name).just_pressed
+    }
+
+    #[inline]
+    pub fn was_released(&self, name: &str) -> bool {
+        self.action(name).just_released
+    }
+
+    #[inline]
+    pub fn axis(&self, name: &str) -> f32 {
+        self.axes.get(name).map_or(0.0, |axis| axis.value)
+    }
+}
+
+fn parse_source(name: &str) -> Option<InputSource> {
+    let lower = name.to_ascii_lowercase();
+    let source = match lower.as_str() {
+        "shift" => InputSource::Modifier(Modifiers::Shift),
+        "control" | "ctrl" => InputSource::Modifier(Modifiers::Control),
+        "alt" => InputSource::Modifier(Modifiers::Alt),
+        "super" => InputSource::Modifier(Modifiers::Super),
+        "mousex" => InputSource::MouseAxis(MouseAxis::X),
+        "mousey" => InputSource::MouseAxis(MouseAxis::Y),
+        "scrollx" => InputSource::MouseAxis(MouseAxis::ScrollX),
+        "scrolly" => InputSource::MouseAxis(MouseAxis::ScrollY),
+        _ => {
+            if let Some(name) = lower.strip_prefix("pad") {
+                let axis_name = format!("axis{}", name);
+                let button_name = format!("button{}", name);
+                if let Some(axis) = GAMEPAD_AXES
+                    .iter()
+                    .find(|axis| format!("{:?}", axis).eq_ignore_ascii_case(&axis_name))
+                {
+                    return Some(InputSource::GamepadAxis(*axis));
+                }
+                return GAMEPAD_BUTTONS
+                    .iter()
+                    .find(|button| format!("{:?}", button).eq_ignore_ascii_case(&button_name))
+                    .map(|button| InputSource::GamepadButton(*button));
+            }
+            if let Some(index) = lower.strip_prefix("mouse") {
+                let index = index.parse::<i32>().ok()?;
+                return MouseButton::from_i32(index - 1).map(InputSource::MouseButton);
+            }
+            return KEYS
+                .iter()
+                .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+                .map(|key| InputSource::Key(*key));
+        }
+    };
+    Some(source)
+}
+
+const GAMEPAD_BUTTONS: &[GamepadButton] = &[
+    GamepadButton::ButtonA,
+    GamepadButton::ButtonB,
+    GamepadButton::ButtonX,
+    GamepadButton::ButtonY,
+    GamepadButton::ButtonLeftBumper,
+    GamepadButton::ButtonRightBumper,
+    GamepadButton::ButtonBack,
+    GamepadButton::ButtonStart,
+    GamepadButton::ButtonGuide,
+    GamepadButton::ButtonLeftThumb,
+    GamepadButton::ButtonRightThumb,
+    GamepadButton::ButtonDpadUp,
+    GamepadButton::ButtonDpadRight,
+    GamepadButton::ButtonDpadDown,
+    GamepadButton::ButtonDpadLeft,
+];
+
+const GAMEPAD_AXES: &[GamepadAxis] = &[
+    GamepadAxis::AxisLeftX,
+    GamepadAxis::AxisLeftY,
+    GamepadAxis::AxisRightX,
+    GamepadAxis::AxisRightY,
+    GamepadAxis::AxisLeftTrigger,
+    GamepadAxis::AxisRightTrigger,
+];
+
+/// All bindable keys, named by their variant.
+const KEYS: &[Key] = &[
+    Key::Space,
+    Key::Apostrophe,
+    Key::Comma,
+    Key::Minus,
+    Key::Period,
+    Key::Slash,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Semicolon,
+    Key::Equal,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::LeftBracket,
+    Key::Backslash,
+    Key::RightBracket,
+    Key::GraveAccent,
+    Key::World1,
+    Key::World2,
+    Key::Escape,
+    Key::Enter,
+    Key::Tab,
+    Key::Backspace,
+    Key::Insert,
+    Key::Delete,
+    Key::Right,
+    Key::Left,
+    Key::Down,
+    Key::Up,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Home,
+    Key::End,
+    Key::CapsLock,
+    Key::ScrollLock,
+    Key::NumLock,
+    Key::PrintScreen,
+    Key::Pause,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+    Key::F21,
+    Key::F22,
+    Key::F23,
+    Key::F24,
+    Key::F25,
+    Key::Kp0,
+    Key::Kp1,
+    Key::Kp2,
+    Key::Kp3,
+    Key::Kp4,
+    Key::Kp5,
+    Key::Kp6,
+    Key::Kp7,
+    Key::Kp8,
+    Key::Kp9,
+    Key::KpDecimal,
+    Key::KpDivide,
+    Key::KpMultiply,
+    Key::KpSubtract,
+    Key::KpAdd,
+    Key::KpEnter,
+    Key::KpEqual,
+    Key::LeftShift,
+    Key::LeftControl,
+    Key::LeftAlt,
+    Key::LeftSuper,
+    Key::RightShift,
+    Key::RightControl,
+    Key::RightAlt,
+    Key::RightSuper,
+    Key::Menu,
+];
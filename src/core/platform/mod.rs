@@ -1,9 +1,13 @@
+pub mod gamepad;
+pub mod gui;
+pub mod input_map;
 pub mod prelude;
+pub mod replay;
 pub mod sys_info;
 
-use crate::config::CoreConfig;
+use crate::config::{CoreConfig, DisplayConfig};
 use glfw::*;
-use log::info;
+use log::{info, warn};
 use rayon::iter::*;
 use std::sync::mpsc::Receiver;
 
@@ -144,10 +148,77 @@ impl WindowData {
         window.set_mouse_button_polling(true);
         window.set_cursor_pos_polling(true);
         window.set_key_polling(true);
+        window.set_scroll_polling(true);
+        window.set_char_polling(true);
+        window.set_focus_polling(true);
+        window.set_iconify_polling(true);
+        window.set_cursor_enter_polling(true);
+        window.set_drag_and_drop_polling(true);
+        window.set_content_scale_polling(true);
+        window.set_size_polling(true);
+        window.set_framebuffer_size_polling(true);
+        // raw motion only applies while the cursor is captured:
+        if context.supports_raw_motion() {
+            window.set_raw_mouse_motion(true);
+        }
         WindowData {
             context,
             window,
             events,
         }
     }
+
+    /// Switches between fullscreen and windowed mode at runtime,
+    /// writing back the resolution actually in use.
+    pub fn apply_display_config(&mut self, display: &mut DisplayConfig) {
+        let window = &mut self.window;
+        let fullscreen = display.window_mode == crate::config::WindowMode::FullScreen;
+        let (width, height) = (display.resolution.0 as i32, display.resolution.1 as i32);
+        let applied = self.context.with_primary_monitor_mut(|_, monitor| {
+            let monitor = monitor?;
+            let video_mode = monitor.get_video_mode()?;
+            if fullscreen {
+                window.set_monitor(
+                    glfw::WindowMode::FullScreen(monitor),
+                    0,
+                    0,
+                    video_mode.width,
+                    video_mode.height,
+                    Some(video_mode.refresh_rate),
+                );
+            } else {
+                // center the window in the work area:
+                let (x, y, area_width, area_height) = monitor.get_workarea();
+                window.set_monitor(
+                    glfw::WindowMode::Windowed,
+                    x + (area_width - width).max(0) / 2,
+                    y + (area_height - height).max(0) / 2,
+                    width as _,
+                    height as _,
+                    None,
+                );
+            }
+            Some(())
+        });
+        if applied.is_none() {
+            if fullscreen {
+                warn!("Failed to retrieve primary monitor! Staying in windowed mode...");
+                display.window_mode = crate::config::WindowMode::Windowed;
+            }
+            window.set_monitor(
+                glfw::WindowMode::Windowed,
+                100,
+                100,
+                width as _,
+                height as _,
+                None,
+            );
+        }
+        let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+        display.resolution = (framebuffer_width as _, framebuffer_height as _);
+        info!(
+            "Window mode: {:?}, resolution: {:?}",
+            display.window_mode, display.resolution
+        );
+    }
 }
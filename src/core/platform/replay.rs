@@ -0,0 +1,130 @@
+//! Recording of the per frame input state and deterministic replay in place of glfw polling.
+
+use super::gamepad::{Gamepad, GamepadState};
+use crate::scenery_resources::{CursorPos, KeyInputStateCollection, MouseInputStateCollection};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Input state of a single frame, after the gui captured its input.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InputFrame {
+    /// Seconds since the previous frame.
+    pub delta: f32,
+    /// Indices of the held keys.
+    pub keys: Vec<u16>,
+    /// Indices of the held mouse buttons.
+    pub mouse_buttons: Vec<u8>,
+    pub cursor_pos: (f32, f32),
+    pub scroll: (f32, f32),
+    /// Connected gamepads by joystick index.
+    pub gamepads: Vec<(usize, Gamepad)>,
+}
+
+impl InputFrame {
+    pub fn capture(
+        delta: f32,
+        keys: &KeyInputStateCollection,
+        mouse: &MouseInputStateCollection,
+        gamepads: &GamepadState,
+        cursor_pos: CursorPos,
+        scroll: (f32, f32),
+    ) -> Self {
+        Self {
+            delta,
+            keys: keys.pressed_indices().map(|index| index as u16).collect(),
+            mouse_buttons: mouse.pressed_indices().map(|index| index as u8).collect(),
+            cursor_pos: (cursor_pos.0, cursor_pos.1),
+            scroll,
+            gamepads: gamepads
+                .indexed_gamepads()
+                .map(|(index, pad)| (index, pad.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        serde_yaml::from_str(&fs::read_to_string(path)?)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let yaml = serde_yaml::to_string(self)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        fs::write(path, yaml)
+    }
+}
+
+/// Collects the input frames and writes them when dropped,
+/// so a session which ends in a panic is still recorded.
+pub struct InputRecorder {
+    path: PathBuf,
+    recording: InputRecording,
+}
+
+impl InputRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        info!("Recording input to: {:?}", path);
+        Self {
+            path,
+            recording: InputRecording::default(),
+        }
+    }
+
+    #[inline]
+    pub fn record(&mut self, frame: InputFrame) {
+        self.recording.frames.push(frame);
+    }
+}
+
+impl Drop for InputRecorder {
+    fn drop(&mut self) {
+        match self.recording.save(&self.path) {
+            Ok(()) => info!(
+                "Recorded {} input frames to: {:?}",
+                self.recording.frames.len(),
+                self.path
+            ),
+            Err(error) => warn!("Failed to save input recording {:?}: {}", self.path, error),
+        }
+    }
+}
+
+/// Hands out the recorded frames in order.
+pub struct InputPlayer {
+    recording: InputRecording,
+    position: usize,
+}
+
+impl InputPlayer {
+    pub fn load(path: &Path) -> Self {
+        let recording = InputRecording::load(path).expect("Failed to load input recording!");
+        info!(
+            "Replaying {} input frames from: {:?}",
+            recording.frames.len(),
+            path
+        );
+        Self {
+            recording,
+            position: 0,
+        }
+    }
+
+    #[inline]
+    pub fn next_frame(&mut self) -> Option<&InputFrame> {
+        let frame = self.recording.frames.get(self.position);
+        self.position += 1;
+        frame
+    }
+}
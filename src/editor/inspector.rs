@@ -0,0 +1,587 @@
+use super::scene_file::{rotation_from_degrees, rotation_to_degrees, SceneFile, SCENE_DIR};
+use crate::components::{
+    Camera, FlyController, FollowController, Light, MeshRenderer, OrbitController, Projection,
+    Transform,
+};
+use crate::resources::{
+    material::{AlphaMode, Material, MaterialProperties},
+    mesh::Mesh,
+    texture::Texture,
+    Resource, ResourceCache, ResourceId, ResourceManager,
+};
+use crate::scenery::Scenery;
+use crate::systems::graphics::GraphicsSystem;
+use cgmath::{Deg, Quaternion, Vector3, Zero};
+use imgui::{
+    im_str, ChildWindow, CollapsingHeader, ColorEdit, ComboBox, Condition, Drag, ImString,
+    Selectable, Ui, Window,
+};
+use legion::{world::Entry, Entity, EntityStore, IntoQuery, World};
+use log::{info, warn};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Structural changes and material rebuilds can't happen while the ui borrows the world,
+/// so they are recorded and applied after the frame.
+pub enum EditCommand {
+    Spawn,
+    Despawn(Entity),
+    SetMaterial {
+        entity: Entity,
+        properties: MaterialProperties,
+        alpha_mode: AlphaMode,
+    },
+    AddMeshRenderer {
+        entity: Entity,
+        mesh: Arc<Mesh>,
+        albedo: Arc<Texture>,
+    },
+    SaveScene(PathBuf),
+}
+
+pub struct Inspector {
+    selected: Option<Entity>,
+    scene_path: ImString,
+    commands: Vec<EditCommand>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        let mut scene_path = ImString::with_capacity(256);
+        scene_path.push_str(SCENE_DIR);
+        scene_path.push_str("/untitled.yml");
+        Self {
+            selected: None,
+            scene_path,
+            commands: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn take_commands(&mut self) -> Vec<EditCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        opened: &mut bool,
+        world: &mut World,
+        resource_manager: Option<&ResourceManager>,
+    ) {
+        let selected = &mut self.selected;
+        let scene_path = &mut self.scene_path;
+        let commands = &mut self.commands;
+        Window::new(im_str!("Inspector"))
+            .opened(opened)
+            .position([16.0, 256.0], Condition::FirstUseEver)
+            .size([360.0, 560.0], Condition::FirstUseEver)
+            .build(ui, || {
+                if ui.small_button(im_str!("Spawn")) {
+                    commands.push(EditCommand::Spawn);
+                }
+                if let Some(entity) = *selected {
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Despawn")) {
+                        commands.push(EditCommand::Despawn(entity));
+                    }
+                }
+                ui.input_text(im_str!("##scene"), scene_path).build();
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Save scene")) {
+                    commands.push(EditCommand::SaveScene(PathBuf::from(scene_path.to_str())));
+                }
+                ui.separator();
+
+                ui.text(format!("Entities: {}", world.len()));
+                let entities = <Entity>::query()
+                    .iter(world)
+                    .copied()
+                    .collect::<Vec<Entity>>();
+                ChildWindow::new("entities")
+                    .size([0.0, 160.0])
+                    .border(true)
+                    .build(ui, || {
+                        for entity in entities {
+                            let label = ImString::from(format!(
+                                "{:?} {}",
+                                entity,
+                                entity_kind(world, entity)
+                            ));
+                            if Selectable::new(&label)
+                                .selected(*selected == Some(entity))
+                                .build(ui)
+                            {
+                                *selected = Some(entity);
+                            }
+                        }
+                    });
+
+                let entity = match *selected {
+                    Some(entity) => entity,
+                    None => return,
+                };
+                let mut entry = match world.entry(entity) {
+                    Some(entry) => entry,
+                    None => {
+                        *selected = None;
+                        return;
+                    }
+                };
+                edit_transform(ui, &mut entry);
+                edit_camera(ui, &mut entry);
+                edit_fly_controller(ui, &mut entry);
+                edit_orbit_controller(ui, &mut entry);
+                edit_follow_controller(ui, &mut entry);
+                if let Some(resource_manager) = resource_manager {
+                    edit_mesh_renderer(ui, entity, &mut entry, resource_manager, commands);
+                }
+            });
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn apply_commands(commands: Vec<EditCommand>, system: &GraphicsSystem, scenery: &mut Scenery) {
+    for command in commands {
+        match command {
+            EditCommand::Spawn => {
+                let entity = scenery.world.push((Transform {
+                    position: Vector3::zero(),
+                    rotation: Quaternion::from_sv(1.0, Vector3::zero()),
+                    scale: Vector3::new(1.0, 1.0, 1.0),
+                },));
+                info!("Spawned entity {:?}", entity);
+            }
+            EditCommand::Despawn(entity) => {
+                if scenery.world.remove(entity) {
+                    info!("Despawned entity {:?}", entity);
+                }
+            }
+            EditCommand::SetMaterial {
+                entity,
+                properties,
+                alpha_mode,
+            } => {
+                if let Some(mut entry) = scenery.world.entry(entity) {
+                    if let Ok(renderer) = entry.get_component_mut::<MeshRenderer>() {
+                        renderer.material = Material::load(system, properties, alpha_mode);
+                    }
+                }
+            }
+            EditCommand::AddMeshRenderer {
+                entity,
+                mesh,
+                albedo,
+            } => {
+                if let Some(mut entry) = scenery.world.entry(entity) {
+                    entry.add_component(MeshRenderer {
+                        mesh,
+                        material: Material::load(
+                            system,
+                            MaterialProperties::Lambert { albedo },
+                            AlphaMode::Opaque,
+                        ),
+                        cast_shadows: true,
+                        receive_shadows: true,
+                    });
+                }
+            }
+            EditCommand::SaveScene(path) => {
+                let resource_manager = match scenery.resources.get::<ResourceManager>() {
+                    Some(resource_manager) => resource_manager,
+                    None => {
+                        warn!("Can't save scene without a resource manager!");
+                        continue;
+                    }
+                };
+                if let Err(error) =
+                    SceneFile::capture(&scenery.world, &resource_manager).save(&path)
+                {
+                    warn!("Failed to save scene {:?}: {}", path, error);
+                }
+            }
+        }
+    }
+}
+
+fn entity_kind(world: &World, entity: Entity) -> &'static str {
+    let entry = match world.entry_ref(entity) {
+        Ok(entry) => entry,
+        Err(_) => return "",
+    };
+    if entry.get_component::<Camera>().is_ok() {
+        "Camera"
+    } else if entry.get_component::<Light>().is_ok() {
+        "Light"
+    } else if entry.get_component::<MeshRenderer>().is_ok() {
+        "Mesh"
+    } else {
+        ""
+    }
+}
+
+fn resource_label<T: Resource>(cache: &ResourceCache<T>, id: ResourceId) -> ImString {
+    match cache.path(id).and_then(|path| path.file_name()) {
+        Some(name) => ImString::from(name.to_string_lossy().into_owned()),
+        None => ImString::from(format!("{:#X}", id)),
+    }
+}
+
+/// Combo box listing all cached resources, returns the picked one.
+fn resource_picker<T: Resource>(
+    ui: &Ui,
+    label: &imgui::ImStr,
+    cache: &ResourceCache<T>,
+    current: &Arc<T>,
+) -> Option<Arc<T>> {
+    let current_id = cache.find(current);
+    let preview = current_id.map_or_else(
+        || ImString::new("<not cached>"),
+        |id| resource_label(cache, id),
+    );
+    let mut picked = None;
+    ComboBox::new(label).preview_value(&preview).build(ui, || {
+        for (id, resource) in cache.table() {
+            let label = resource_label(cache, *id);
+            if Selectable::new(&label)
+                .selected(current_id == Some(*id))
+                .build(ui)
+            {
+                picked = Some(resource.clone());
+            }
+        }
+    });
+    picked
+}
+
+fn edit_transform(ui: &Ui, entry: &mut Entry) {
+    let transform = match entry.get_component_mut::<Transform>() {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    if !CollapsingHeader::new(im_str!("Transform"))
+        .default_open(true)
+        .build(ui)
+    {
+        return;
+    }
+    let mut position: [f32; 3] = transform.position.into();
+    if Drag::new(im_str!("Position"))
+        .speed(0.01)
+        .build_array(ui, &mut position)
+    {
+        transform.position = position.into();
+    }
+    let mut rotation = rotation_to_degrees(transform.rotation);
+    if Drag::new(im_str!("Rotation"))
+        .speed(0.5)
+        .build_array(ui, &mut rotation)
+    {
+        transform.rotation = rotation_from_degrees(rotation);
+    }
+    let mut scale: [f32; 3] = transform.scale.into();
+    if Drag::new(im_str!("Scale"))
+        .speed(0.01)
+        .build_array(ui, &mut scale)
+    {
+        transform.scale = scale.into();
+    }
+}
+
+fn edit_camera(ui: &Ui, entry: &mut Entry) {
+    let camera = match entry.get_component_mut::<Camera>() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    if !CollapsingHeader::new(im_str!("Camera"))
+        .default_open(true)
+        .build(ui)
+    {
+        return;
+    }
+    let perspective = matches!(camera.projection, Projection::Perspective { .. });
+    if ui.radio_button_bool(im_str!("Perspective"), perspective) && !perspective {
+        camera.projection = Projection::Perspective { fov: Deg(75.0) };
+    }
+    ui.same_line(0.0);
+    if ui.radio_button_bool(im_str!("Orthographic"), !perspective) && perspective {
+        camera.projection = Projection::Orthographic { height: 10.0 };
+    }
+    match &mut camera.projection {
+        Projection::Perspective { fov } => {
+            Drag::new(im_str!("Fov"))
+                .range(1.0..=179.0)
+                .build(ui, &mut fov.0);
+        }
+        Projection::Orthographic { height } => {
+            Drag::new(im_str!("Height"))
+                .speed(0.1)
+                .range(0.01..=10_000.0)
+                .build(ui, height);
+        }
+    }
+    Drag::new(im_str!("Near clip"))
+        .speed(0.01)
+        .range(0.001..=camera.far_clip)
+        .build(ui, &mut camera.near_clip);
+    Drag::new(im_str!("Far clip"))
+        .speed(0.5)
+        .range(camera.near_clip..=100_000.0)
+        .build(ui, &mut camera.far_clip);
+}
+
+fn edit_fly_controller(ui: &Ui, entry: &mut Entry) {
+    let controller = match entry.get_component_mut::<FlyController>() {
+        Ok(controller) => controller,
+        Err(_) => return,
+    };
+    if !CollapsingHeader::new(im_str!("Fly controller"))
+        .default_open(true)
+        .build(ui)
+    {
+        return;
+    }
+    Drag::new(im_str!("Look sensitivity"))
+        .speed(0.01)
+        .range(0.0..=10.0)
+        .build(ui, &mut controller.look_sensitivity);
+    Drag::new(im_str!("Smoothing"))
+        .speed(0.1)
+        .range(0.0..=100.0)
+        .build(ui, &mut controller.smoothing);
+    Drag::new(im_str!("Clamp y"))
+        .range(0.0..=89.0)
+        .build(ui, &mut controller.clamp_y.0);
+    Drag::new(im_str!("Speed"))
+        .speed(0.01)
+        .range(0.0..=100.0)
+        .build(ui, &mut controller.speed);
+    Drag::new(im_str!("Sprint multiplier"))
+        .speed(0.01)
+        .range(1.0..=20.0)
+        .build(ui, &mut controller.sprint_multiplier);
+    Drag::new(im_str!("Vertical speed"))
+        .speed(0.01)
+        .range(0.0..=100.0)
+        .build(ui, &mut controller.vertical_speed);
+}
+
+fn edit_orbit_controller(ui: &Ui, entry: &mut Entry) {
+    let controller = match entry.get_component_mut::<OrbitController>() {
+        Ok(controller) => controller,
+        Err(_) => return,
+    };
+    if !CollapsingHeader::new(im_str!("Orbit controller"))
+        .default_open(true)
+        .build(ui)
+    {
+        return;
+    }
+    let mut target: [f32; 3] = controller.target.into();
+    if Drag::new(im_str!("Target"))
+        .speed(0.01)
+        .build_array(ui, &mut target)
+    {
+        controller.target = target.into();
+    }
+    Drag::new(im_str!("Distance"))
+        .speed(0.01)
+        .range(controller.min_distance..=controller.max_distance)
+        .build(ui, &mut controller.distance);
+    Drag::new(im_str!("Min distance"))
+        .speed(0.01)
+        .range(0.01..=controller.max_distance)
+        .build(ui, &mut controller.min_distance);
+    Drag::new(im_str!("Max distance"))
+        .speed(0.1)
+        .range(controller.min_distance..=10_000.0)
+        .build(ui, &mut controller.max_distance);
+    Drag::new(im_str!("Look sensitivity"))
+        .speed(0.01)
+        .range(0.0..=10.0)
+        .build(ui, &mut controller.look_sensitivity);
+    Drag::new(im_str!("Zoom speed"))
+        .speed(0.001)
+        .range(0.0..=0.9)
+        .build(ui, &mut controller.zoom_speed);
+    Drag::new(im_str!("Pan speed"))
+        .speed(0.01)
+        .range(0.0..=100.0)
+        .build(ui, &mut controller.pan_speed);
+    Drag::new(im_str!("Clamp y"))
+        .range(0.0..=89.0)
+        .build(ui, &mut controller.clamp_y.0);
+}
+
+fn edit_follow_controller(ui: &Ui, entry: &mut Entry) {
+    let controller = match entry.get_component_mut::<FollowController>() {
+        Ok(controller) => controller,
+        Err(_) => return,
+    };
+    if !CollapsingHeader::new(im_str!("Follow controller"))
+        .default_open(true)
+        .build(ui)
+    {
+        return;
+    }
+    ui.text(format!("Target: {:?}", controller.target));
+    let mut offset: [f32; 3] = controller.offset.into();
+    if Drag::new(im_str!("Offset"))
+        .speed(0.01)
+        .build_array(ui, &mut offset)
+    {
+        controller.offset = offset.into();
+    }
+    Drag::new(im_str!("Stiffness"))
+        .speed(0.1)
+        .range(0.0..=100.0)
+        .build(ui, &mut controller.stiffness);
+}
+
+fn edit_mesh_renderer(
+    ui: &Ui,
+    entity: Entity,
+    entry: &mut Entry,
+    resource_manager: &ResourceManager,
+    commands: &mut Vec<EditCommand>,
+) {
+    let renderer = match entry.get_component_mut::<MeshRenderer>() {
+        Ok(renderer) => renderer,
+        Err(_) => {
+            let mesh = resource_manager.mesh_cache.table().values().next();
+            let albedo = resource_manager.texture_cache.table().values().next();
+            if let (Some(mesh), Some(albedo)) = (mesh, albedo) {
+                if ui.small_button(im_str!("Add mesh renderer")) {
+                    commands.push(EditCommand::AddMeshRenderer {
+                        entity,
+                        mesh: mesh.clone(),
+                        albedo: albedo.clone(),
+                    });
+                }
+            }
+            return;
+        }
+    };
+    if !CollapsingHeader::new(im_str!("Mesh renderer"))
+        .default_open(true)
+        .build(ui)
+    {
+        return;
+    }
+
+    if let Some(mesh) = resource_picker(
+        ui,
+        im_str!("Mesh"),
+        &resource_manager.mesh_cache,
+        &renderer.mesh,
+    ) {
+        renderer.mesh = mesh;
+    }
+    ui.checkbox(im_str!("Cast shadows"), &mut renderer.cast_shadows);
+    ui.checkbox(im_str!("Receive shadows"), &mut renderer.receive_shadows);
+
+    // material edits rebuild the material, everything else is edited in place:
+    let mut properties = renderer.material.properties().clone();
+    let mut alpha_mode = renderer.material.alpha_mode();
+    let mut changed = false;
+
+    ui.separator();
+    match &mut properties {
+        MaterialProperties::Lambert { albedo } => {
+            ui.text("Material: Lambert");
+            if let Some(texture) = resource_picker(
+                ui,
+                im_str!("Albedo"),
+                &resource_manager.texture_cache,
+                albedo,
+            ) {
+                *albedo = texture;
+                changed = true;
+            }
+        }
+        MaterialProperties::Pbr {
+            albedo, factors, ..
+        } => {
+            ui.text("Material: Pbr");
+            if let Some(texture) = resource_picker(
+                ui,
+                im_str!("Albedo"),
+                &resource_manager.texture_cache,
+                albedo,
+            ) {
+                *albedo = texture;
+                changed = true;
+            }
+            let mut base_color: [f32; 4] = factors.base_color.into();
+            if ColorEdit::new(im_str!("Base color"), &mut base_color).build(ui) {
+                factors.base_color = base_color.into();
+                changed = true;
+            }
+            changed |= Drag::new(im_str!("Metallic"))
+                .speed(0.01)
+                .range(0.0..=1.0)
+                .build(ui, &mut factors.metallic);
+            changed |= Drag::new(im_str!("Roughness"))
+                .speed(0.01)
+                .range(0.0..=1.0)
+                .build(ui, &mut factors.roughness);
+            let mut emissive: [f32; 3] = factors.emissive.into();
+            if ColorEdit::new(im_str!("Emissive"), &mut emissive).build(ui) {
+                factors.emissive = emissive.into();
+                changed = true;
+            }
+        }
+    }
+
+    let modes = [
+        AlphaMode::Opaque,
+        AlphaMode::Mask {
+            cutoff: alpha_mode.cutoff().max(0.5),
+        },
+        AlphaMode::Blend,
+    ];
+    let preview = ImString::new(alpha_mode_name(alpha_mode));
+    ComboBox::new(im_str!("Alpha mode"))
+        .preview_value(&preview)
+        .build(ui, || {
+            for &mode in &modes {
+                let label = ImString::new(alpha_mode_name(mode));
+                if Selectable::new(&label)
+                    .selected(alpha_mode_name(mode) == alpha_mode_name(alpha_mode))
+                    .build(ui)
+                    && mode != alpha_mode
+                {
+                    alpha_mode = mode;
+                    changed = true;
+                }
+            }
+        });
+    if let AlphaMode::Mask { cutoff } = &mut alpha_mode {
+        changed |= Drag::new(im_str!("Cutoff"))
+            .speed(0.01)
+            .range(0.0..=1.0)
+            .build(ui, cutoff);
+    }
+
+    if changed {
+        commands.push(EditCommand::SetMaterial {
+            entity,
+            properties,
+            alpha_mode,
+        });
+    }
+}
+
+#[inline]
+fn alpha_mode_name(mode: AlphaMode) -> &'static str {
+    match mode {
+        AlphaMode::Opaque => "Opaque",
+        AlphaMode::Mask { .. } => "Mask",
+        AlphaMode::Blend => "Blend",
+    }
+}
@@ -0,0 +1,155 @@
+pub mod inspector;
+mod panels;
+pub mod scene_file;
+
+use crate::config::{AppConfig, CoreConfig, DisplayConfig, GraphicsConfig, MemoryConfig};
+use crate::core::graphics::pipeline_cache::PipelineCacheStats;
+use crate::core::graphics::render_graph::RenderGraphStats;
+use crate::core::graphics::shader_compiler::ShaderError;
+use crate::resources::ResourceManager;
+use crate::scenery::Scenery;
+use crate::scenery_resources::{FrameTime, InputMap, RuntimeConfig};
+use imgui::{im_str, MenuItem, Ui};
+use inspector::{EditCommand, Inspector};
+use log::info;
+use serde::Serialize;
+
+/// Number of frames kept for the frame time graph.
+pub const FRAME_HISTORY: usize = 128;
+
+/// Built in engine debug panels, drawn on top of the simulation.
+pub struct DebugUi {
+    pub visible: bool,
+    pub show_frame_stats: bool,
+    pub show_inspector: bool,
+    pub show_resources: bool,
+    pub show_config: bool,
+    pub show_settings: bool,
+    frame_times: [f32; FRAME_HISTORY],
+    frame_time_offset: usize,
+    inspector: Inspector,
+    config_snapshot: Vec<(&'static str, String)>,
+    settings: CoreConfig,
+    shader_errors: Vec<ShaderError>,
+}
+
+impl DebugUi {
+    pub fn new(config: &CoreConfig) -> Self {
+        Self {
+            visible: false,
+            show_frame_stats: true,
+            show_inspector: true,
+            show_resources: false,
+            show_config: false,
+            show_settings: false,
+            frame_times: [0.0; FRAME_HISTORY],
+            frame_time_offset: 0,
+            inspector: Inspector::new(),
+            config_snapshot: config_snapshot(config),
+            settings: config.clone(),
+            shader_errors: Vec::new(),
+        }
+    }
+
+    /// Shows the config applied at runtime.
+    pub fn set_config(&mut self, config: &CoreConfig) {
+        self.config_snapshot = config_snapshot(config);
+        self.settings = config.clone();
+    }
+
+    /// Shows the errors of the last shader reload, even while the debug ui is hidden.
+    #[inline]
+    pub fn set_shader_errors(&mut self, errors: Vec<ShaderError>) {
+        self.shader_errors = errors;
+    }
+
+    pub fn update(&mut self, input: &InputMap, frame_time: &FrameTime) {
+        if input.was_pressed("toggle_debug_ui") {
+            self.visible = !self.visible;
+            info!("Debug UI {}", if self.visible { "shown" } else { "hidden" });
+        }
+        self.frame_times[self.frame_time_offset] = frame_time.delta * 1000.0;
+        self.frame_time_offset = (self.frame_time_offset + 1) % FRAME_HISTORY;
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        scenery: &mut Scenery,
+        graph_stats: &RenderGraphStats,
+        cache_stats: &PipelineCacheStats,
+    ) {
+        if !self.shader_errors.is_empty() {
+            panels::shader_errors(ui, &self.shader_errors);
+        }
+        if !self.visible {
+            return;
+        }
+
+        ui.main_menu_bar(|| {
+            ui.menu(im_str!("Windows"), true, || {
+                MenuItem::new(im_str!("Frame stats"))
+                    .build_with_ref(ui, &mut self.show_frame_stats);
+                MenuItem::new(im_str!("Inspector")).build_with_ref(ui, &mut self.show_inspector);
+                MenuItem::new(im_str!("Resources")).build_with_ref(ui, &mut self.show_resources);
+                MenuItem::new(im_str!("Config")).build_with_ref(ui, &mut self.show_config);
+                MenuItem::new(im_str!("Settings")).build_with_ref(ui, &mut self.show_settings);
+            });
+        });
+
+        if self.show_frame_stats {
+            panels::frame_stats(
+                ui,
+                &mut self.show_frame_stats,
+                &self.frame_times,
+                self.frame_time_offset,
+                graph_stats,
+                cache_stats,
+            );
+        }
+        if self.show_inspector {
+            let resource_manager = scenery.resources.get::<ResourceManager>();
+            self.inspector.draw(
+                ui,
+                &mut self.show_inspector,
+                &mut scenery.world,
+                resource_manager.as_deref(),
+            );
+        }
+        if self.show_resources {
+            panels::resources(ui, &mut self.show_resources, scenery);
+        }
+        if self.show_config {
+            panels::config(ui, &mut self.show_config, &self.config_snapshot);
+        }
+        if self.show_settings {
+            if let Some(mut runtime_config) = scenery.resources.get_mut::<RuntimeConfig>() {
+                panels::settings(
+                    ui,
+                    &mut self.show_settings,
+                    &mut self.settings,
+                    &mut runtime_config,
+                );
+            }
+        }
+    }
+
+    /// Edits recorded by the inspector, to be applied once the frame is done.
+    #[inline]
+    pub fn take_edits(&mut self) -> Vec<EditCommand> {
+        self.inspector.take_commands()
+    }
+}
+
+fn config_snapshot(config: &CoreConfig) -> Vec<(&'static str, String)> {
+    fn snapshot<T: Serialize>(config: &T) -> String {
+        serde_yaml::to_string(config).unwrap_or_default()
+    }
+
+    vec![
+        (AppConfig::FILE_NAME, snapshot(&config.application_config)),
+        (MemoryConfig::FILE_NAME, snapshot(&config.memory_config)),
+        (DisplayConfig::FILE_NAME, snapshot(&config.display_config)),
+        (GraphicsConfig::FILE_NAME, snapshot(&config.graphics_config)),
+    ]
+}
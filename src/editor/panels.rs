@@ -0,0 +1,222 @@
+use crate::config::{CoreConfig, DepthMode, MsaaMode, WindowMode};
+use crate::core::graphics::pipeline_cache::PipelineCacheStats;
+use crate::core::graphics::render_graph::RenderGraphStats;
+use crate::core::graphics::shader_compiler::ShaderError;
+use crate::resources::ResourceManager;
+use crate::scenery::Scenery;
+use crate::scenery_resources::RuntimeConfig;
+use imgui::{im_str, CollapsingHeader, Condition, Drag, ImString, Ui, Window};
+
+pub fn frame_stats(
+    ui: &Ui,
+    opened: &mut bool,
+    frame_times: &[f32],
+    offset: usize,
+    graph_stats: &RenderGraphStats,
+    cache_stats: &PipelineCacheStats,
+) {
+    Window::new(im_str!("Frame stats"))
+        .opened(opened)
+        .position([16.0, 32.0], Condition::FirstUseEver)
+        .always_auto_resize(true)
+        .build(ui, || {
+            let average = frame_times.iter().sum::<f32>() / frame_times.len() as f32;
+            let fps = if average > 0.0 { 1000.0 / average } else { 0.0 };
+            let overlay = ImString::from(format!("{:.2} ms", average));
+            ui.text(format!("FPS: {:.1}", fps));
+            ui.plot_lines(im_str!("Frame time"), frame_times)
+                .values_offset(offset)
+                .overlay_text(&overlay)
+                .scale_min(0.0)
+                .graph_size([256.0, 64.0])
+                .build();
+            ui.separator();
+            ui.text("Render graph:");
+            ui.text(format!(
+                "Passes: {} ({} culled)",
+                graph_stats.passes, graph_stats.culled_passes
+            ));
+            ui.text(format!(
+                "Transient textures: {} ({} physical)",
+                graph_stats.transient_textures, graph_stats.physical_textures
+            ));
+            ui.separator();
+            ui.text("Pipeline cache:");
+            ui.text(format!(
+                "Render pipelines: {} ({} hits)",
+                cache_stats.render_pipelines, cache_stats.render_pipeline_hits
+            ));
+            ui.text(format!(
+                "Bind group layouts: {} ({} hits)",
+                cache_stats.bind_group_layouts, cache_stats.bind_group_layout_hits
+            ));
+            ui.text(format!(
+                "Samplers: {} ({} hits)",
+                cache_stats.samplers, cache_stats.sampler_hits
+            ));
+        });
+}
+
+pub fn resources(ui: &Ui, opened: &mut bool, scenery: &Scenery) {
+    Window::new(im_str!("Resources"))
+        .opened(opened)
+        .position([352.0, 32.0], Condition::FirstUseEver)
+        .size([320.0, 400.0], Condition::FirstUseEver)
+        .build(ui, || {
+            let manager = match scenery.resources.get::<ResourceManager>() {
+                Some(manager) => manager,
+                None => {
+                    ui.text_disabled("No resource manager");
+                    return;
+                }
+            };
+
+            let textures = manager.texture_cache.table();
+            if CollapsingHeader::new(&ImString::from(format!("Textures ({})", textures.len())))
+                .build(ui)
+            {
+                for (id, texture) in textures {
+                    ui.text(format!(
+                        "{:#X}: {}x{}",
+                        id,
+                        texture.width(),
+                        texture.height()
+                    ));
+                }
+            }
+
+            let linear_textures = manager.linear_texture_cache.table();
+            if CollapsingHeader::new(&ImString::from(format!(
+                "Linear textures ({})",
+                linear_textures.len()
+            )))
+            .build(ui)
+            {
+                for (id, texture) in linear_textures {
+                    ui.text(format!(
+                        "{:#X}: {}x{}",
+                        id,
+                        texture.width(),
+                        texture.height()
+                    ));
+                }
+            }
+
+            let meshes = manager.mesh_cache.table();
+            if CollapsingHeader::new(&ImString::from(format!("Meshes ({})", meshes.len())))
+                .build(ui)
+            {
+                for (id, mesh) in meshes {
+                    ui.text(format!(
+                        "{:#X}: {} vertices, {} indices",
+                        id,
+                        mesh.vertices().len(),
+                        mesh.indices().len()
+                    ));
+                }
+            }
+        });
+}
+
+pub fn config(ui: &Ui, opened: &mut bool, snapshot: &[(&'static str, String)]) {
+    Window::new(im_str!("Config"))
+        .opened(opened)
+        .position([352.0, 448.0], Condition::FirstUseEver)
+        .size([320.0, 400.0], Condition::FirstUseEver)
+        .build(ui, || {
+            ui.text_disabled("Values in effect");
+            for (file_name, yaml) in snapshot {
+                if CollapsingHeader::new(&ImString::new(*file_name)).build(ui) {
+                    for line in yaml.lines().filter(|line| *line != "---") {
+                        ui.text(line);
+                    }
+                }
+            }
+        });
+}
+
+/// Edits a copy of the display and graphics settings, which is applied live and saved on apply.
+pub fn settings(
+    ui: &Ui,
+    opened: &mut bool,
+    draft: &mut CoreConfig,
+    runtime_config: &mut RuntimeConfig,
+) {
+    Window::new(im_str!("Settings"))
+        .opened(opened)
+        .position([688.0, 32.0], Condition::FirstUseEver)
+        .size([320.0, 240.0], Condition::FirstUseEver)
+        .build(ui, || {
+            let display = &mut draft.display_config;
+            let fullscreen = display.window_mode == WindowMode::FullScreen;
+            if ui.radio_button_bool(im_str!("Windowed"), !fullscreen) {
+                display.window_mode = WindowMode::Windowed;
+            }
+            ui.same_line(0.0);
+            if ui.radio_button_bool(im_str!("Fullscreen"), fullscreen) {
+                display.window_mode = WindowMode::FullScreen;
+            }
+            // fullscreen always uses the video mode of the monitor:
+            if !fullscreen {
+                Drag::new(im_str!("Width"))
+                    .range(800..=16384)
+                    .build(ui, &mut display.resolution.0);
+                Drag::new(im_str!("Height"))
+                    .range(600..=16384)
+                    .build(ui, &mut display.resolution.1);
+            }
+            ui.checkbox(im_str!("VSync"), &mut display.vsync);
+
+            let graphics = &mut draft.graphics_config;
+            ui.text("MSAA");
+            for (label, mode) in &[
+                (im_str!("Off"), MsaaMode::Off),
+                (im_str!("2x"), MsaaMode::X2),
+                (im_str!("4x"), MsaaMode::X4),
+                (im_str!("8x"), MsaaMode::X8),
+            ] {
+                ui.same_line(0.0);
+                if ui.radio_button_bool(label, graphics.msaa_mode == *mode) {
+                    graphics.msaa_mode = *mode;
+                }
+            }
+            let post_processing = &graphics.post_processing;
+            if post_processing.enabled && post_processing.fxaa {
+                ui.text_disabled("FXAA is enabled, MSAA is ignored");
+            }
+            let reverse_z = graphics.depth_mode == DepthMode::ReverseZ;
+            if ui.radio_button_bool(im_str!("Standard depth"), !reverse_z) {
+                graphics.depth_mode = DepthMode::Standard;
+            }
+            ui.same_line(0.0);
+            if ui.radio_button_bool(im_str!("Reverse-Z"), reverse_z) {
+                graphics.depth_mode = DepthMode::ReverseZ;
+            }
+
+            ui.separator();
+            if ui.button(im_str!("Apply"), [0.0, 0.0]) {
+                runtime_config.request(draft.clone(), true);
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("Revert"), [0.0, 0.0]) {
+                *draft = runtime_config.current.clone();
+            }
+        });
+}
+
+/// Errors of the last shader hot reload, the previous pipelines stay in use until they are fixed.
+pub fn shader_errors(ui: &Ui, errors: &[ShaderError]) {
+    const ERROR_COLOR: [f32; 4] = [1.0, 0.35, 0.35, 1.0];
+    Window::new(im_str!("Shader errors"))
+        .position([352.0, 448.0], Condition::FirstUseEver)
+        .size([640.0, 240.0], Condition::FirstUseEver)
+        .build(ui, || {
+            for error in errors {
+                ui.text(format!("{}", error.path.display()));
+                for diagnostic in &error.diagnostics {
+                    ui.text_colored(ERROR_COLOR, format!("  {}", diagnostic));
+                }
+                ui.separator();
+            }
+        });
+}
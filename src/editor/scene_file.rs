@@ -0,0 +1,318 @@
+use crate::components::{
+    Camera, FlyController, FollowController, Light, LightKind, MeshRenderer, OrbitController,
+    Projection, Transform,
+};
+use crate::resources::{material::AlphaMode, material::MaterialProperties, ResourceManager};
+use cgmath::{Deg, Euler, Quaternion};
+use legion::{Entity, EntityStore, IntoQuery, World};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SCENE_DIR: &str = "db/scenes";
+
+/// Serializable snapshot of the scenery world.
+/// Resources are referenced by the path they were imported from.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SceneFile {
+    pub entities: Vec<EntityDescriptor>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EntityDescriptor {
+    pub transform: Option<TransformDescriptor>,
+    pub camera: Option<CameraDescriptor>,
+    pub fly_controller: Option<FlyControllerDescriptor>,
+    pub orbit_controller: Option<OrbitControllerDescriptor>,
+    pub follow_controller: Option<FollowControllerDescriptor>,
+    pub light: Option<LightDescriptor>,
+    pub mesh_renderer: Option<MeshRendererDescriptor>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransformDescriptor {
+    pub position: [f32; 3],
+    /// Euler angles in degrees.
+    pub rotation: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ProjectionDescriptor {
+    /// Vertical field of view in degrees.
+    Perspective {
+        fov: f32,
+    },
+    Orthographic {
+        height: f32,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CameraDescriptor {
+    pub projection: ProjectionDescriptor,
+    pub near_clip: f32,
+    pub far_clip: f32,
+    pub forward: [f32; 3],
+}
+
+/// Angles are yaw and pitch in radians.
+#[derive(Serialize, Deserialize)]
+pub struct FlyControllerDescriptor {
+    pub look_sensitivity: f32,
+    pub smoothing: f32,
+    pub clamp_y: f32,
+    pub speed: f32,
+    pub sprint_multiplier: f32,
+    pub vertical_speed: f32,
+    pub angles: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OrbitControllerDescriptor {
+    pub target: [f32; 3],
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub look_sensitivity: f32,
+    pub zoom_speed: f32,
+    pub pan_speed: f32,
+    pub clamp_y: f32,
+    pub angles: [f32; 2],
+}
+
+/// The target is the index of the followed entity in the scene file.
+#[derive(Serialize, Deserialize)]
+pub struct FollowControllerDescriptor {
+    pub target: Option<usize>,
+    pub offset: [f32; 3],
+    pub stiffness: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum LightKindDescriptor {
+    Directional,
+    Spot { range: f32, angle: f32 },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LightDescriptor {
+    pub kind: LightKindDescriptor,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub cast_shadows: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MeshRendererDescriptor {
+    pub mesh: Option<PathBuf>,
+    pub material: MaterialDescriptor,
+    pub alpha_mode: AlphaMode,
+    pub cast_shadows: bool,
+    pub receive_shadows: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum MaterialDescriptor {
+    Lambert {
+        albedo: Option<PathBuf>,
+    },
+    Pbr {
+        albedo: Option<PathBuf>,
+        normal: Option<PathBuf>,
+        metallic_roughness: Option<PathBuf>,
+        occlusion: Option<PathBuf>,
+        emissive: Option<PathBuf>,
+        base_color: [f32; 4],
+        metallic: f32,
+        roughness: f32,
+        occlusion_strength: f32,
+        normal_scale: f32,
+        emissive_factor: [f32; 3],
+    },
+}
+
+impl SceneFile {
+    pub fn capture(world: &World, resource_manager: &ResourceManager) -> Self {
+        let all_entities = <Entity>::query().iter(world).copied().collect::<Vec<_>>();
+        let entities = all_entities
+            .iter()
+            .map(|entity| {
+                let entry = world
+                    .entry_ref(*entity)
+                    .expect("Queried entity does not exist!");
+                EntityDescriptor {
+                    transform: entry
+                        .get_component::<Transform>()
+                        .ok()
+                        .map(describe_transform),
+                    camera: entry.get_component::<Camera>().ok().map(describe_camera),
+                    fly_controller: entry
+                        .get_component::<FlyController>()
+                        .ok()
+                        .map(describe_fly_controller),
+                    orbit_controller: entry
+                        .get_component::<OrbitController>()
+                        .ok()
+                        .map(describe_orbit_controller),
+                    follow_controller: entry.get_component::<FollowController>().ok().map(
+                        |controller| FollowControllerDescriptor {
+                            target: all_entities
+                                .iter()
+                                .position(|entity| *entity == controller.target),
+                            offset: controller.offset.into(),
+                            stiffness: controller.stiffness,
+                        },
+                    ),
+                    light: entry.get_component::<Light>().ok().map(describe_light),
+                    mesh_renderer: entry
+                        .get_component::<MeshRenderer>()
+                        .ok()
+                        .map(|renderer| describe_mesh_renderer(renderer, resource_manager)),
+                }
+            })
+            .collect();
+        Self { entities }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        info!("Saving scene: {:?}", path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let yaml = serde_yaml::to_string(self)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        fs::write(path, yaml)
+    }
+}
+
+fn describe_transform(transform: &Transform) -> TransformDescriptor {
+    TransformDescriptor {
+        position: transform.position.into(),
+        rotation: rotation_to_degrees(transform.rotation),
+        scale: transform.scale.into(),
+    }
+}
+
+fn describe_camera(camera: &Camera) -> CameraDescriptor {
+    CameraDescriptor {
+        projection: match camera.projection {
+            Projection::Perspective { fov } => ProjectionDescriptor::Perspective { fov: fov.0 },
+            Projection::Orthographic { height } => ProjectionDescriptor::Orthographic { height },
+        },
+        near_clip: camera.near_clip,
+        far_clip: camera.far_clip,
+        forward: camera.forward.into(),
+    }
+}
+
+fn describe_fly_controller(controller: &FlyController) -> FlyControllerDescriptor {
+    FlyControllerDescriptor {
+        look_sensitivity: controller.look_sensitivity,
+        smoothing: controller.smoothing,
+        clamp_y: controller.clamp_y.0,
+        speed: controller.speed,
+        sprint_multiplier: controller.sprint_multiplier,
+        vertical_speed: controller.vertical_speed,
+        angles: controller.angles.into(),
+    }
+}
+
+fn describe_orbit_controller(controller: &OrbitController) -> OrbitControllerDescriptor {
+    OrbitControllerDescriptor {
+        target: controller.target.into(),
+        distance: controller.distance,
+        min_distance: controller.min_distance,
+        max_distance: controller.max_distance,
+        look_sensitivity: controller.look_sensitivity,
+        zoom_speed: controller.zoom_speed,
+        pan_speed: controller.pan_speed,
+        clamp_y: controller.clamp_y.0,
+        angles: controller.angles.into(),
+    }
+}
+
+fn describe_light(light: &Light) -> LightDescriptor {
+    LightDescriptor {
+        kind: match light.kind {
+            LightKind::Directional => LightKindDescriptor::Directional,
+            LightKind::Spot { range, angle } => LightKindDescriptor::Spot {
+                range,
+                angle: angle.0,
+            },
+        },
+        color: light.color.into(),
+        intensity: light.intensity,
+        cast_shadows: light.cast_shadows,
+    }
+}
+
+fn describe_mesh_renderer(
+    renderer: &MeshRenderer,
+    resource_manager: &ResourceManager,
+) -> MeshRendererDescriptor {
+    let texture_path = |texture| {
+        let cache = &resource_manager.texture_cache;
+        cache.find(texture).and_then(|id| cache.path(id)).cloned()
+    };
+    let linear_texture_path = |texture| {
+        let cache = &resource_manager.linear_texture_cache;
+        cache.find(texture).and_then(|id| cache.path(id)).cloned()
+    };
+
+    let material = match renderer.material.properties() {
+        MaterialProperties::Lambert { albedo } => MaterialDescriptor::Lambert {
+            albedo: texture_path(albedo),
+        },
+        MaterialProperties::Pbr {
+            albedo,
+            normal,
+            metallic_roughness,
+            occlusion,
+            emissive,
+            factors,
+        } => MaterialDescriptor::Pbr {
+            albedo: texture_path(albedo),
+            normal: normal.as_ref().and_then(linear_texture_path),
+            metallic_roughness: metallic_roughness.as_ref().and_then(linear_texture_path),
+            occlusion: occlusion.as_ref().and_then(linear_texture_path),
+            emissive: emissive.as_ref().and_then(texture_path),
+            base_color: factors.base_color.into(),
+            metallic: factors.metallic,
+            roughness: factors.roughness,
+            occlusion_strength: factors.occlusion_strength,
+            normal_scale: factors.normal_scale,
+            emissive_factor: factors.emissive.into(),
+        },
+    };
+
+    let mesh_cache = &resource_manager.mesh_cache;
+    MeshRendererDescriptor {
+        mesh: mesh_cache
+            .find(&renderer.mesh)
+            .and_then(|id| mesh_cache.path(id))
+            .cloned(),
+        material,
+        alpha_mode: renderer.material.alpha_mode(),
+        cast_shadows: renderer.cast_shadows,
+        receive_shadows: renderer.receive_shadows,
+    }
+}
+
+/// Builds a rotation from euler angles in degrees.
+#[inline]
+pub fn rotation_from_degrees(angles: [f32; 3]) -> Quaternion<f32> {
+    Quaternion::from(Euler::new(Deg(angles[0]), Deg(angles[1]), Deg(angles[2])))
+}
+
+/// Euler angles of a rotation in degrees.
+#[inline]
+pub fn rotation_to_degrees(rotation: Quaternion<f32>) -> [f32; 3] {
+    let euler = Euler::from(rotation);
+    [
+        Deg::from(euler.x).0,
+        Deg::from(euler.y).0,
+        Deg::from(euler.z).0,
+    ]
+}
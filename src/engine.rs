@@ -1,10 +1,11 @@
-use super::config::CoreConfig;
+use super::config::{ConfigWatcher, CoreConfig};
 use super::resources::ResourceManager;
 use super::scenery::Scenery;
+use super::scenery_resources::RuntimeConfig;
 use super::scheduler::{self, ScheduleHandle};
 use super::systems::SystemSupervisor;
 use humantime::Duration;
-use log::info;
+use log::{info, warn};
 use std::process;
 use std::time::Instant;
 
@@ -12,10 +13,14 @@ pub struct Engine {
     pub config: CoreConfig,
     pub systems: SystemSupervisor,
     pub scenery: Box<Scenery>,
-    pub resource_manager: ResourceManager,
     pub service_scheduler_thread: Option<ScheduleHandle>,
+    /// Disabled in safe mode.
+    pub config_watcher: Option<ConfigWatcher>,
 }
 
+/// The service routine runs less often in power save mode.
+const POWER_SAVE_SERVICE_INTERVAL_FACTOR: u8 = 4;
+
 impl Engine {
     pub fn initialize() -> Box<Self> {
         super::panic_hook::install();
@@ -35,30 +40,47 @@ impl Engine {
         );
 
         let mut config = CoreConfig::load();
+        if config.application_config.safe_mode {
+            config.apply_safe_mode();
+        }
         let systems = SystemSupervisor::initialize(&mut config);
         let mut resource_manager = ResourceManager::with_capacity(
             config.application_config.default_resource_cache_capacity,
         );
 
         let disable_service_routine = config.application_config.disable_service_routine;
-        let service_routine_interval = config.application_config.service_routine_minute_interval;
+        let mut service_routine_interval =
+            config.application_config.service_routine_minute_interval;
+        if config.application_config.power_safe_mode {
+            service_routine_interval =
+                service_routine_interval.saturating_mul(POWER_SAVE_SERVICE_INTERVAL_FACTOR);
+        }
         let service_scheduler_thread =
             scheduler::launch_fixed_routine(disable_service_routine, service_routine_interval);
 
         info!("Initializing scenery...");
         let scenery_clock = Instant::now();
-        let scenery = Scenery::default_preset(&systems, &mut resource_manager);
+        let mut scenery = Scenery::default_preset(&systems, &mut resource_manager);
+        scenery.resources.insert(resource_manager);
+        scenery.resources.insert(RuntimeConfig::new(config.clone()));
         info!(
             "Scenery is initialized! Time: {}",
             Duration::from(scenery_clock.elapsed())
         );
 
+        let config_watcher = if config.application_config.safe_mode {
+            info!("Config hot reload is disabled in safe mode!");
+            None
+        } else {
+            Some(ConfigWatcher::default())
+        };
+
         let value = Box::new(Self {
             config,
             systems,
             scenery,
-            resource_manager,
             service_scheduler_thread,
+            config_watcher,
         });
 
         info!(
@@ -97,7 +119,63 @@ impl Engine {
     }
 
     fn tick(&mut self) -> bool {
-        self.systems.tick_all(&mut self.scenery)
+        let running = self.systems.tick_all(&mut self.scenery);
+        let files_changed = match &mut self.config_watcher {
+            Some(config_watcher) => config_watcher.poll(),
+            None => false,
+        };
+        if files_changed {
+            info!("Config files changed, reloading...");
+            let config = CoreConfig::load();
+            self.scenery
+                .resources
+                .get_mut::<RuntimeConfig>()
+                .unwrap()
+                .request(config, false);
+        }
+        self.apply_config_changes();
+        running
+    }
+
+    /// Applies a config requested by the settings panel or reloaded from the files.
+    fn apply_config_changes(&mut self) {
+        let (pending, persist, previous) = {
+            let mut runtime_config = self.scenery.resources.get_mut::<RuntimeConfig>().unwrap();
+            let previous = runtime_config.current.clone();
+            (
+                runtime_config.pending.take(),
+                runtime_config.persist,
+                previous,
+            )
+        };
+        let mut config = match pending {
+            Some(config) => config,
+            None => return,
+        };
+
+        info!("Applying config changes...");
+        // the systems resolve the config, like MSAA off with FXAA, only the requested values are saved:
+        let requested = config.clone();
+        self.systems.reconfigure_all(&mut config, &mut self.scenery);
+        // the safe mode settings must not replace the regular ones:
+        if persist && config.application_config.safe_mode {
+            warn!("Config changes are not saved in safe mode!");
+        } else if persist {
+            match requested.save_changes(&previous) {
+                Ok(()) => info!("Saved config changes!"),
+                Err(error) => warn!("Failed to save config: {}", error),
+            }
+        }
+        if let Some(config_watcher) = &mut self.config_watcher {
+            config_watcher.refresh();
+        }
+
+        self.scenery
+            .resources
+            .get_mut::<RuntimeConfig>()
+            .unwrap()
+            .current = config.clone();
+        self.config = config;
     }
 }
 
@@ -1,15 +1,94 @@
 use super::prelude::*;
-use super::texture::Texture;
+use super::texture::{LinearTexture, Texture};
 use crate::systems::graphics::GraphicsSystem;
+use cgmath::{Vector3, Vector4};
+use serde::{Deserialize, Serialize};
 use wgpu::BindGroup;
 
+/// Constant factors, multiplied with the corresponding pbr maps.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PbrFactors {
+    pub base_color: Vector4<f32>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub occlusion_strength: f32,
+    pub normal_scale: f32,
+    pub emissive: Vector3<f32>,
+}
+
+impl Default for PbrFactors {
+    fn default() -> Self {
+        Self {
+            base_color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            metallic: 1.0,
+            roughness: 1.0,
+            occlusion_strength: 1.0,
+            normal_scale: 1.0,
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AlphaMode {
+    Opaque,
+    /// Alpha tested (cutout), fragments below the cutoff are discarded.
+    Mask {
+        cutoff: f32,
+    },
+    /// Alpha blended, drawn after all opaque geometry sorted back to front.
+    Blend,
+}
+
+impl AlphaMode {
+    #[inline]
+    pub fn render_queue(&self) -> RenderQueue {
+        match self {
+            Self::Opaque => RenderQueue::Opaque,
+            Self::Mask { .. } => RenderQueue::AlphaTest,
+            Self::Blend => RenderQueue::Transparent,
+        }
+    }
+
+    #[inline]
+    pub fn cutoff(&self) -> f32 {
+        match self {
+            Self::Mask { cutoff } => *cutoff,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Render queues are drawn in this order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RenderQueue {
+    Opaque = 0,
+    AlphaTest = 1,
+    Transparent = 2,
+}
+
+#[derive(Clone)]
 pub enum MaterialProperties {
-    Lambert { albedo: Arc<Texture> },
+    Lambert {
+        albedo: Arc<Texture>,
+    },
+    /// Metallic-roughness workflow: roughness is read from the green, metallic from the blue channel.
+    Pbr {
+        albedo: Arc<Texture>,
+        normal: Option<Arc<LinearTexture>>,
+        metallic_roughness: Option<Arc<LinearTexture>>,
+        occlusion: Option<Arc<LinearTexture>>,
+        emissive: Option<Arc<Texture>>,
+        factors: PbrFactors,
+    },
 }
 
 pub struct Material {
     properties: MaterialProperties,
+    alpha_mode: AlphaMode,
     bind_group: wgpu::BindGroup,
+    /// Albedo of the shadow pipeline, for the alpha tested shadows.
+    shadow_bind_group: wgpu::BindGroup,
 }
 
 impl Material {
@@ -18,12 +97,31 @@ impl Material {
         &self.properties
     }
 
+    #[inline]
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    #[inline]
+    pub fn render_queue(&self) -> RenderQueue {
+        self.alpha_mode.render_queue()
+    }
+
     #[inline]
     pub fn bind_group(&self) -> &BindGroup {
         &self.bind_group
     }
 
-    pub fn load(system: &GraphicsSystem, properties: MaterialProperties) -> Arc<Self> {
+    #[inline]
+    pub fn shadow_bind_group(&self) -> &BindGroup {
+        &self.shadow_bind_group
+    }
+
+    pub fn load(
+        system: &GraphicsSystem,
+        properties: MaterialProperties,
+        alpha_mode: AlphaMode,
+    ) -> Arc<Self> {
         use wgpu::*;
 
         let bind_group = match &properties {
@@ -49,11 +147,94 @@ impl Material {
                         label: None,
                     })
             }
+            MaterialProperties::Pbr {
+                albedo,
+                normal,
+                metallic_roughness,
+                occlusion,
+                emissive,
+                ..
+            } => {
+                let pipeline = &system.pbr_pipeline;
+                let normal = normal
+                    .as_ref()
+                    .map_or(&pipeline.fallback_normal, |t| t.view());
+                let metallic_roughness = metallic_roughness
+                    .as_ref()
+                    .map_or(&pipeline.fallback_white, |t| t.view());
+                let occlusion = occlusion
+                    .as_ref()
+                    .map_or(&pipeline.fallback_white, |t| t.view());
+                let emissive = emissive
+                    .as_ref()
+                    .map_or(&pipeline.fallback_black, |t| t.view());
+                system
+                    .drivers
+                    .device
+                    .create_bind_group(&BindGroupDescriptor {
+                        layout: &pipeline.shader_pipeline.per_material_bind_group_layout,
+                        entries: &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource: BindingResource::TextureView(albedo.view()),
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: BindingResource::TextureView(normal),
+                            },
+                            BindGroupEntry {
+                                binding: 2,
+                                resource: BindingResource::TextureView(metallic_roughness),
+                            },
+                            BindGroupEntry {
+                                binding: 3,
+                                resource: BindingResource::TextureView(occlusion),
+                            },
+                            BindGroupEntry {
+                                binding: 4,
+                                resource: BindingResource::TextureView(emissive),
+                            },
+                            BindGroupEntry {
+                                binding: 5,
+                                resource: BindingResource::Sampler(albedo.sampler()),
+                            },
+                        ],
+                        label: None,
+                    })
+            }
+        };
+
+        let albedo = match &properties {
+            MaterialProperties::Lambert { albedo } | MaterialProperties::Pbr { albedo, .. } => {
+                albedo
+            }
         };
+        let shadow_bind_group = system
+            .drivers
+            .device
+            .create_bind_group(&BindGroupDescriptor {
+                layout: &system
+                    .shadow_pipeline
+                    .shader_pipeline
+                    .per_material_bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(albedo.view()),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(albedo.sampler()),
+                    },
+                ],
+                label: None,
+            });
 
         Arc::new(Self {
             properties,
+            alpha_mode,
             bind_group,
+            shadow_bind_group,
         })
     }
 }
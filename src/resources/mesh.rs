@@ -10,28 +10,33 @@ use std::time::Instant;
 pub struct Vertex {
     position: [f32; 4],
     tex_coords: [f32; 2],
+    normal: [f32; 3],
 }
 
 unsafe impl Pod for Vertex {}
 unsafe impl Zeroable for Vertex {}
 
 impl Vertex {
-    pub const fn new(position: [f32; 4], tex_coords: [f32; 2]) -> Self {
+    pub const fn new(position: [f32; 4], tex_coords: [f32; 2], normal: [f32; 3]) -> Self {
         Self {
             position,
             tex_coords,
+            normal,
         }
     }
 
-    pub const fn from_integers(pos: [i8; 3], tc: [i8; 2]) -> Vertex {
+    pub const fn from_integers(pos: [i8; 3], tc: [i8; 2], n: [i8; 3]) -> Vertex {
         Vertex {
             position: [pos[0] as f32, pos[1] as f32, pos[2] as f32, 1.0],
             tex_coords: [tc[0] as f32, tc[1] as f32],
+            normal: [n[0] as f32, n[1] as f32, n[2] as f32],
         }
     }
 }
 
 pub type Index = u16;
+/// Format of the index buffers, matching `Index`.
+pub const INDEX_FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
 
 pub struct Mesh {
     indices: Box<[Index]>,
@@ -82,6 +87,7 @@ impl Resource for Mesh {
             .map(|v: &TexturedVertex| Vertex {
                 position: [v.position[0], v.position[1], v.position[2], 1.0],
                 tex_coords: [v.texture[0], v.texture[1]],
+                normal: v.normal,
             })
             .collect();
 
@@ -133,35 +139,35 @@ impl Resource for Mesh {
 
 pub const CUBE_VERTICES: [Vertex; 24] = [
     // top (0, 0, 1)
-    Vertex::from_integers([-1, -1, 1], [0, 0]),
-    Vertex::from_integers([1, -1, 1], [1, 0]),
-    Vertex::from_integers([1, 1, 1], [1, 1]),
-    Vertex::from_integers([-1, 1, 1], [0, 1]),
+    Vertex::from_integers([-1, -1, 1], [0, 0], [0, 0, 1]),
+    Vertex::from_integers([1, -1, 1], [1, 0], [0, 0, 1]),
+    Vertex::from_integers([1, 1, 1], [1, 1], [0, 0, 1]),
+    Vertex::from_integers([-1, 1, 1], [0, 1], [0, 0, 1]),
     // bottom (0, 0, -1)
-    Vertex::from_integers([-1, 1, -1], [1, 0]),
-    Vertex::from_integers([1, 1, -1], [0, 0]),
-    Vertex::from_integers([1, -1, -1], [0, 1]),
-    Vertex::from_integers([-1, -1, -1], [1, 1]),
+    Vertex::from_integers([-1, 1, -1], [1, 0], [0, 0, -1]),
+    Vertex::from_integers([1, 1, -1], [0, 0], [0, 0, -1]),
+    Vertex::from_integers([1, -1, -1], [0, 1], [0, 0, -1]),
+    Vertex::from_integers([-1, -1, -1], [1, 1], [0, 0, -1]),
     // right (1, 0, 0)
-    Vertex::from_integers([1, -1, -1], [0, 0]),
-    Vertex::from_integers([1, 1, -1], [1, 0]),
-    Vertex::from_integers([1, 1, 1], [1, 1]),
-    Vertex::from_integers([1, -1, 1], [0, 1]),
+    Vertex::from_integers([1, -1, -1], [0, 0], [1, 0, 0]),
+    Vertex::from_integers([1, 1, -1], [1, 0], [1, 0, 0]),
+    Vertex::from_integers([1, 1, 1], [1, 1], [1, 0, 0]),
+    Vertex::from_integers([1, -1, 1], [0, 1], [1, 0, 0]),
     // left (-1, 0, 0)
-    Vertex::from_integers([-1, -1, 1], [1, 0]),
-    Vertex::from_integers([-1, 1, 1], [0, 0]),
-    Vertex::from_integers([-1, 1, -1], [0, 1]),
-    Vertex::from_integers([-1, -1, -1], [1, 1]),
+    Vertex::from_integers([-1, -1, 1], [1, 0], [-1, 0, 0]),
+    Vertex::from_integers([-1, 1, 1], [0, 0], [-1, 0, 0]),
+    Vertex::from_integers([-1, 1, -1], [0, 1], [-1, 0, 0]),
+    Vertex::from_integers([-1, -1, -1], [1, 1], [-1, 0, 0]),
     // front (0, 1, 0)
-    Vertex::from_integers([1, 1, -1], [1, 0]),
-    Vertex::from_integers([-1, 1, -1], [0, 0]),
-    Vertex::from_integers([-1, 1, 1], [0, 1]),
-    Vertex::from_integers([1, 1, 1], [1, 1]),
+    Vertex::from_integers([1, 1, -1], [1, 0], [0, 1, 0]),
+    Vertex::from_integers([-1, 1, -1], [0, 0], [0, 1, 0]),
+    Vertex::from_integers([-1, 1, 1], [0, 1], [0, 1, 0]),
+    Vertex::from_integers([1, 1, 1], [1, 1], [0, 1, 0]),
     // back (0, -1, 0)
-    Vertex::from_integers([1, -1, 1], [0, 0]),
-    Vertex::from_integers([-1, -1, 1], [1, 0]),
-    Vertex::from_integers([-1, -1, -1], [1, 1]),
-    Vertex::from_integers([1, -1, -1], [0, 1]),
+    Vertex::from_integers([1, -1, 1], [0, 0], [0, -1, 0]),
+    Vertex::from_integers([-1, -1, 1], [1, 0], [0, -1, 0]),
+    Vertex::from_integers([-1, -1, -1], [1, 1], [0, -1, 0]),
+    Vertex::from_integers([1, -1, -1], [0, 1], [0, -1, 0]),
 ];
 
 pub const CUBE_INDICES: [u16; 36] = [
@@ -10,7 +10,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
-use texture::Texture;
+use texture::{LinearTexture, Texture};
 
 pub type ResourceId = u64;
 
@@ -20,42 +20,66 @@ pub trait Resource {
     fn load(_system: &Self::ImportSystem, raw_data: Vec<u8>) -> Self;
 }
 
-pub struct ResourceCache<T: Resource>(HashMap<ResourceId, Arc<T>>);
+pub struct ResourceCache<T: Resource> {
+    table: HashMap<ResourceId, Arc<T>>,
+    paths: HashMap<ResourceId, PathBuf>,
+}
 
 impl<T: Resource> ResourceCache<T> {
     #[inline]
     pub fn with_capacity(cap: usize) -> Self {
-        Self(HashMap::with_capacity(cap))
+        Self {
+            table: HashMap::with_capacity(cap),
+            paths: HashMap::with_capacity(cap),
+        }
     }
 
     #[inline]
     pub fn new(table: HashMap<ResourceId, Arc<T>>) -> Self {
-        Self(table)
+        Self {
+            table,
+            paths: HashMap::new(),
+        }
     }
 
     #[inline]
     pub fn table(&self) -> &HashMap<ResourceId, Arc<T>> {
-        &self.0
+        &self.table
     }
 
     #[inline]
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.table.clear();
+        self.paths.clear();
     }
 
     #[inline]
     pub fn contains(&self, other: ResourceId) -> bool {
-        self.0.contains_key(&other)
+        self.table.contains_key(&other)
     }
 
     #[inline]
     pub fn get(&self, other: ResourceId) -> Option<&Arc<T>> {
-        self.0.get(&other)
+        self.table.get(&other)
     }
 
     #[inline]
     pub fn insert(&mut self, k: ResourceId, v: Arc<T>) {
-        self.0.insert(k, v);
+        self.table.insert(k, v);
+    }
+
+    /// Returns the file the resource was imported from, if it was imported.
+    #[inline]
+    pub fn path(&self, id: ResourceId) -> Option<&PathBuf> {
+        self.paths.get(&id)
+    }
+
+    /// Looks up the id of an already cached resource.
+    pub fn find(&self, resource: &Arc<T>) -> Option<ResourceId> {
+        self.table
+            .iter()
+            .find(|(_, cached)| Arc::ptr_eq(cached, resource))
+            .map(|(id, _)| *id)
     }
 
     pub fn import(&mut self, system: &T::ImportSystem, path: PathBuf) -> Arc<T> {
@@ -76,6 +100,7 @@ impl<T: Resource> ResourceCache<T> {
             });
             let ptr = Arc::new(T::load(system, bytes));
             self.insert(hash, ptr.clone());
+            self.paths.insert(hash, path);
             ptr
         }
     }
@@ -83,6 +108,7 @@ impl<T: Resource> ResourceCache<T> {
 
 pub struct ResourceManager {
     pub texture_cache: ResourceCache<Texture>,
+    pub linear_texture_cache: ResourceCache<LinearTexture>,
     pub mesh_cache: ResourceCache<Mesh>,
 }
 
@@ -90,6 +116,7 @@ impl ResourceManager {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             texture_cache: ResourceCache::with_capacity(capacity),
+            linear_texture_cache: ResourceCache::with_capacity(capacity),
             mesh_cache: ResourceCache::with_capacity(capacity),
         }
     }
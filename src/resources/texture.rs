@@ -11,7 +11,7 @@ pub struct Texture {
     extent: wgpu::Extent3d,
     texture: wgpu::Texture,
     view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
+    sampler: Arc<wgpu::Sampler>,
 }
 
 impl Texture {
@@ -55,6 +55,40 @@ impl Resource for Texture {
     type ImportSystem = graphics::GraphicsSystem;
 
     fn load(system: &Self::ImportSystem, raw_data: Vec<u8>) -> Self {
+        Self::decode(system, raw_data, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+}
+
+/// Texture holding non-color data (normals, roughness...), which must not be srgb decoded.
+pub struct LinearTexture(Texture);
+
+impl std::ops::Deref for LinearTexture {
+    type Target = Texture;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Resource for LinearTexture {
+    type ImportSystem = graphics::GraphicsSystem;
+
+    fn load(system: &Self::ImportSystem, raw_data: Vec<u8>) -> Self {
+        Self(Texture::decode(
+            system,
+            raw_data,
+            wgpu::TextureFormat::Rgba8Unorm,
+        ))
+    }
+}
+
+impl Texture {
+    fn decode(
+        system: &graphics::GraphicsSystem,
+        raw_data: Vec<u8>,
+        format: wgpu::TextureFormat,
+    ) -> Self {
         use image::io::Reader as ImageReader;
         use wgpu::*;
 
@@ -81,8 +115,6 @@ impl Resource for Texture {
             depth: 1,
         };
 
-        let format = TextureFormat::Rgba8UnormSrgb;
-
         let texture = system.drivers.device.create_texture(&TextureDescriptor {
             label: None,
             size: extent,
@@ -119,16 +151,19 @@ impl Resource for Texture {
 
         let view = texture.create_view(&TextureViewDescriptor::default());
 
-        let sampler = system.drivers.device.create_sampler(&SamplerDescriptor {
-            address_mode_u: AddressMode::ClampToEdge,
-            address_mode_v: AddressMode::ClampToEdge,
-            address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: FilterMode::Linear,
-            min_filter: FilterMode::Linear,
-            mipmap_filter: FilterMode::Linear,
-            anisotropy_clamp: NonZeroU8::new(16),
-            ..std::default::Default::default()
-        });
+        let sampler = system.drivers.pipeline_cache.sampler(
+            &system.drivers.device,
+            &SamplerDescriptor {
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Linear,
+                anisotropy_clamp: NonZeroU8::new(16),
+                ..std::default::Default::default()
+            },
+        );
 
         Self {
             width,
@@ -1,7 +1,9 @@
-use super::components::{Camera, MeshRenderer, Transform};
+use super::components::{
+    Camera, FlyController, Light, LightKind, MeshRenderer, Projection, Transform,
+};
 use super::systems::SystemSupervisor;
 use crate::resources::{
-    material::{Material, MaterialProperties},
+    material::{AlphaMode, Material, MaterialProperties, PbrFactors},
     ResourceManager,
 };
 use cgmath::*;
@@ -34,9 +36,12 @@ impl Scenery {
                 scale: Vector3::from_value(1.0),
             },
             Camera {
-                fov: Deg(75.0),
+                projection: Projection::Perspective { fov: Deg(75.0) },
                 near_clip: 0.1,
                 far_clip: 100.0,
+                ..Default::default()
+            },
+            FlyController {
                 clamp_y: Deg(60.0),
                 ..Default::default()
             },
@@ -44,6 +49,48 @@ impl Scenery {
 
         scenery.world.push(camera);
 
+        let sun = (
+            Transform {
+                position: Vector3::zero(),
+                rotation: Quaternion::from(Euler::new(Deg(-50.0), Deg(30.0), Deg(0.0))),
+                scale: Vector3::from_value(1.0),
+            },
+            Light {
+                kind: LightKind::Directional,
+                color: Vector3::new(1.0, 0.95, 0.85),
+                intensity: 1.0,
+                cast_shadows: true,
+            },
+        );
+
+        scenery.world.push(sun);
+
+        let ground = (
+            Transform {
+                position: Vector3::new(1.5, -0.5, 1.5),
+                rotation: Quaternion::zero(),
+                scale: Vector3::new(4.0, 0.05, 4.0),
+            },
+            MeshRenderer {
+                mesh: resource_manager
+                    .mesh_cache
+                    .import(&systems.graphics, PathBuf::from("db/meshes/cube.obj")),
+                material: Material::load(
+                    &systems.graphics,
+                    MaterialProperties::Lambert {
+                        albedo: resource_manager
+                            .texture_cache
+                            .import(&systems.graphics, PathBuf::from("db/textures/wood.png")),
+                    },
+                    AlphaMode::Opaque,
+                ),
+                cast_shadows: false,
+                receive_shadows: true,
+            },
+        );
+
+        scenery.world.push(ground);
+
         let mut cube = (
             Transform {
                 position: Vector3::new(0.0, 0.0, 0.0),
@@ -61,7 +108,10 @@ impl Scenery {
                             .texture_cache
                             .import(&systems.graphics, PathBuf::from("db/textures/grid.png")),
                     },
+                    AlphaMode::Opaque,
                 ),
+                cast_shadows: true,
+                receive_shadows: true,
             },
         );
 
@@ -74,6 +124,103 @@ impl Scenery {
             }
         }
 
+        let metal_cube = (
+            Transform {
+                position: Vector3::new(1.5, 0.25, -1.0),
+                rotation: Quaternion::zero(),
+                scale: Vector3::from_value(0.5),
+            },
+            MeshRenderer {
+                mesh: resource_manager
+                    .mesh_cache
+                    .import(&systems.graphics, PathBuf::from("db/meshes/cube.obj")),
+                material: Material::load(
+                    &systems.graphics,
+                    MaterialProperties::Pbr {
+                        albedo: resource_manager
+                            .texture_cache
+                            .import(&systems.graphics, PathBuf::from("db/textures/metal.png")),
+                        normal: None,
+                        metallic_roughness: None,
+                        occlusion: None,
+                        emissive: None,
+                        factors: PbrFactors {
+                            metallic: 1.0,
+                            roughness: 0.35,
+                            ..Default::default()
+                        },
+                    },
+                    AlphaMode::Opaque,
+                ),
+                cast_shadows: true,
+                receive_shadows: true,
+            },
+        );
+
+        scenery.world.push(metal_cube);
+
+        let tree = (
+            Transform {
+                position: Vector3::new(-1.5, -0.45, 1.5),
+                rotation: Quaternion::zero(),
+                scale: Vector3::from_value(0.5),
+            },
+            MeshRenderer {
+                mesh: resource_manager
+                    .mesh_cache
+                    .import(&systems.graphics, PathBuf::from("db/meshes/tree.obj")),
+                material: Material::load(
+                    &systems.graphics,
+                    MaterialProperties::Lambert {
+                        albedo: resource_manager
+                            .texture_cache
+                            .import(&systems.graphics, PathBuf::from("db/textures/tree.png")),
+                    },
+                    AlphaMode::Mask { cutoff: 0.5 },
+                ),
+                cast_shadows: true,
+                receive_shadows: true,
+            },
+        );
+
+        scenery.world.push(tree);
+
+        let glass_cube = (
+            Transform {
+                position: Vector3::new(3.0, 0.25, -1.0),
+                rotation: Quaternion::zero(),
+                scale: Vector3::from_value(0.5),
+            },
+            MeshRenderer {
+                mesh: resource_manager
+                    .mesh_cache
+                    .import(&systems.graphics, PathBuf::from("db/meshes/cube.obj")),
+                material: Material::load(
+                    &systems.graphics,
+                    MaterialProperties::Pbr {
+                        albedo: resource_manager
+                            .texture_cache
+                            .import(&systems.graphics, PathBuf::from("db/textures/grid.png")),
+                        normal: None,
+                        metallic_roughness: None,
+                        occlusion: None,
+                        emissive: None,
+                        factors: PbrFactors {
+                            base_color: Vector4::new(0.6, 0.8, 1.0, 0.35),
+                            metallic: 0.0,
+                            roughness: 0.1,
+                            ..Default::default()
+                        },
+                    },
+                    AlphaMode::Blend,
+                ),
+                cast_shadows: false,
+                receive_shadows: true,
+            },
+        );
+
+        scenery.world.push(glass_cube);
+
         scenery
     }
 }
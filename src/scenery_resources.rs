@@ -1,5 +1,11 @@
-pub use crate::core::platform::prelude::{Action, Key, Modifiers, MouseButton};
+use crate::config::CoreConfig;
+pub use crate::core::platform::gamepad::GamepadState;
+pub use crate::core::platform::input_map::InputMap;
+pub use crate::core::platform::prelude::{
+    Action, CursorMode, Key, Modifiers, MouseButton, WindowEvent,
+};
 use crate::core::platform::prelude::{KEY_COUNT, MOUSE_BUTTON_COUNT};
+use std::path::PathBuf;
 
 #[derive(Default, Copy, Clone, Debug)]
 pub struct CursorPos(pub f32, pub f32);
@@ -22,6 +28,25 @@ impl KeyInputStateCollection {
     pub fn is_key_pressed(&self, key: Key) -> bool {
         self.0[key as usize]
     }
+
+    /// Indices of the held keys, used by the input recorder.
+    #[inline]
+    pub fn pressed_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, pressed)| if *pressed { Some(index) } else { None })
+    }
+
+    pub fn from_pressed_indices(indices: &[u16]) -> Self {
+        let mut this = Self::default();
+        for &index in indices {
+            if let Some(pressed) = this.0.get_mut(index as usize) {
+                *pressed = true;
+            }
+        }
+        this
+    }
 }
 
 impl std::default::Default for KeyInputStateCollection {
@@ -47,6 +72,25 @@ impl MouseInputStateCollection {
     pub fn is_key_pressed(&self, key: MouseButton) -> bool {
         self.0[key as usize]
     }
+
+    /// Indices of the held buttons, used by the input recorder.
+    #[inline]
+    pub fn pressed_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, pressed)| if *pressed { Some(index) } else { None })
+    }
+
+    pub fn from_pressed_indices(indices: &[u8]) -> Self {
+        let mut this = Self::default();
+        for &index in indices {
+            if let Some(pressed) = this.0.get_mut(index as usize) {
+                *pressed = true;
+            }
+        }
+        this
+    }
 }
 
 impl std::default::Default for MouseInputStateCollection {
@@ -54,3 +98,121 @@ impl std::default::Default for MouseInputStateCollection {
         Self([false; MOUSE_BUTTON_COUNT])
     }
 }
+
+/// Window events polled this frame, in the order glfw delivered them.
+#[derive(Default)]
+pub struct WindowEventQueue(pub Vec<WindowEvent>);
+
+impl WindowEventQueue {
+    #[inline]
+    pub fn events(&self) -> &[WindowEvent] {
+        &self.0
+    }
+
+    #[inline]
+    pub fn was_key_pressed(&self, key: Key) -> bool {
+        self.0
+            .iter()
+            .any(|event| matches!(event, WindowEvent::Key(k, _, Action::Press, _) if *k == key))
+    }
+}
+
+#[derive(Default, Copy, Clone, Debug)]
+pub struct FrameTime {
+    /// Seconds since the previous frame.
+    pub delta: f32,
+    /// Seconds since the simulation started.
+    pub elapsed: f64,
+    pub frame: u64,
+}
+
+/// Devices the gui captured last frame, their input is hidden from the input map
+/// except for the keys of `input_map::UNCAPTURED_ACTIONS`.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct InputCapture {
+    pub keyboard: bool,
+    pub mouse: bool,
+}
+
+/// Window state tracked from the focus, iconify, cursor enter and content scale events.
+#[derive(Copy, Clone, Debug)]
+pub struct WindowState {
+    pub focused: bool,
+    pub iconified: bool,
+    /// Whether the cursor is inside the client area.
+    pub cursor_inside: bool,
+    pub content_scale: (f32, f32),
+    /// Size of the client area in screen coordinates.
+    pub size: (i32, i32),
+    pub framebuffer_size: (i32, i32),
+}
+
+impl WindowState {
+    /// Whether nothing rendered would be seen by the user.
+    #[inline]
+    pub fn is_inactive(&self) -> bool {
+        !self.focused || self.iconified
+    }
+}
+
+impl std::default::Default for WindowState {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            iconified: false,
+            cursor_inside: false,
+            content_scale: (1.0, 1.0),
+            size: (0, 0),
+            framebuffer_size: (0, 0),
+        }
+    }
+}
+
+/// The config the systems currently run with.
+/// A requested config is applied by the engine once the frame is done.
+#[derive(Clone)]
+pub struct RuntimeConfig {
+    pub current: CoreConfig,
+    pub pending: Option<CoreConfig>,
+    /// Whether the pending config is written to the project config directory once applied.
+    pub persist: bool,
+}
+
+impl RuntimeConfig {
+    pub fn new(current: CoreConfig) -> Self {
+        Self {
+            current,
+            pending: None,
+            persist: false,
+        }
+    }
+
+    #[inline]
+    pub fn request(&mut self, config: CoreConfig, persist: bool) {
+        self.pending = Some(config);
+        self.persist = persist;
+    }
+}
+
+/// Text typed this frame, with keyboard layout and dead keys applied.
+#[derive(Default, Clone, Debug)]
+pub struct TextInput(pub String);
+
+/// Paths dropped onto the window this frame.
+#[derive(Default, Clone, Debug)]
+pub struct DroppedFiles(pub Vec<PathBuf>);
+
+/// Cursor mode requested by the simulation, applied by the platform system each frame.
+/// `CursorMode::Disabled` hides and captures the cursor for unbounded mouse look.
+#[derive(Copy, Clone, Debug)]
+pub struct CursorControl {
+    pub mode: CursorMode,
+}
+
+impl std::default::Default for CursorControl {
+    fn default() -> Self {
+        Self {
+            mode: CursorMode::Normal,
+        }
+    }
+}
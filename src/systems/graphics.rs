@@ -1,33 +1,73 @@
 use super::prelude::*;
 use crate::components::{Camera, MeshRenderer, Transform};
 use crate::core::graphics::{
-    camera, drivers::Drivers, gui::Renderer as ImGuiRenderer,
-    gui::RendererConfig as ImGuiRendererConfig, pipeline::Pipeline, pipelines::lambert,
+    boot::{depth_clear_value, DEPTH_FORMAT, HDR_FORMAT},
+    camera,
+    drivers::Drivers,
+    environment::Environment,
+    gui::Renderer as ImGuiRenderer,
+    gui::RendererConfig as ImGuiRendererConfig,
+    lighting::{Lighting, MAX_SHADOW_LAYERS},
+    pass::Pass,
+    pipeline::{KeywordSet, Pipeline, ALPHA_TEST_KEYWORD},
+    pipelines::{self, lambert, pbr, shadow},
+    post_processing::{PostProcessing, PostProcessingContext},
+    render_graph::{
+        RenderGraph, TextureHandle, TextureSize, TransientTextureDescriptor, TransientTexturePool,
+    },
+    shader_compiler::ShaderWatcher,
 };
-use crate::core::platform::prelude::WindowHandle;
-use crate::scenery_resources::{KeyInputStateCollection, MouseInputStateCollection};
-use cgmath::{Matrix4, SquareMatrix};
-use legion::IntoQuery;
-use log::warn;
-use wgpu::ShaderStage;
+use crate::core::platform::{gui as imgui_platform, prelude::WindowHandle};
+use crate::editor::{inspector, DebugUi};
+use crate::resources::material::{AlphaMode, Material, MaterialProperties, RenderQueue};
+use crate::scenery_resources::{
+    CursorControl, CursorMode, FrameTime, InputCapture, InputMap, RuntimeConfig, WindowEventQueue,
+    WindowState,
+};
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector3, Vector4, Zero};
+use imgui::DrawData;
+use legion::{IntoQuery, World};
+use log::{error, info, warn};
+use smallvec::SmallVec;
+use std::cmp::Ordering;
+use wgpu::{Color, Device, LoadOp, Queue, ShaderStage};
 
 pub struct GraphicsSystem {
     pub drivers: Drivers,
     pub lambert_pipeline: lambert::LambertPipeline,
+    pub pbr_pipeline: pbr::PbrPipeline,
+    pub shadow_pipeline: shadow::ShadowPipeline,
+    pub lighting: Lighting,
+    pub environment: Environment,
+    pub post_processing: PostProcessing,
     pub imgui: imgui::Context,
     pub imgui_renderer: ImGuiRenderer,
+    pub debug_ui: DebugUi,
+    pub transient_textures: TransientTexturePool,
+    /// Skips rendering while the window is unfocused or minimized.
+    pub power_save: bool,
+    /// Recompiles the pipelines on edited shader sources, disabled in safe mode.
+    pub shader_watcher: Option<ShaderWatcher>,
 }
 
 impl SubSystem for GraphicsSystem {
     type Args = WindowHandle;
 
     fn initialize(cfg: &mut CoreConfig, window: &Self::Args) -> Self {
+        resolve_anti_aliasing(cfg);
+
         let mut drivers = Drivers::initialize(window, cfg);
         let lambert_pipeline = lambert::LambertPipeline::create(&mut drivers, cfg);
+        let pbr_pipeline = pbr::PbrPipeline::create(&mut drivers, cfg);
+        let shadow_pipeline = shadow::ShadowPipeline::create(&mut drivers, cfg);
+        let lighting = Lighting::new(&drivers, &cfg.graphics_config.shadows);
+        let environment = Environment::new(&mut drivers, cfg);
+        let post_processing = PostProcessing::new(&mut drivers, cfg);
 
         let mut imgui = imgui::Context::create();
 
         imgui.set_ini_filename(None);
+        imgui_platform::configure(&mut imgui, window);
 
         imgui
             .fonts()
@@ -40,112 +80,564 @@ impl SubSystem for GraphicsSystem {
                 }),
             }]);
 
-        let io = imgui.io_mut();
-        io.display_size[0] = cfg.display_config.resolution.0 as f32;
-        io.display_size[1] = cfg.display_config.resolution.1 as f32;
-
         let imgui_renderer_config = ImGuiRendererConfig {
             texture_format: drivers.swap_chain_format,
-            sample_count: drivers.msaa_samples as u32,
+            sample_count: 1,
             ..Default::default()
         };
         let imgui_renderer = ImGuiRenderer::new(
             &mut imgui,
             &drivers.device,
             &drivers.queue,
+            &drivers.pipeline_cache,
             imgui_renderer_config,
         );
 
         Self {
             drivers,
             lambert_pipeline,
+            pbr_pipeline,
+            shadow_pipeline,
+            lighting,
+            environment,
+            post_processing,
             imgui,
             imgui_renderer,
+            debug_ui: DebugUi::new(cfg),
+            transient_textures: TransientTexturePool::default(),
+            power_save: cfg.application_config.power_safe_mode,
+            shader_watcher: if cfg.application_config.safe_mode {
+                None
+            } else {
+                Some(ShaderWatcher::default())
+            },
         }
     }
 
     fn tick(&mut self, scenery: &mut Scenery) -> bool {
+        if self.power_save
+            && scenery
+                .resources
+                .get::<WindowState>()
+                .unwrap()
+                .is_inactive()
+        {
+            return true;
+        }
+
+        let shaders_changed = match &mut self.shader_watcher {
+            Some(watcher) => watcher.poll(),
+            None => false,
+        };
+        if shaders_changed {
+            let mut cfg = scenery
+                .resources
+                .get::<RuntimeConfig>()
+                .unwrap()
+                .current
+                .clone();
+            self.reload_shaders(&mut cfg);
+        }
+
         let mut flag = true;
+
+        // feed platform input into the gui:
+        {
+            let events = scenery.resources.get::<WindowEventQueue>().unwrap();
+            let frame_time = scenery.resources.get::<FrameTime>().unwrap();
+            let input = scenery.resources.get::<InputMap>().unwrap();
+            let io = self.imgui.io_mut();
+            for event in events.events() {
+                imgui_platform::handle_event(io, event);
+            }
+            // a captured cursor belongs to the camera, not the gui:
+            if scenery.resources.get::<CursorControl>().unwrap().mode == CursorMode::Disabled {
+                io.mouse_pos = [-f32::MAX, -f32::MAX];
+            }
+            imgui_platform::prepare_frame(io, frame_time.delta);
+            self.debug_ui.update(&input, &frame_time);
+        }
+
+        {
+            let input = scenery.resources.get::<InputMap>().unwrap();
+            let delta = scenery.resources.get::<FrameTime>().unwrap().delta;
+            camera::capture_cursor(
+                &input,
+                &mut scenery.resources.get_mut::<CursorControl>().unwrap(),
+            );
+            camera::update_controllers(&mut scenery.world, &input, delta);
+        }
+
+        let camera = <(&Transform, &Camera)>::query().iter(&scenery.world).next();
+        let mut camera_state = None;
+        let view_proj_matrix = if let Some((transform, camera)) = camera {
+            camera_state = Some((transform.clone(), camera.clone()));
+            camera::view_projection_matrix(
+                self.drivers.aspect_ratio(),
+                transform,
+                camera,
+                self.drivers.depth_mode,
+            )
+        } else {
+            warn!("No camera found!");
+            flag = false;
+            Matrix4::identity()
+        };
+
+        self.lighting.update(
+            &self.drivers.queue,
+            &scenery.world,
+            camera_state.as_ref(),
+            self.drivers.aspect_ratio(),
+        );
+
+        self.request_pipeline_variants(&scenery.world);
+
+        let ui = self.imgui.frame();
+        self.debug_ui.draw(
+            &ui,
+            scenery,
+            self.transient_textures.stats(),
+            &self.drivers.pipeline_cache.stats(),
+        );
+        let draw_data = ui.render();
+
         let mut frame = self.drivers.begin_frame();
+        let mut graph = RenderGraph::<FrameContext>::new();
+        let backbuffer = graph.backbuffer();
+        let samples = self.drivers.msaa_samples as u32;
+
+        // draw shadow maps:
+        let mut shadow_layers = SmallVec::<[TextureHandle; MAX_SHADOW_LAYERS]>::new();
+        for caster in self.lighting.casters.iter() {
+            let layer =
+                graph.import_texture("shadow layer", &self.lighting.layer_views[caster.layer]);
+            let light_view_proj_matrix = caster.view_proj_matrix;
+            graph
+                .add_pass("shadow")
+                .depth(layer, LoadOp::Clear(1.0))
+                .execute(move |pass, ctx| {
+                    // transparent materials cast no shadows, alpha tested ones discard by their albedo:
+                    let mut render_query = <(&Transform, &MeshRenderer)>::query();
+                    for &queue in &[RenderQueue::Opaque, RenderQueue::AlphaTest] {
+                        let pipeline = ctx.shadow_pipeline;
+                        pass.set_shader_pipeline(if queue == RenderQueue::AlphaTest {
+                            pipeline
+                                .variants
+                                .get(alpha_test_keywords::<shadow::ShadowPipeline>())
+                                .unwrap_or(&pipeline.shader_pipeline)
+                        } else {
+                            &pipeline.shader_pipeline
+                        });
+                        for (transform, renderer) in render_query.iter(ctx.world) {
+                            if !renderer.cast_shadows || renderer.material.render_queue() != queue {
+                                continue;
+                            }
+                            draw_shadow_caster(pass, transform, renderer, light_view_proj_matrix);
+                        }
+                    }
+                });
+            shadow_layers.push(layer);
+        }
+
+        // draw 3d scene:
+        let hdr = graph.create_texture(
+            "scene hdr",
+            TransientTextureDescriptor {
+                size: TextureSize::Relative(1),
+                format: HDR_FORMAT,
+                sample_count: 1,
+            },
+        );
         {
-            let camera = <(&mut Transform, &mut Camera)>::query()
-                .iter_mut(&mut scenery.world)
-                .next();
-            let view_proj_matrix = if let Some(camera) = camera {
-                let cursor_pos = *scenery.resources.get_mut_or_default();
-                let key_queue = scenery.resources.get::<KeyInputStateCollection>().unwrap();
-                let mouse_queue = scenery
-                    .resources
-                    .get::<MouseInputStateCollection>()
-                    .unwrap();
-                camera::compute_camera(
-                    self.drivers.aspect_ratio(),
-                    camera,
-                    cursor_pos,
-                    &*key_queue,
-                    &*mouse_queue,
-                )
+            let depth = graph.create_texture(
+                "scene depth",
+                TransientTextureDescriptor {
+                    size: TextureSize::Relative(1),
+                    format: DEPTH_FORMAT,
+                    sample_count: samples,
+                },
+            );
+            let clear = LoadOp::Clear(Color::WHITE);
+            let pass = if self.drivers.msaa_samples == MsaaMode::Off {
+                graph.add_pass("scene").color(hdr, clear)
             } else {
-                warn!("No camera found!");
-                flag = false;
-                Matrix4::identity()
+                let color = graph.create_texture(
+                    "scene color",
+                    TransientTextureDescriptor {
+                        size: TextureSize::Relative(1),
+                        format: HDR_FORMAT,
+                        sample_count: samples,
+                    },
+                );
+                graph.add_pass("scene").color_resolved(color, hdr, clear)
             };
-
-            // draw 3d scene:
-            {
-                let mut pass = frame.create_pass(true);
-                pass.set_pipeline(&self.lambert_pipeline);
-
+            let pass = shadow_layers
+                .iter()
+                .fold(pass, |pass, &layer| pass.read(layer));
+            let depth_clear = LoadOp::Clear(depth_clear_value(self.drivers.depth_mode));
+            pass.depth(depth, depth_clear).execute(|pass, ctx| {
                 let mut render_query = <(&Transform, &MeshRenderer)>::query();
-                render_query.for_each(&scenery.world, |(transform, renderer)| {
-                    let world_matrix = transform.calculate_matrix();
-                    let push_constant_data = lambert::PushConstantData {
-                        world_matrix,
-                        view_proj_matrix,
-                    };
-                    pass.set_push_constans(
-                        ShaderStage::VERTEX,
-                        0,
-                        bytemuck::bytes_of(&push_constant_data),
-                    );
-                    pass.set_bind_group(0, renderer.material.bind_group());
-                    pass.draw_indexed(&renderer.mesh);
-                });
-            }
+                let mut transparent = Vec::new();
 
-            // draw gui:
-            {
-                let mut pass = frame
-                    .encoder
-                    .begin_render_pass(&wgpu::RenderPassDescriptor {
-                        label: None,
-                        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                            attachment: &frame.view.view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Load,
-                                store: true,
-                            },
-                        }],
-                        depth_stencil_attachment: None,
-                    });
-                let ui = self.imgui.frame();
-                {
-                    let mut opened = true;
-                    ui.show_demo_window(&mut opened);
+                // opaque and alpha tested queues, grouped by pipeline:
+                for &queue in &[RenderQueue::Opaque, RenderQueue::AlphaTest] {
+                    let alpha_test = queue == RenderQueue::AlphaTest;
+                    for &pbr in &[false, true] {
+                        set_surface_pipeline(pass, ctx, pbr, alpha_test, false);
+                        for (transform, renderer) in render_query.iter(ctx.world) {
+                            if renderer.material.render_queue() == queue
+                                && is_pbr(&renderer.material) == pbr
+                            {
+                                draw_surface(pass, ctx, transform, renderer);
+                            }
+                        }
+                    }
                 }
-                self.imgui_renderer
-                    .render(
-                        ui.render(),
-                        &self.drivers.queue,
-                        &self.drivers.device,
-                        &mut pass,
-                    )
-                    .expect("GUI rendering failed");
-            }
+
+                // transparent queue, back to front:
+                let camera_position = ctx.camera_position;
+                for (transform, renderer) in render_query.iter(ctx.world) {
+                    if renderer.material.render_queue() == RenderQueue::Transparent {
+                        let distance = (transform.position - camera_position).magnitude2();
+                        transparent.push((distance, transform, renderer));
+                    }
+                }
+                transparent.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+                let mut bound = None;
+                for (_, transform, renderer) in transparent {
+                    let pbr = is_pbr(&renderer.material);
+                    if bound != Some(pbr) {
+                        set_surface_pipeline(pass, ctx, pbr, false, true);
+                        bound = Some(pbr);
+                    }
+                    draw_surface(pass, ctx, transform, renderer);
+                }
+            });
         }
 
+        // post processing:
+        self.post_processing.build(&mut graph, hdr, backbuffer);
+
+        // draw gui:
+        graph
+            .add_pass("gui")
+            .color(backbuffer, LoadOp::Load)
+            .execute(|pass, ctx| {
+                ctx.imgui_renderer
+                    .render(ctx.draw_data, ctx.queue, ctx.device, &mut pass.0)
+                    .expect("GUI rendering failed");
+            });
+
+        let mut context = FrameContext {
+            world: &scenery.world,
+            lambert_pipeline: &self.lambert_pipeline,
+            pbr_pipeline: &self.pbr_pipeline,
+            shadow_pipeline: &self.shadow_pipeline,
+            lighting: &self.lighting,
+            environment: &self.environment,
+            post_processing: &self.post_processing,
+            view_proj_matrix,
+            camera_position: camera_state
+                .as_ref()
+                .map_or(Vector3::zero(), |(transform, _)| transform.position),
+            alpha_to_coverage: self.drivers.msaa_samples != MsaaMode::Off,
+            imgui_renderer: &mut self.imgui_renderer,
+            draw_data,
+            device: &self.drivers.device,
+            queue: &self.drivers.queue,
+        };
+        graph.execute(
+            &mut frame,
+            &self.drivers.device,
+            &mut self.transient_textures,
+            &mut context,
+        );
+
         frame.end();
+
+        let io = self.imgui.io();
+        *scenery.resources.get_mut::<InputCapture>().unwrap() = InputCapture {
+            keyboard: io.want_capture_keyboard,
+            mouse: io.want_capture_mouse,
+        };
+
+        let edits = self.debug_ui.take_edits();
+        inspector::apply_commands(edits, self, scenery);
         flag
     }
+
+    /// Shadow, environment and post processing settings are only read at startup.
+    fn reconfigure(&mut self, cfg: &mut CoreConfig, scenery: &mut Scenery) {
+        resolve_anti_aliasing(cfg);
+        self.power_save = cfg.application_config.power_safe_mode;
+
+        let window_state = *scenery.resources.get::<WindowState>().unwrap();
+        let (width, height) = window_state.framebuffer_size;
+        self.drivers
+            .reconfigure_swap_chain(width as _, height as _, cfg.display_config.vsync);
+        imgui_platform::set_display_metrics(
+            self.imgui.io_mut(),
+            window_state.size,
+            window_state.framebuffer_size,
+        );
+
+        let graphics = &cfg.graphics_config;
+        if graphics.msaa_mode != self.drivers.msaa_samples
+            || graphics.depth_mode != self.drivers.depth_mode
+        {
+            info!(
+                "Recreating surface pipelines, MSAA samples: {:?}, depth mode: {:?}",
+                graphics.msaa_mode, graphics.depth_mode
+            );
+            self.drivers.msaa_samples = graphics.msaa_mode;
+            self.drivers.depth_mode = graphics.depth_mode;
+            self.lambert_pipeline = lambert::LambertPipeline::create(&mut self.drivers, cfg);
+            self.pbr_pipeline = pbr::PbrPipeline::create(&mut self.drivers, cfg);
+            // drop the render targets of the old sample count:
+            self.transient_textures.clear();
+        }
+
+        self.debug_ui.set_config(cfg);
+    }
+}
+
+impl GraphicsSystem {
+    /// Recreates the pipelines from the edited shader sources, only if all of them compile.
+    /// The environment is baked once at startup and keeps its maps.
+    fn reload_shaders(&mut self, cfg: &mut CoreConfig) {
+        info!("Shader sources changed, reloading...");
+        let errors = pipelines::check_all(
+            &mut self.drivers.shader_compiler,
+            self.drivers.push_constant_emulation.is_some(),
+        );
+        if !errors.is_empty() {
+            for shader_error in &errors {
+                error!("{}", shader_error);
+            }
+            self.debug_ui.set_shader_errors(errors);
+            return;
+        }
+        cfg.graphics_config.msaa_mode = self.drivers.msaa_samples;
+        cfg.graphics_config.depth_mode = self.drivers.depth_mode;
+        self.lambert_pipeline = lambert::LambertPipeline::create(&mut self.drivers, cfg);
+        self.pbr_pipeline = pbr::PbrPipeline::create(&mut self.drivers, cfg);
+        self.shadow_pipeline = shadow::ShadowPipeline::create(&mut self.drivers, cfg);
+        self.post_processing = PostProcessing::new(&mut self.drivers, cfg);
+        // the replaced pipelines and shader modules are only left in the cache:
+        self.drivers.pipeline_cache.purge_unused();
+        self.debug_ui.set_shader_errors(Vec::new());
+        info!("Shaders reloaded");
+    }
+
+    /// Creates the pipeline variants of the alpha tested materials on their first use.
+    fn request_pipeline_variants(&mut self, world: &World) {
+        let lambert_keywords = alpha_test_keywords::<lambert::LambertPipeline>();
+        let pbr_keywords = alpha_test_keywords::<pbr::PbrPipeline>();
+        let shadow_keywords = alpha_test_keywords::<shadow::ShadowPipeline>();
+        for renderer in <&MeshRenderer>::query().iter(world) {
+            if renderer.material.render_queue() != RenderQueue::AlphaTest {
+                continue;
+            }
+            if renderer.cast_shadows {
+                self.shadow_pipeline
+                    .variants
+                    .request(&mut self.drivers, shadow_keywords);
+            }
+            if is_pbr(&renderer.material) {
+                self.pbr_pipeline
+                    .variants
+                    .request(&mut self.drivers, pbr_keywords);
+            } else {
+                self.lambert_pipeline
+                    .variants
+                    .request(&mut self.drivers, lambert_keywords);
+            }
+        }
+    }
+}
+
+/// FXAA and MSAA are exclusive, FXAA wins.
+fn resolve_anti_aliasing(cfg: &mut CoreConfig) {
+    let post_config = &cfg.graphics_config.post_processing;
+    if post_config.enabled && post_config.fxaa && cfg.graphics_config.msaa_mode != MsaaMode::Off {
+        warn!("FXAA is enabled, disabling MSAA");
+        cfg.graphics_config.msaa_mode = MsaaMode::Off;
+    }
+}
+
+struct FrameContext<'a> {
+    world: &'a World,
+    lambert_pipeline: &'a lambert::LambertPipeline,
+    pbr_pipeline: &'a pbr::PbrPipeline,
+    shadow_pipeline: &'a shadow::ShadowPipeline,
+    lighting: &'a Lighting,
+    environment: &'a Environment,
+    post_processing: &'a PostProcessing,
+    view_proj_matrix: Matrix4<f32>,
+    camera_position: Vector3<f32>,
+    alpha_to_coverage: bool,
+    imgui_renderer: &'a mut ImGuiRenderer,
+    draw_data: &'a DrawData,
+    device: &'a Device,
+    queue: &'a Queue,
+}
+
+impl PostProcessingContext for FrameContext<'_> {
+    #[inline]
+    fn post_processing(&self) -> &PostProcessing {
+        self.post_processing
+    }
+}
+
+#[inline]
+fn is_pbr(material: &Material) -> bool {
+    matches!(material.properties(), MaterialProperties::Pbr { .. })
+}
+
+#[inline]
+fn alpha_test_keywords<T: Pipeline>() -> KeywordSet {
+    KeywordSet::of::<T>(&[ALPHA_TEST_KEYWORD])
+}
+
+/// Alpha tested variants are requested before recording, a variant without any
+/// material is missing and falls back to the opaque pipeline, which then draws nothing.
+fn set_surface_pipeline<'p>(
+    pass: &mut Pass<'p>,
+    ctx: &FrameContext<'p>,
+    pbr: bool,
+    alpha_test: bool,
+    transparent: bool,
+) {
+    if pbr {
+        let pipeline = ctx.pbr_pipeline;
+        pass.set_shader_pipeline(if transparent {
+            &pipeline.transparent_pipeline
+        } else if alpha_test {
+            pipeline
+                .variants
+                .get(alpha_test_keywords::<pbr::PbrPipeline>())
+                .unwrap_or(&pipeline.shader_pipeline)
+        } else {
+            &pipeline.shader_pipeline
+        });
+        pass.set_bind_group(2, &ctx.environment.bind_group);
+    } else {
+        let pipeline = ctx.lambert_pipeline;
+        pass.set_shader_pipeline(if transparent {
+            &pipeline.transparent_pipeline
+        } else if alpha_test {
+            pipeline
+                .variants
+                .get(alpha_test_keywords::<lambert::LambertPipeline>())
+                .unwrap_or(&pipeline.shader_pipeline)
+        } else {
+            &pipeline.shader_pipeline
+        });
+    }
+    pass.set_bind_group(1, &ctx.lighting.bind_group);
+}
+
+/// Draws a mesh into a shadow layer with the currently bound shadow pipeline.
+fn draw_shadow_caster<'p>(
+    pass: &mut Pass<'p>,
+    transform: &Transform,
+    renderer: &'p MeshRenderer,
+    light_view_proj_matrix: Matrix4<f32>,
+) {
+    let push_constant_data = shadow::PushConstantData {
+        world_matrix: transform.calculate_matrix(),
+        light_view_proj_matrix,
+    };
+    pass.set_push_constans(
+        ShaderStage::VERTEX,
+        0,
+        bytemuck::bytes_of(&push_constant_data),
+    );
+    let alpha_factor = match renderer.material.properties() {
+        MaterialProperties::Pbr { factors, .. } => factors.base_color.w,
+        MaterialProperties::Lambert { .. } => 1.0,
+    };
+    let material_push_constant_data = shadow::MaterialPushConstantData {
+        params: Vector4::new(
+            renderer.material.alpha_mode().cutoff(),
+            alpha_factor,
+            0.0,
+            0.0,
+        ),
+    };
+    pass.set_push_constans(
+        ShaderStage::FRAGMENT,
+        shadow::MATERIAL_PUSH_CONSTANT_OFFSET,
+        bytemuck::bytes_of(&material_push_constant_data),
+    );
+    pass.set_bind_group(0, renderer.material.shadow_bind_group());
+    pass.draw_indexed(&renderer.mesh);
+}
+
+/// Draws a mesh with the currently bound surface pipeline matching its material.
+fn draw_surface<'p>(
+    pass: &mut Pass<'p>,
+    ctx: &FrameContext<'p>,
+    transform: &Transform,
+    renderer: &'p MeshRenderer,
+) {
+    let alpha_mode = renderer.material.alpha_mode();
+    let cutoff = alpha_mode.cutoff();
+    let to_coverage = ctx.alpha_to_coverage as u8 as f32;
+    let blended = (alpha_mode == AlphaMode::Blend) as u8 as f32;
+    let receive_shadows = renderer.receive_shadows as u8 as f32;
+    let world_matrix = transform.calculate_matrix();
+    let view_proj_matrix = ctx.view_proj_matrix;
+
+    match renderer.material.properties() {
+        MaterialProperties::Lambert { .. } => {
+            let push_constant_data = lambert::PushConstantData {
+                world_matrix,
+                view_proj_matrix,
+            };
+            pass.set_push_constans(
+                ShaderStage::VERTEX,
+                0,
+                bytemuck::bytes_of(&push_constant_data),
+            );
+            let material_push_constant_data = lambert::MaterialPushConstantData {
+                params: Vector4::new(receive_shadows, cutoff, to_coverage, blended),
+            };
+            pass.set_push_constans(
+                ShaderStage::FRAGMENT,
+                lambert::MATERIAL_PUSH_CONSTANT_OFFSET,
+                bytemuck::bytes_of(&material_push_constant_data),
+            );
+        }
+        MaterialProperties::Pbr { factors, .. } => {
+            let push_constant_data = pbr::PushConstantData {
+                world_matrix,
+                view_proj_matrix,
+            };
+            pass.set_push_constans(
+                ShaderStage::VERTEX,
+                0,
+                bytemuck::bytes_of(&push_constant_data),
+            );
+            let material_push_constant_data = pbr::MaterialPushConstantData {
+                base_color: factors.base_color,
+                params: Vector4::new(
+                    factors.metallic,
+                    factors.roughness,
+                    factors.occlusion_strength,
+                    factors.normal_scale,
+                ),
+                emissive: factors.emissive.extend(receive_shadows),
+                alpha: Vector4::new(cutoff, to_coverage, blended, 0.0),
+            };
+            pass.set_push_constans(
+                ShaderStage::FRAGMENT,
+                pbr::MATERIAL_PUSH_CONSTANT_OFFSET,
+                bytemuck::bytes_of(&material_push_constant_data),
+            );
+        }
+    }
+    pass.set_bind_group(0, renderer.material.bind_group());
+    pass.draw_indexed(&renderer.mesh);
 }
@@ -18,6 +18,9 @@ pub trait SubSystem {
     fn tick(&mut self, _scenery: &mut Scenery) -> bool {
         true
     }
+    /// Applies a changed config at runtime.
+    /// Values which could not be applied as requested are written back into the config.
+    fn reconfigure(&mut self, _cfg: &mut CoreConfig, _scenery: &mut Scenery) {}
 }
 
 pub struct SystemSupervisor {
@@ -53,6 +56,13 @@ impl SystemSupervisor {
     pub fn tick_all(&mut self, scenery: &mut Scenery) -> bool {
         self.platform.tick(scenery) && self.memory.tick(scenery) && self.graphics.tick(scenery)
     }
+
+    /// The platform system goes first, the graphics system needs the new window size.
+    pub fn reconfigure_all(&mut self, cfg: &mut CoreConfig, scenery: &mut Scenery) {
+        self.platform.reconfigure(cfg, scenery);
+        self.memory.reconfigure(cfg, scenery);
+        self.graphics.reconfigure(cfg, scenery);
+    }
 }
 
 pub mod prelude {
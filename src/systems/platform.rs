@@ -1,10 +1,30 @@
 use super::prelude::*;
 use crate::core::platform::prelude::*;
-use crate::scenery_resources::{CursorPos, KeyInputStateCollection, MouseInputStateCollection};
+use crate::core::platform::replay::{InputFrame, InputPlayer, InputRecorder};
+use crate::editor::scene_file::SceneFile;
+use crate::resources::ResourceManager;
+use crate::scenery_resources::{
+    CursorControl, CursorPos, DroppedFiles, FrameTime, GamepadState, InputCapture, InputMap,
+    KeyInputStateCollection, MouseInputStateCollection, TextInput, WindowEventQueue, WindowState,
+};
+use log::{info, warn};
+use std::time::Duration;
+
+/// Frame rate cap of the power save mode.
+const POWER_SAVE_FPS_LIMIT: u16 = 30;
+
+/// Polling rate of an unfocused or minimized window in power save mode.
+const POWER_SAVE_INACTIVE_FPS_LIMIT: u16 = 10;
 
 pub struct PlatformSystem {
     pub win_data: WindowData,
     pub sys_info: SystemInfo,
+    pub display_config: DisplayConfig,
+    pub input_config: InputConfig,
+    pub input_recorder: Option<InputRecorder>,
+    pub input_player: Option<InputPlayer>,
+    pub power_save: bool,
+    pub fps_limit: Option<u16>,
 }
 
 impl SubSystem for PlatformSystem {
@@ -14,7 +34,22 @@ impl SubSystem for PlatformSystem {
         let sys_info = get_and_print_system_info();
         let win_data = WindowData::create_window(cfg);
 
-        Self { win_data, sys_info }
+        let (input_recorder, input_player) = match &cfg.input_config.recording {
+            InputRecordingMode::Off => (None, None),
+            InputRecordingMode::Record { path } => (Some(InputRecorder::new(path.clone())), None),
+            InputRecordingMode::Replay { path, .. } => (None, Some(InputPlayer::load(path))),
+        };
+
+        Self {
+            win_data,
+            sys_info,
+            display_config: cfg.display_config.clone(),
+            input_config: cfg.input_config.clone(),
+            input_recorder,
+            input_player,
+            power_save: cfg.application_config.power_safe_mode,
+            fps_limit: fps_limit(cfg),
+        }
     }
 
     fn prepare(&mut self, scenery: &mut Scenery) {
@@ -23,17 +58,68 @@ impl SubSystem for PlatformSystem {
             .resources
             .insert(MouseInputStateCollection::default());
         scenery.resources.insert(CursorPos(0.0, 0.0));
-        self.win_data.window.focus();
-        self.win_data.window.show();
+        scenery.resources.insert(WindowEventQueue::default());
+        scenery.resources.insert(FrameTime::default());
+        scenery
+            .resources
+            .insert(InputMap::from_config(&self.input_config));
+        scenery.resources.insert(InputCapture::default());
+        scenery.resources.insert(GamepadState::default());
+        scenery.resources.insert(TextInput::default());
+        scenery.resources.insert(DroppedFiles::default());
+        scenery.resources.insert(CursorControl::default());
+        scenery.resources.insert(WindowState {
+            focused: true,
+            iconified: false,
+            cursor_inside: false,
+            content_scale: self.win_data.window.get_content_scale(),
+            size: self.win_data.window.get_size(),
+            framebuffer_size: self.win_data.window.get_framebuffer_size(),
+        });
+        self.win_data.context.set_time(0.0);
+
+        // replays run with a hidden window:
+        if self.input_player.is_none() {
+            self.win_data.window.focus();
+            self.win_data.window.show();
+        }
     }
 
     fn tick(&mut self, scenery: &mut Scenery) -> bool {
+        if self.input_player.is_some() {
+            return self.replay(scenery);
+        }
+
+        self.limit_frame_rate(scenery);
+        {
+            let now = self.win_data.context.get_time();
+            let mut frame_time = scenery.resources.get_mut::<FrameTime>().unwrap();
+            frame_time.delta = (now - frame_time.elapsed) as f32;
+            frame_time.elapsed = now;
+            frame_time.frame += 1;
+        }
+
+        // apply the cursor mode requested last frame:
+        let cursor_mode = scenery.resources.get::<CursorControl>().unwrap().mode;
+        if self.win_data.window.get_cursor_mode() != cursor_mode {
+            self.win_data.window.set_cursor_mode(cursor_mode);
+        }
+
         self.win_data.context.poll_events();
+        let mut event_queue = scenery.resources.get_mut::<WindowEventQueue>().unwrap();
+        let mut window_state = scenery.resources.get_mut::<WindowState>().unwrap();
+        let mut text_input = scenery.resources.get_mut::<TextInput>().unwrap();
+        let mut dropped_files = scenery.resources.get_mut::<DroppedFiles>().unwrap();
+        event_queue.0.clear();
+        text_input.0.clear();
+        dropped_files.0.clear();
+        let mut scroll = (0.0, 0.0);
         for (_, event) in flush_messages(&self.win_data.events) {
             use WindowEvent::*;
 
+            event_queue.0.push(event.clone());
             match event {
-                Key(key, _, action, _) => {
+                Key(key, _, action, _) if key != glfw::Key::Unknown => {
                     let mut key_queue = scenery
                         .resources
                         .get_mut::<KeyInputStateCollection>()
@@ -63,9 +149,228 @@ impl SubSystem for PlatformSystem {
                     cursor_pos.0 = x as f32;
                     cursor_pos.1 = y as f32;
                 }
+                Scroll(x, y) => {
+                    scroll.0 += x as f32;
+                    scroll.1 += y as f32;
+                }
+                Char(c) => text_input.0.push(c),
+                FileDrop(paths) => dropped_files.0.extend(paths),
+                Focus(focused) => {
+                    window_state.focused = focused;
+                    // releases are not delivered while unfocused, so drop held input:
+                    if !focused {
+                        *scenery
+                            .resources
+                            .get_mut::<KeyInputStateCollection>()
+                            .unwrap() = KeyInputStateCollection::default();
+                        *scenery
+                            .resources
+                            .get_mut::<MouseInputStateCollection>()
+                            .unwrap() = MouseInputStateCollection::default();
+                    }
+                }
+                Iconify(iconified) => window_state.iconified = iconified,
+                CursorEnter(inside) => window_state.cursor_inside = inside,
+                ContentScale(x, y) => window_state.content_scale = (x, y),
+                Size(width, height) => window_state.size = (width, height),
+                FramebufferSize(width, height) => window_state.framebuffer_size = (width, height),
                 _ => (),
             }
         }
+        drop(event_queue);
+        drop(window_state);
+        drop(text_input);
+        drop(dropped_files);
+
+        scenery.resources.get_mut::<GamepadState>().unwrap().poll(
+            &self.win_data.context,
+            self.input_config.gamepad_deadzone,
+            self.input_config.gamepad_trigger_deadzone,
+        );
+
+        // input captured by the gui is hidden from the input map, except for the uncaptured actions:
+        let capture = *scenery.resources.get::<InputCapture>().unwrap();
+        let no_buttons = MouseInputStateCollection::default();
+        let key_queue = scenery.resources.get::<KeyInputStateCollection>().unwrap();
+        let mouse_queue = scenery
+            .resources
+            .get::<MouseInputStateCollection>()
+            .unwrap();
+        let gamepads = scenery.resources.get::<GamepadState>().unwrap();
+        let cursor_pos = *scenery.resources.get::<CursorPos>().unwrap();
+        let uncaptured_keys = if capture.keyboard {
+            let input_map = scenery.resources.get::<InputMap>().unwrap();
+            input_map.uncaptured_keys(&key_queue)
+        } else {
+            KeyInputStateCollection::default()
+        };
+        let keys = if capture.keyboard {
+            &uncaptured_keys
+        } else {
+            &*key_queue
+        };
+        let buttons = if capture.mouse {
+            &no_buttons
+        } else {
+            &*mouse_queue
+        };
+        let scroll = if capture.mouse { (0.0, 0.0) } else { scroll };
+        if let Some(recorder) = &mut self.input_recorder {
+            let delta = scenery.resources.get::<FrameTime>().unwrap().delta;
+            recorder.record(InputFrame::capture(
+                delta, keys, buttons, &gamepads, cursor_pos, scroll,
+            ));
+        }
+        scenery
+            .resources
+            .get_mut::<InputMap>()
+            .unwrap()
+            .update(keys, buttons, &gamepads, cursor_pos, scroll);
+
+        !self.win_data.window.should_close()
+    }
+
+    fn reconfigure(&mut self, cfg: &mut CoreConfig, scenery: &mut Scenery) {
+        let display = &cfg.display_config;
+        if display.window_mode != self.display_config.window_mode
+            || display.resolution != self.display_config.resolution
+        {
+            self.win_data.apply_display_config(&mut cfg.display_config);
+            let mut window_state = scenery.resources.get_mut::<WindowState>().unwrap();
+            window_state.size = self.win_data.window.get_size();
+            window_state.framebuffer_size = self.win_data.window.get_framebuffer_size();
+        }
+        self.display_config = cfg.display_config.clone();
+        self.power_save = cfg.application_config.power_safe_mode;
+        self.fps_limit = fps_limit(cfg);
+
+        // rebuilding the input map resets the action states, so only do it for new bindings:
+        let bindings = |config: &InputConfig| {
+            serde_yaml::to_string(&(&config.actions, &config.axes)).unwrap_or_default()
+        };
+        if bindings(&cfg.input_config) != bindings(&self.input_config) {
+            info!("Input bindings changed, rebuilding input map...");
+            *scenery.resources.get_mut::<InputMap>().unwrap() =
+                InputMap::from_config(&cfg.input_config);
+        }
+        // a running recording or replay is kept:
+        let recording = self.input_config.recording.clone();
+        self.input_config = cfg.input_config.clone();
+        self.input_config.recording = recording;
+    }
+}
+
+impl PlatformSystem {
+    /// Sleeps off the rest of the frame time allowed by the fps limit.
+    fn limit_frame_rate(&self, scenery: &Scenery) {
+        let inactive = scenery
+            .resources
+            .get::<WindowState>()
+            .unwrap()
+            .is_inactive();
+        let limit = if self.power_save && inactive {
+            Some(POWER_SAVE_INACTIVE_FPS_LIMIT)
+        } else {
+            self.fps_limit
+        };
+        let limit = match limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        let last_frame = scenery.resources.get::<FrameTime>().unwrap().elapsed;
+        let remaining = 1.0 / limit as f64 - (self.win_data.context.get_time() - last_frame);
+        if remaining > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(remaining));
+        }
+    }
+
+    /// Feeds the next recorded frame in place of glfw input and frame timing.
+    fn replay(&mut self, scenery: &mut Scenery) -> bool {
+        self.win_data.context.poll_events();
+        // the recording replaces all window input:
+        for _ in flush_messages(&self.win_data.events) {}
+
+        let frame = match self.input_player.as_mut().unwrap().next_frame() {
+            Some(frame) => frame.clone(),
+            None => {
+                self.finish_replay(scenery);
+                return false;
+            }
+        };
+
+        {
+            let mut frame_time = scenery.resources.get_mut::<FrameTime>().unwrap();
+            frame_time.delta = frame.delta;
+            frame_time.elapsed += frame.delta as f64;
+            frame_time.frame += 1;
+        }
+        scenery
+            .resources
+            .get_mut::<WindowEventQueue>()
+            .unwrap()
+            .0
+            .clear();
+        scenery.resources.get_mut::<TextInput>().unwrap().0.clear();
+        scenery
+            .resources
+            .get_mut::<DroppedFiles>()
+            .unwrap()
+            .0
+            .clear();
+
+        let keys = KeyInputStateCollection::from_pressed_indices(&frame.keys);
+        let buttons = MouseInputStateCollection::from_pressed_indices(&frame.mouse_buttons);
+        let cursor_pos = CursorPos(frame.cursor_pos.0, frame.cursor_pos.1);
+        let mut gamepads = scenery.resources.get_mut::<GamepadState>().unwrap();
+        gamepads.replace(&frame.gamepads);
+        scenery.resources.get_mut::<InputMap>().unwrap().update(
+            &keys,
+            &buttons,
+            &gamepads,
+            cursor_pos,
+            frame.scroll,
+        );
+        drop(gamepads);
+
+        *scenery
+            .resources
+            .get_mut::<KeyInputStateCollection>()
+            .unwrap() = keys;
+        *scenery
+            .resources
+            .get_mut::<MouseInputStateCollection>()
+            .unwrap() = buttons;
+        *scenery.resources.get_mut::<CursorPos>().unwrap() = cursor_pos;
+
         !self.win_data.window.should_close()
     }
+
+    fn finish_replay(&self, scenery: &Scenery) {
+        info!(
+            "Input replay finished after {} frames!",
+            scenery.resources.get::<FrameTime>().unwrap().frame
+        );
+        if let InputRecordingMode::Replay {
+            world_state_path: Some(path),
+            ..
+        } = &self.input_config.recording
+        {
+            let resource_manager = scenery.resources.get::<ResourceManager>().unwrap();
+            if let Err(error) = SceneFile::capture(&scenery.world, &resource_manager).save(path) {
+                warn!("Failed to save world state {:?}: {}", path, error);
+            }
+        }
+    }
+}
+
+/// The configured fps limit, capped in power save mode.
+fn fps_limit(cfg: &CoreConfig) -> Option<u16> {
+    let limit = cfg.display_config.fps_limit;
+    if cfg.application_config.power_safe_mode {
+        Some(limit.map_or(POWER_SAVE_FPS_LIMIT, |limit| {
+            limit.min(POWER_SAVE_FPS_LIMIT)
+        }))
+    } else {
+        limit
+    }
 }